@@ -36,6 +36,16 @@ impl DummyAdapter {
     }
 }
 
+/// The deterministic signature of the `DummyAdapter`,
+/// which allows a state root signed by one dummy adapter to be verified by another.
+fn dummy_signature(state_root: &str, signer: &ValidatorId) -> String {
+    format!(
+        "Dummy adapter signature for {} by {}",
+        state_root,
+        signer.to_checksum()
+    )
+}
+
 #[derive(Debug)]
 pub struct Error {}
 impl fmt::Display for Error {
@@ -59,28 +69,16 @@ impl Adapter for DummyAdapter {
     }
 
     fn sign(&self, state_root: &str) -> AdapterResult<String, Self::AdapterError> {
-        let signature = format!(
-            "Dummy adapter signature for {} by {}",
-            state_root,
-            self.whoami().to_checksum()
-        );
-        Ok(signature)
+        Ok(dummy_signature(state_root, self.whoami()))
     }
 
     fn verify(
         &self,
         signer: &ValidatorId,
-        _state_root: &str,
+        state_root: &str,
         signature: &str,
     ) -> AdapterResult<bool, Self::AdapterError> {
-        // select the `identity` and compare it to the signer
-        // for empty string this will return array with 1 element - an empty string `[""]`
-        let is_same = match signature.rsplit(' ').take(1).next() {
-            Some(from) => from == signer.to_checksum(),
-            None => false,
-        };
-
-        Ok(is_same)
+        Ok(signature == dummy_signature(state_root, signer))
     }
 
     async fn validate_channel<'a>(
@@ -130,3 +128,48 @@ impl Adapter for DummyAdapter {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use primitives::config::configuration;
+    use primitives::util::tests::prep_db::{AUTH, IDS};
+
+    fn setup_dummy_adapter(identity: &str) -> DummyAdapter {
+        let config = configuration("development", None).expect("Dev config should be available");
+        let options = DummyAdapterOptions {
+            dummy_identity: IDS[identity],
+            dummy_auth: IDS.clone(),
+            dummy_auth_tokens: AUTH.clone(),
+        };
+
+        DummyAdapter::init(options, &config)
+    }
+
+    #[test]
+    fn signature_of_one_adapter_is_verified_by_another() {
+        let leader = setup_dummy_adapter("leader");
+        let follower = setup_dummy_adapter("follower");
+        let state_root = "1fc6dbc1b7ebef1d5fbd9ed8d2bc5e1a66d35ee15d1ae6aa5d1bb0ff9d8f6a4d";
+        let tampered_state_root =
+            "2fc6dbc1b7ebef1d5fbd9ed8d2bc5e1a66d35ee15d1ae6aa5d1bb0ff9d8f6a4d";
+
+        let signature = leader.sign(state_root).expect("Should sign");
+
+        assert!(follower
+            .verify(&IDS["leader"], state_root, &signature)
+            .expect("Should verify"));
+        assert!(
+            !follower
+                .verify(&IDS["leader"], tampered_state_root, &signature)
+                .expect("Should verify"),
+            "A tampered state root should not be verified"
+        );
+        assert!(
+            !follower
+                .verify(&IDS["follower"], state_root, &signature)
+                .expect("Should verify"),
+            "A signature of another signer should not be verified"
+        );
+    }
+}
@@ -17,8 +17,16 @@ use primitives::{
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use slog::{debug, Logger};
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fs;
+use std::future::Future;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, RwLock,
+};
+use std::time::{Duration, Instant};
 use tiny_keccak::Keccak;
 use web3::{
     contract::tokens::Tokenizable,
@@ -39,20 +47,55 @@ lazy_static! {
 #[derive(Debug, Clone)]
 pub struct EthereumAdapter {
     address: ValidatorId,
-    keystore_json: Value,
+    /// `None` for a read-only adapter, which has no wallet to unlock
+    keystore_json: Option<Value>,
     keystore_pwd: Password,
     config: Config,
     wallet: Option<SafeAccount>,
-    web3: Web3<Http>,
+    /// The `ethereum_network` followed by the `ethereum_network_fallbacks`
+    providers: Vec<Provider>,
+    /// The index of the provider which is queried first
+    current_provider: Arc<AtomicUsize>,
+    /// The cached Active AdExCore `states` of the channels, see `Config::channel_state_cache_ttl`.
+    /// The expired states are evicted on every insert,
+    /// since the Sentry validates any channel submitted to it.
+    channel_states: Arc<RwLock<HashMap<ChannelId, (Instant, U256)>>>,
     relayer: RelayerClient,
+    logger: Logger,
 }
 
 // Enables EthereumAdapter to be able to
 // check if a channel is valid
 impl ChannelValidator for EthereumAdapter {}
 
+/// A web3 provider of the AdExCore `Contract`,
+/// which is created once, instead of parsing the ABI on each call.
+#[derive(Debug, Clone)]
+struct Provider {
+    web3: Web3<Http>,
+    adex_core: Contract<Http>,
+}
+
+impl Provider {
+    fn new(network: &str, config: &Config) -> Result<Self, Error> {
+        let web3 = Web3::new(Http::new(network).map_err(Error::Web3)?);
+        let adex_core = Contract::from_json(
+            web3.eth(),
+            config.ethereum_core_address.into(),
+            &ADEXCORE_ABI,
+        )
+        .map_err(Error::ContractInitialization)?;
+
+        Ok(Self { web3, adex_core })
+    }
+}
+
 impl EthereumAdapter {
-    pub fn init(opts: KeystoreOptions, config: &Config) -> AdapterResult<EthereumAdapter, Error> {
+    pub fn init(
+        opts: KeystoreOptions,
+        config: &Config,
+        logger: Logger,
+    ) -> AdapterResult<EthereumAdapter, Error> {
         let keystore_contents =
             fs::read_to_string(&opts.keystore_file).map_err(KeystoreError::ReadingFile)?;
 
@@ -66,22 +109,154 @@ impl EthereumAdapter {
 
         let address = ValidatorId::try_from(&address).map_err(KeystoreError::AddressInvalid)?;
 
-        let transport =
-            web3::transports::Http::new(&config.ethereum_network).map_err(Error::Web3)?;
-        let web3 = web3::Web3::new(transport);
+        Self::new(
+            address,
+            Some(keystore_json),
+            opts.keystore_pwd,
+            config,
+            logger,
+        )
+    }
+
+    /// Creates an adapter without a wallet, which can only be used for validation,
+    /// e.g. `validate_channel`, `verify` and `session_from_token`.
+    /// `unlock`, `sign` and `get_auth` return an `Error::ReadOnly`.
+    pub fn new_read_only(
+        address: ValidatorId,
+        config: &Config,
+        logger: Logger,
+    ) -> AdapterResult<EthereumAdapter, Error> {
+        Self::new(address, None, String::new(), config, logger)
+    }
+
+    /// Sets up the providers of the Ethereum network (with its fallbacks) and the Relayer client,
+    /// which are used both by the unlockable and by the read-only adapter.
+    fn new(
+        address: ValidatorId,
+        keystore_json: Option<Value>,
+        keystore_pwd: String,
+        config: &Config,
+        logger: Logger,
+    ) -> AdapterResult<EthereumAdapter, Error> {
+        let providers = std::iter::once(&config.ethereum_network)
+            .chain(config.ethereum_network_fallbacks.iter())
+            .map(|network| Provider::new(network, config))
+            .collect::<Result<Vec<_>, _>>()?;
         let relayer =
             RelayerClient::new(&config.ethereum_adapter_relayer).map_err(Error::RelayerClient)?;
 
         Ok(Self {
             address,
             keystore_json,
-            keystore_pwd: opts.keystore_pwd.into(),
+            keystore_pwd: keystore_pwd.into(),
             wallet: None,
             config: config.to_owned(),
-            web3,
+            providers,
+            current_provider: Arc::new(AtomicUsize::new(0)),
+            channel_states: Default::default(),
             relayer,
+            logger,
         })
     }
+
+    fn unlocked_wallet(&self) -> AdapterResult<&SafeAccount, Error> {
+        match (&self.wallet, &self.keystore_json) {
+            (Some(wallet), _) => Ok(wallet),
+            (None, None) => Err(Error::ReadOnly.into()),
+            (None, Some(_)) => Err(AdapterError::LockedWallet),
+        }
+    }
+
+    /// Calls `query` starting with the current provider and fails over to the next one on error.
+    /// The first provider that succeeds becomes the current one.
+    async fn with_failover<T, F, Fut>(&self, query: F) -> Result<T, Error>
+    where
+        F: Fn(Provider) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let providers_count = self.providers.len();
+        let current = self.current_provider.load(Ordering::SeqCst);
+        let mut offset = 0;
+
+        loop {
+            let index = (current + offset) % providers_count;
+
+            match query(self.providers[index].clone()).await {
+                Ok(result) => {
+                    self.current_provider.store(index, Ordering::SeqCst);
+                    return Ok(result);
+                }
+                // all of the providers have failed
+                Err(err) if offset + 1 >= providers_count => return Err(err),
+                Err(_) => offset += 1,
+            }
+        }
+    }
+
+    /// Checks if the Ethereum node is reachable and returns its latest block number.
+    pub async fn check_node(&self) -> AdapterResult<u64, Error> {
+        let block_number = self
+            .with_failover(|provider| async move {
+                provider
+                    .web3
+                    .eth()
+                    .block_number()
+                    .await
+                    .map_err(Error::Web3)
+            })
+            .await?;
+
+        Ok(block_number.as_u64())
+    }
+
+    /// Queries the AdExCore `states` of the channel,
+    /// unless it has been queried in the last `channel_state_cache_ttl`.
+    ///
+    /// Only the Active state is cached, so a channel which is being opened
+    /// can be validated as soon as it becomes Active on-chain.
+    async fn channel_state(&self, channel_id: &ChannelId) -> Result<U256, Error> {
+        let channel_id = *channel_id;
+        let ttl = Duration::from_millis(self.config.channel_state_cache_ttl.into());
+
+        let cached = self
+            .channel_states
+            .read()
+            .expect("The channel states lock should not be poisoned")
+            .get(&channel_id)
+            .filter(|(queried_at, _)| queried_at.elapsed() < ttl)
+            .map(|(_, state)| *state);
+
+        if let Some(state) = cached {
+            return Ok(state);
+        }
+
+        let state = self
+            .with_failover(|provider| async move {
+                provider
+                    .adex_core
+                    .query(
+                        "states",
+                        H256(*channel_id).into_token(),
+                        None,
+                        Options::default(),
+                        None,
+                    )
+                    .await
+                    .map_err(Error::ContractQuerying)
+            })
+            .await?;
+
+        let mut channel_states = self
+            .channel_states
+            .write()
+            .expect("The channel states lock should not be poisoned");
+        channel_states.retain(|_, (queried_at, _)| queried_at.elapsed() < ttl);
+        if state == *CHANNEL_STATE_ACTIVE {
+            channel_states.insert(channel_id, (Instant::now(), state));
+        }
+
+        Ok(state)
+    }
 }
 
 #[async_trait]
@@ -89,9 +264,9 @@ impl Adapter for EthereumAdapter {
     type AdapterError = Error;
 
     fn unlock(&mut self) -> AdapterResult<(), Self::AdapterError> {
+        let keystore_json = self.keystore_json.clone().ok_or(Error::ReadOnly)?;
         let account = SafeAccount::from_file(
-            serde_json::from_value(self.keystore_json.clone())
-                .map_err(KeystoreError::Deserialization)?,
+            serde_json::from_value(keystore_json).map_err(KeystoreError::Deserialization)?,
             None,
             &Some(self.keystore_pwd.clone()),
         )
@@ -107,18 +282,23 @@ impl Adapter for EthereumAdapter {
     }
 
     fn sign(&self, state_root: &str) -> AdapterResult<String, Self::AdapterError> {
-        if let Some(wallet) = &self.wallet {
-            let state_root = hex::decode(state_root).map_err(VerifyError::StateRootDecoding)?;
-            let message = Message::from(hash_message(&state_root));
-            let wallet_sign = wallet
-                .sign(&self.keystore_pwd, &message)
-                .map_err(EwtSigningError::SigningMessage)?;
-            let signature: Signature = wallet_sign.into_electrum().into();
-
-            Ok(format!("0x{}", signature))
-        } else {
-            Err(AdapterError::LockedWallet)
-        }
+        let wallet = self.unlocked_wallet()?;
+
+        let state_root = hex::decode(state_root).map_err(VerifyError::StateRootDecoding)?;
+        let message = Message::from(hash_message(&state_root));
+        let wallet_sign = wallet
+            .sign(&self.keystore_pwd, &message)
+            .map_err(EwtSigningError::SigningMessage)?;
+        let signature: Signature = wallet_sign.into_electrum().into();
+
+        debug!(
+            self.logger,
+            "Signed state root";
+            "state_root" => hex::encode(&state_root),
+            "signer" => %self.whoami()
+        );
+
+        Ok(format!("0x{}", signature))
     }
 
     /// `state_root` is hex string which **should not** be `0x` prefixed
@@ -141,6 +321,14 @@ impl Adapter for EthereumAdapter {
         let verify_address = verify_address(&address, &signature, &message)
             .map_err(VerifyError::PublicKeyRecovery)?;
 
+        debug!(
+            self.logger,
+            "Verified state root signature";
+            "state_root" => hex::encode(&state_root),
+            "signer" => %signer,
+            "is_valid" => verify_address
+        );
+
         Ok(verify_address)
     }
 
@@ -167,23 +355,15 @@ impl Adapter for EthereumAdapter {
             ));
         }
 
-        let contract = Contract::from_json(
-            self.web3.eth(),
-            self.config.ethereum_core_address.into(),
-            &ADEXCORE_ABI,
-        )
-        .map_err(Error::ContractInitialization)?;
+        let channel_status = self.channel_state(&channel.id).await?;
 
-        let channel_status: U256 = contract
-            .query(
-                "states",
-                H256(*channel.id).into_token(),
-                None,
-                Options::default(),
-                None,
-            )
-            .await
-            .map_err(Error::ContractQuerying)?;
+        debug!(
+            self.logger,
+            "Validated channel";
+            "channel" => %channel.id,
+            "validator" => %self.whoami(),
+            "is_active" => channel_status == *CHANNEL_STATE_ACTIVE
+        );
 
         if channel_status != *CHANNEL_STATE_ACTIVE {
             Err(AdapterError::Adapter(
@@ -230,6 +410,11 @@ impl Adapter for EthereumAdapter {
         }
 
         let sess = match &verified.payload.identity {
+            Some(_) if !self.config.use_relayer => {
+                return Err(AdapterError::Authorization(
+                    "identity tokens are not allowed when the relayer is disabled".to_string(),
+                ));
+            }
             Some(identity) => {
                 if self
                     .relayer
@@ -252,11 +437,20 @@ impl Adapter for EthereumAdapter {
             },
         };
 
+        // the token itself is a credential and is never logged
+        debug!(
+            self.logger,
+            "Created a session from a token";
+            "uid" => %sess.uid,
+            "era" => sess.era,
+            "signer" => %verified.from
+        );
+
         Ok(sess)
     }
 
     fn get_auth(&self, validator: &ValidatorId) -> AdapterResult<String, Self::AdapterError> {
-        let wallet = self.wallet.as_ref().ok_or(AdapterError::LockedWallet)?;
+        let wallet = self.unlocked_wallet()?;
 
         let era = Utc::now().timestamp_millis() as f64 / 60000.0;
         let payload = Payload {
@@ -340,7 +534,7 @@ pub struct Payload {
     pub identity: Option<ValidatorId>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
 pub struct VerifyPayload {
     pub from: ValidatorId,
     pub payload: Payload,
@@ -428,6 +622,10 @@ mod test {
     use chrono::{Duration, Utc};
     use hex::FromHex;
     use primitives::config::configuration;
+    use primitives::util::tests::discard_logger;
+    use primitives::util::tests::prep_db::{
+        DUMMY_CHANNEL, DUMMY_VALIDATOR_FOLLOWER, DUMMY_VALIDATOR_LEADER, IDS,
+    };
     use primitives::ChannelId;
     use primitives::{adapter::KeystoreOptions, targeting::Rules};
     use primitives::{ChannelSpec, EventSubmission, SpecValidators, ValidatorDesc};
@@ -449,7 +647,8 @@ mod test {
             config.ethereum_core_address = ct_address;
         }
 
-        EthereumAdapter::init(keystore_options, &config).expect("should init ethereum adapter")
+        EthereumAdapter::init(keystore_options, &config, discard_logger())
+            .expect("should init ethereum adapter")
     }
 
     #[test]
@@ -580,6 +779,407 @@ mod test {
         assert_eq!(session.uid, identity);
     }
 
+    #[tokio::test]
+    async fn session_from_token_with_identity_and_relayer_toggle() {
+        use std::collections::HashMap;
+
+        let identity = ValidatorId::try_from("0x5B04DBc513F90CaAFAa09307Ad5e3C65EB4b26F0").unwrap();
+        let server = MockServer::start().await;
+        let identities_owned: HashMap<ValidatorId, u8> = vec![(identity, 2)].into_iter().collect();
+
+        let keystore_options = KeystoreOptions {
+            keystore_file: "./test/resources/keystore.json".to_string(),
+            keystore_pwd: "adexvalidator".to_string(),
+        };
+        let mut config = configuration("development", None).expect("failed parse config");
+        config.ethereum_adapter_relayer = server.uri();
+
+        let mut with_relayer =
+            EthereumAdapter::init(keystore_options.clone(), &config, discard_logger())
+                .expect("should init ethereum adapter");
+        with_relayer.unlock().expect("should unlock eth adapter");
+
+        config.use_relayer = false;
+        let mut without_relayer =
+            EthereumAdapter::init(keystore_options, &config, discard_logger())
+                .expect("should init ethereum adapter");
+        without_relayer.unlock().expect("should unlock eth adapter");
+
+        Mock::given(method("GET"))
+            .and(path(format!(
+                "/identity/by-owner/{}",
+                with_relayer.whoami()
+            )))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&identities_owned))
+            // the adapter without a relayer should never call it
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let era = Utc::now().timestamp_millis() as f64 / 60000.0;
+        let payload = Payload {
+            id: with_relayer.whoami().to_checksum(),
+            era: era.floor() as i64,
+            identity: Some(identity),
+            address: with_relayer.whoami().to_checksum(),
+        };
+        let wallet = with_relayer.wallet.clone().expect("Should be unlocked");
+        let token = ewt_sign(&wallet, &with_relayer.keystore_pwd, &payload).unwrap();
+
+        let session = with_relayer
+            .session_from_token(&token)
+            .await
+            .expect("Should get the session using the relayer");
+        assert_eq!(session.uid, identity);
+
+        let error = without_relayer
+            .session_from_token(&token)
+            .await
+            .expect_err("Identity tokens should not be allowed without a relayer");
+        assert!(matches!(error, AdapterError::Authorization(_)));
+
+        // tokens without identity don't require the relayer
+        let direct_payload = Payload {
+            identity: None,
+            ..payload
+        };
+        let direct_token = ewt_sign(&wallet, &without_relayer.keystore_pwd, &direct_payload)
+            .expect("Should sign the token");
+        let session = without_relayer
+            .session_from_token(&direct_token)
+            .await
+            .expect("Should get the session without the relayer");
+        assert_eq!(&session.uid, without_relayer.whoami());
+    }
+
+    #[tokio::test]
+    async fn channel_state_fails_over_to_the_next_provider() {
+        let failing = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&failing)
+            .await;
+
+        let working = MockServer::start().await;
+        // an ABI encoded `uint256` equal to `1`, i.e. an Active channel
+        let active_state = format!("0x{:0>64}", "1");
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": active_state,
+            })))
+            .mount(&working)
+            .await;
+
+        let mut config = configuration("development", None).expect("failed parse config");
+        config.ethereum_network = failing.uri();
+        config.ethereum_network_fallbacks = vec![working.uri()];
+        let keystore_options = KeystoreOptions {
+            keystore_file: "./test/resources/keystore.json".to_string(),
+            keystore_pwd: "adexvalidator".to_string(),
+        };
+        let eth_adapter = EthereumAdapter::init(keystore_options, &config, discard_logger())
+            .expect("should init ethereum adapter");
+
+        let channel_state = eth_adapter
+            .channel_state(&ChannelId::from([1_u8; 32]))
+            .await
+            .expect("Should fail over to the working provider");
+
+        assert_eq!(*CHANNEL_STATE_ACTIVE, channel_state);
+
+        // the contract is created on `init` and reused
+        for provider in eth_adapter.providers.iter() {
+            assert_eq!(
+                Address::from(config.ethereum_core_address),
+                provider.adex_core.address()
+            );
+        }
+        assert_eq!(
+            1,
+            eth_adapter.current_provider.load(Ordering::SeqCst),
+            "The working provider should become the current one"
+        );
+    }
+
+    #[tokio::test]
+    async fn check_node_returns_the_latest_block_number() {
+        let reachable = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": "0x10",
+            })))
+            .mount(&reachable)
+            .await;
+
+        let unreachable = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&unreachable)
+            .await;
+
+        let mut config = configuration("development", None).expect("failed parse config");
+
+        config.ethereum_network = reachable.uri();
+        let eth_adapter = EthereumAdapter::new_read_only(IDS["leader"], &config, discard_logger())
+            .expect("should create adapter");
+        assert_eq!(
+            16,
+            eth_adapter
+                .check_node()
+                .await
+                .expect("Should get the block number")
+        );
+
+        config.ethereum_network = unreachable.uri();
+        let eth_adapter = EthereumAdapter::new_read_only(IDS["leader"], &config, discard_logger())
+            .expect("should create adapter");
+        match eth_adapter.check_node().await {
+            Err(AdapterError::Adapter(err)) => assert!(matches!(*err, Error::Web3(_))),
+            other => panic!("Expected a web3 error, got: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn validate_channel_caches_the_channel_state_for_the_ttl() {
+        let server = MockServer::start().await;
+        // an ABI encoded `uint256` equal to `1`, i.e. an Active channel
+        let active_state = format!("0x{:0>64}", "1");
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": active_state,
+            })))
+            // a query with the first adapter and two with the adapter without a cache
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let mut config = configuration("development", None).expect("failed parse config");
+        config.ethereum_network = server.uri();
+
+        let mut channel = DUMMY_CHANNEL.clone();
+        channel.valid_until = Utc::now() + Duration::days(2);
+        channel.spec.withdraw_period_start = Utc::now() + Duration::days(1);
+        let eth_channel =
+            EthereumChannel::try_from(&channel).expect("failed to create eth channel");
+        channel.id = ChannelId::from(eth_channel.hash(&config.ethereum_core_address));
+
+        let cached =
+            EthereumAdapter::new_read_only(DUMMY_VALIDATOR_LEADER.id, &config, discard_logger())
+                .expect("should create adapter");
+        for _ in 0..2 {
+            assert!(cached
+                .validate_channel(&channel)
+                .await
+                .expect("Should validate the channel"));
+        }
+
+        config.channel_state_cache_ttl = 0;
+        let not_cached =
+            EthereumAdapter::new_read_only(DUMMY_VALIDATOR_LEADER.id, &config, discard_logger())
+                .expect("should create adapter");
+        let expired_channel = ChannelId::from([1; 32]);
+        not_cached
+            .channel_states
+            .write()
+            .unwrap()
+            .insert(expired_channel, (Instant::now(), U256::zero()));
+        for _ in 0..2 {
+            assert!(not_cached
+                .validate_channel(&channel)
+                .await
+                .expect("Should validate the channel"));
+        }
+
+        // the expired states are evicted
+        let channel_states = not_cached.channel_states.read().unwrap();
+        assert!(!channel_states.contains_key(&expired_channel));
+        assert_eq!(1, channel_states.len());
+    }
+
+    #[tokio::test]
+    async fn channel_state_caches_only_the_active_state() {
+        let server = MockServer::start().await;
+        // an ABI encoded `uint256` equal to `0`, i.e. an Unknown channel
+        let unknown_state = format!("0x{:0>64}", "0");
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": unknown_state,
+            })))
+            // every query reaches the node, since the state is not cached
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let mut config = configuration("development", None).expect("failed parse config");
+        config.ethereum_network = server.uri();
+        let eth_adapter =
+            EthereumAdapter::new_read_only(DUMMY_VALIDATOR_LEADER.id, &config, discard_logger())
+                .expect("should create adapter");
+
+        let channel_id = ChannelId::from([1_u8; 32]);
+        for _ in 0..2 {
+            let channel_state = eth_adapter
+                .channel_state(&channel_id)
+                .await
+                .expect("Should query the channel state");
+
+            assert_eq!(U256::zero(), channel_state);
+        }
+
+        assert!(eth_adapter
+            .channel_states
+            .read()
+            .expect("The channel states lock should not be poisoned")
+            .is_empty());
+    }
+
+    /// Collects the messages and the key-values of the log records
+    #[derive(Clone, Default)]
+    struct CaptureDrain(Arc<std::sync::Mutex<Vec<String>>>);
+
+    struct KeyValues(Vec<String>);
+
+    impl slog::Serializer for KeyValues {
+        fn emit_arguments(
+            &mut self,
+            key: slog::Key,
+            value: &std::fmt::Arguments<'_>,
+        ) -> slog::Result {
+            self.0.push(format!("{}={}", key, value));
+            Ok(())
+        }
+    }
+
+    impl slog::Drain for CaptureDrain {
+        type Ok = ();
+        type Err = slog::Never;
+
+        fn log(
+            &self,
+            record: &slog::Record<'_>,
+            values: &slog::OwnedKVList,
+        ) -> Result<Self::Ok, Self::Err> {
+            use slog::KV;
+
+            let mut key_values = KeyValues(vec![]);
+            record
+                .kv()
+                .serialize(record, &mut key_values)
+                .expect("Should serialize the record key-values");
+            values
+                .serialize(record, &mut key_values)
+                .expect("Should serialize the logger key-values");
+
+            self.0.lock().expect("Should lock the logs").push(format!(
+                "{} {}",
+                record.msg(),
+                key_values.0.join(" ")
+            ));
+
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn validate_channel_is_logged() {
+        let server = MockServer::start().await;
+        // an ABI encoded `uint256` equal to `1`, i.e. an Active channel
+        let active_state = format!("0x{:0>64}", "1");
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": active_state,
+            })))
+            .mount(&server)
+            .await;
+
+        let mut config = configuration("development", None).expect("failed parse config");
+        config.ethereum_network = server.uri();
+
+        let mut channel = DUMMY_CHANNEL.clone();
+        channel.valid_until = Utc::now() + Duration::days(2);
+        channel.spec.withdraw_period_start = Utc::now() + Duration::days(1);
+        let eth_channel =
+            EthereumChannel::try_from(&channel).expect("failed to create eth channel");
+        channel.id = ChannelId::from(eth_channel.hash(&config.ethereum_core_address));
+
+        let drain = CaptureDrain::default();
+        let logger = slog::Logger::root(slog::Drain::fuse(drain.clone()), slog::o!());
+        let eth_adapter =
+            EthereumAdapter::new_read_only(DUMMY_VALIDATOR_LEADER.id, &config, logger)
+                .expect("should create adapter");
+
+        eth_adapter
+            .validate_channel(&channel)
+            .await
+            .expect("Should validate the channel");
+
+        let logs = drain.0.lock().expect("Should lock the logs");
+        assert_eq!(1, logs.len());
+        assert!(logs[0].starts_with("Validated channel"));
+        assert!(logs[0].contains(&format!("channel={}", channel.id)));
+        assert!(logs[0].contains(&format!("validator={}", DUMMY_VALIDATOR_LEADER.id)));
+        assert!(logs[0].contains("is_active=true"));
+    }
+
+    #[tokio::test]
+    async fn read_only_adapter_validates_channels_but_cannot_sign() {
+        let server = MockServer::start().await;
+        // an ABI encoded `uint256` equal to `1`, i.e. an Active channel
+        let active_state = format!("0x{:0>64}", "1");
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": active_state,
+            })))
+            .mount(&server)
+            .await;
+
+        let mut config = configuration("development", None).expect("failed parse config");
+        config.ethereum_network = server.uri();
+
+        let mut read_only =
+            EthereumAdapter::new_read_only(DUMMY_VALIDATOR_LEADER.id, &config, discard_logger())
+                .expect("should create a read-only ethereum adapter");
+
+        let mut channel = DUMMY_CHANNEL.clone();
+        channel.valid_until = Utc::now() + Duration::days(2);
+        channel.spec.withdraw_period_start = Utc::now() + Duration::days(1);
+        let eth_channel =
+            EthereumChannel::try_from(&channel).expect("failed to create eth channel");
+        channel.id = ChannelId::from(eth_channel.hash(&config.ethereum_core_address));
+
+        let is_valid = read_only
+            .validate_channel(&channel)
+            .await
+            .expect("Should validate the channel");
+        assert!(is_valid);
+
+        let state_root = "2bdeafae53940669daa6f519373f686c42f4e5a2ae3ed64c0c9aee2e3d07f2a8";
+        match read_only.sign(state_root) {
+            Err(AdapterError::Adapter(err)) => assert!(matches!(*err, Error::ReadOnly)),
+            other => panic!("Expected a read-only error, got: {:?}", other),
+        }
+        match read_only.get_auth(&DUMMY_VALIDATOR_FOLLOWER.id) {
+            Err(AdapterError::Adapter(err)) => assert!(matches!(*err, Error::ReadOnly)),
+            other => panic!("Expected a read-only error, got: {:?}", other),
+        }
+        match read_only.unlock() {
+            Err(AdapterError::Adapter(err)) => assert!(matches!(*err, Error::ReadOnly)),
+            other => panic!("Expected a read-only error, got: {:?}", other),
+        }
+    }
+
     #[tokio::test]
     async fn should_validate_valid_channel_properly() {
         let http =
@@ -22,11 +22,20 @@ pub enum Error {
     ContractQuerying(web3::contract::Error),
     /// Error occurred during verification of Signature and/or StateRoot and/or Address
     VerifyAddress(VerifyError),
+    /// The adapter was created with `EthereumAdapter::new_read_only` and has no wallet
+    ReadOnly,
 }
 
 impl std::error::Error for Error {}
 
-impl AdapterErrorKind for Error {}
+impl AdapterErrorKind for Error {
+    fn is_client_error(&self) -> bool {
+        matches!(
+            self,
+            Error::InvalidChannelId { .. } | Error::ChannelInactive(_)
+        )
+    }
+}
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -43,7 +52,8 @@ impl fmt::Display for Error {
                 VerifyMessage(err) => write!(f, "Verifying message: {}", err),
                 ContractInitialization(err) => write!(f, "Contract initialization: {}", err),
                 ContractQuerying(err) => write!(f, "Contract querying: {}", err),
-                VerifyAddress(err) => write!(f, "Verifying address: {}", err)
+                VerifyAddress(err) => write!(f, "Verifying address: {}", err),
+                ReadOnly => write!(f, "The adapter is read-only and cannot sign messages"),
             }
     }
 }
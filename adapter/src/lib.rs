@@ -6,7 +6,7 @@ use std::error::Error;
 
 use chrono::{DateTime, Utc};
 use hex::FromHex;
-use primitives::{channel::ChannelError, BigNum, Channel, ValidatorId};
+use primitives::{channel::ChannelError, merkle_tree, BigNum, Channel, ValidatorId};
 use sha2::{Digest, Sha256};
 use std::convert::TryFrom;
 use tiny_keccak::Keccak;
@@ -26,43 +26,30 @@ pub enum AdapterTypes {
     EthereumAdapter(Box<EthereumAdapter>),
 }
 
+/// See [`merkle_tree::signable_state_root`], the `channel_id` is right-padded to 32 bytes,
+/// the same way as an ABI encoded `bytes32`.
 pub fn get_signable_state_root(
     channel_id: &[u8],
     balance_root: &[u8; 32],
 ) -> Result<[u8; 32], Box<dyn Error>> {
-    let tokens = [
-        Token::FixedBytes(channel_id.to_vec()),
-        Token::FixedBytes(balance_root.to_vec()),
-    ];
-
-    let encoded = encode(&tokens).to_vec();
-
-    let mut result = Keccak::new_keccak256();
-    result.update(&encoded);
+    if channel_id.len() > 32 {
+        return Err(Box::new(ChannelError::InvalidArgument(
+            "channel id should be at most 32 bytes".into(),
+        )));
+    }
 
-    let mut res: [u8; 32] = [0; 32];
-    result.finalize(&mut res);
+    let mut padded_channel_id = [0_u8; 32];
+    padded_channel_id[..channel_id.len()].copy_from_slice(channel_id);
 
-    Ok(res)
+    Ok(merkle_tree::signable_state_root(
+        &padded_channel_id,
+        balance_root,
+    ))
 }
 
+/// See [`merkle_tree::balance_leaf`]
 pub fn get_balance_leaf(acc: &ValidatorId, amnt: &BigNum) -> Result<[u8; 32], Box<dyn Error>> {
-    let tokens = [
-        Token::Address(Address::from_slice(acc.inner())),
-        Token::Uint(
-            U256::from_dec_str(&amnt.to_str_radix(10))
-                .map_err(|_| ChannelError::InvalidArgument("failed to parse amt".into()))?,
-        ),
-    ];
-    let encoded = encode(&tokens).to_vec();
-
-    let mut result = Keccak::new_keccak256();
-    result.update(&encoded);
-
-    let mut res: [u8; 32] = [0; 32];
-    result.finalize(&mut res);
-
-    Ok(res)
+    Ok(merkle_tree::balance_leaf(acc, amnt)?)
 }
 
 // OnChain channel Representation
@@ -213,6 +200,7 @@ mod test {
     use chrono::{TimeZone, Utc};
 
     use primitives::merkle_tree::MerkleTree;
+    use primitives::util::tests::prep_db::DUMMY_CHANNEL;
 
     use super::*;
 
@@ -240,4 +228,76 @@ mod test {
 
         assert_eq!(state_root.to_vec(), expected_hex);
     }
+
+    #[test]
+    fn get_signable_state_root_pads_the_channel_id_like_the_abi_encoding() {
+        let balance_root = [2_u8; 32];
+
+        // e.g. the contract address of `EthereumChannel::hash_to_sign` has only 20 bytes
+        for channel_id in [vec![1_u8; 20], vec![1_u8; 32]].iter() {
+            let encoded = encode(&[
+                Token::FixedBytes(channel_id.clone()),
+                Token::FixedBytes(balance_root.to_vec()),
+            ]);
+            let mut expected = [0_u8; 32];
+            let mut keccak = Keccak::new_keccak256();
+            keccak.update(&encoded);
+            keccak.finalize(&mut expected);
+
+            let state_root =
+                get_signable_state_root(channel_id, &balance_root).expect("Should get state_root");
+            assert_eq!(expected, state_root);
+        }
+
+        assert!(get_signable_state_root(&[1_u8; 33], &balance_root).is_err());
+    }
+
+    #[test]
+    fn ethereum_channel_solidity_tuple_is_in_the_contract_field_order() {
+        let channel = DUMMY_CHANNEL.clone();
+
+        let eth_channel = EthereumChannel::try_from(&channel).expect("Should convert the Channel");
+
+        let address = |hex: &str| Address::from_slice(&hex::decode(hex).expect("Valid hex"));
+        let mut spec_hash = Sha256::new();
+        spec_hash.input(serde_json::to_string(&channel.spec).expect("Should serialize the spec"));
+        let spec_hash: [u8; 32] = spec_hash.result().into();
+
+        // (creator, tokenAddr, tokenAmount, validUntil, validators, spec)
+        let expected = Token::Tuple(vec![
+            Token::Address(address("033ed90e0fec3f3ea1c9b005c724d704501e0196")),
+            Token::Address(address("89d24A6b4CcB1B6fAA2625fE562bDD9a23260359")),
+            Token::Uint(U256::from(1_000)),
+            // UNIX timestamp for 2100-01-01
+            Token::Uint(U256::from(4_102_444_800_u64)),
+            Token::Array(vec![
+                Token::Address(address("ce07CbB7e054514D590a0262C93070D838bFBA2e")),
+                Token::Address(address("c91763d7f14ac5c5ddfbcd012e0d2a61ab9bded3")),
+            ]),
+            Token::FixedBytes(spec_hash.to_vec()),
+        ]);
+
+        assert_eq!(expected, eth_channel.to_solidity_tuple());
+    }
+
+    #[test]
+    fn ethereum_channel_hash_depends_on_the_contract_address() {
+        let eth_channel =
+            EthereumChannel::try_from(&DUMMY_CHANNEL.clone()).expect("Should convert the Channel");
+
+        let hash = eth_channel.hash(&[1_u8; 20]);
+
+        assert_eq!(hash, eth_channel.hash(&[1_u8; 20]));
+        assert_ne!(hash, eth_channel.hash(&[2_u8; 20]));
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn adapters_are_send_and_sync() {
+        // the adapters are shared behind an `Arc` between the channel ticks of the worker
+        assert_send_sync::<EthereumAdapter>();
+        assert_send_sync::<DummyAdapter>();
+        assert_send_sync::<std::sync::Arc<EthereumAdapter>>();
+    }
 }
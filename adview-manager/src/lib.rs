@@ -57,6 +57,10 @@ pub struct Options {
     /// Defaulted
     pub disabled_video: bool,
     pub disabled_sticky: bool,
+    /// The AdUnits blacklisted by the publisher for the `market_slot`,
+    /// see [`AdSlot::blacklisted_units`](adex_primitives::AdSlot::blacklisted_units)
+    #[serde(default)]
+    pub blacklisted_units: Vec<IPFS>,
 }
 
 impl Options {
@@ -163,6 +167,28 @@ fn randomized_sort_pos(ad_unit: &AdUnit, seed: BigNum) -> BigNum {
     x.mod_floor(&BigNum::from(0x80000000))
 }
 
+/// The price of the ad unit multiplied by the targeting `boost`, see [`targeting::Output::boosted_score`]
+fn boosted_price(unit_with_price: &UnitsWithPrice, output: &targeting::Output) -> f64 {
+    output.boosted_score(unit_with_price.price.to_f64().unwrap_or_default())
+}
+
+/// Ranks the ad units by their boosted price, the highest first,
+/// and applies random selection between the ad units with the same boosted price
+fn auction_order(
+    a: &(UnitsWithPrice, ChannelId, targeting::Output),
+    b: &(UnitsWithPrice, ChannelId, targeting::Output),
+    seed: &BigNum,
+) -> Ordering {
+    match boosted_price(&b.0, &b.2)
+        .partial_cmp(&boosted_price(&a.0, &a.2))
+        .unwrap_or(Ordering::Equal)
+    {
+        Ordering::Equal => randomized_sort_pos(&b.0.unit, seed.clone())
+            .cmp(&randomized_sort_pos(&a.0.unit, seed.clone())),
+        ordering => ordering,
+    }
+}
+
 fn get_unit_html(
     size: &Option<(u64, u64)>,
     ad_unit: &AdUnit,
@@ -461,7 +487,7 @@ impl Manager {
         let seed = BigNum::from(random as u64);
 
         // Apply targeting, now with adView.* variables, and sort the resulting ad units
-        let mut units_with_price: Vec<(UnitsWithPrice, ChannelId)> = campaigns
+        let mut units_with_price: Vec<(UnitsWithPrice, ChannelId, targeting::Output)> = campaigns
             .iter()
             .map(|campaign| {
                 // since we are in a Iterator.map(), we can't use async, so we block
@@ -476,7 +502,7 @@ impl Manager {
                 campaign
                     .units_with_price
                     .iter()
-                    .filter(|unit_with_price| {
+                    .filter_map(|unit_with_price| {
                         unit_input.ad_unit_id = Some(unit_with_price.unit.id.clone());
 
                         let mut output = targeting::Output {
@@ -496,25 +522,26 @@ impl Manager {
                             Some(on_type_error)
                         );
 
-                        output.show
+                        // a boost of 0 disables the ad unit
+                        if output.show && output.boosted_score(1.0) > 0.0 {
+                            Some((unit_with_price.clone(), campaign_id, output))
+                        } else {
+                            None
+                        }
                     })
-                    .map(|uwp| (uwp.clone(), campaign_id))
                     .collect()
             })
             .flatten()
             .filter(|x| !(self.options.disabled_video && is_video(&x.0.unit)))
+            .filter(|x| !self.options.blacklisted_units.contains(&x.0.unit.id))
             .collect();
 
-        units_with_price.sort_by(|b, a| match (&a.0.price).cmp(&b.0.price) {
-            Ordering::Equal => randomized_sort_pos(&a.0.unit, seed.clone())
-                .cmp(&randomized_sort_pos(&b.0.unit, seed.clone())),
-            ordering => ordering,
-        });
+        units_with_price.sort_by(|a, b| auction_order(a, b, &seed));
 
         // Update history
         let auction_winner = units_with_price.get(0);
 
-        if let Some((unit_with_price, campaign_id)) = auction_winner {
+        if let Some((unit_with_price, campaign_id, _)) = auction_winner {
             let history = self.history.read().await.clone();
 
             let new_entry = HistoryEntry {
@@ -538,7 +565,7 @@ impl Manager {
         }
 
         // Return the results, with a fallback unit if there is one
-        if let Some((unit_with_price, campaign_id)) = auction_winner {
+        if let Some((unit_with_price, campaign_id, _)) = auction_winner {
             let validators = campaigns
                 .iter()
                 .find_map(|campaign| {
@@ -646,4 +673,53 @@ mod test {
             assert_eq!(BigNum::from(177_349_401), result);
         }
     }
+
+    mod auction_order {
+        use super::*;
+
+        fn get_candidate(
+            id: usize,
+            price: u64,
+            boost: f64,
+        ) -> (UnitsWithPrice, ChannelId, targeting::Output) {
+            let unit = AdUnit {
+                id: DUMMY_IPFS[id].clone(),
+                ..get_ad_unit("image/jpeg")
+            };
+            let output = targeting::Output {
+                show: true,
+                boost,
+                price: Default::default(),
+            };
+
+            (
+                UnitsWithPrice {
+                    unit,
+                    price: price.into(),
+                },
+                ChannelId::from([id as u8; 32]),
+                output,
+            )
+        }
+
+        #[test]
+        fn the_boost_changes_the_winner() {
+            let seed = BigNum::from(5);
+
+            let mut candidates = vec![get_candidate(0, 100, 1.0), get_candidate(1, 150, 1.0)];
+            candidates.sort_by(|a, b| auction_order(a, b, &seed));
+            assert_eq!(
+                DUMMY_IPFS[1], candidates[0].0.unit.id,
+                "Without a boost the higher price wins"
+            );
+
+            // a boost of 2.0 doubles the price of the cheaper unit to 200
+            let mut candidates = vec![get_candidate(0, 100, 2.0), get_candidate(1, 150, 1.0)];
+            candidates.sort_by(|a, b| auction_order(a, b, &seed));
+            assert_eq!(
+                DUMMY_IPFS[0], candidates[0].0.unit.id,
+                "The boosted unit should win"
+            );
+        }
+    }
 }
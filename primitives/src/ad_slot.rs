@@ -1,4 +1,4 @@
-use crate::{targeting::Rule, BigNum, ValidatorId};
+use crate::{targeting::Rule, AdUnit, BigNum, ValidatorId, IPFS};
 use chrono::{
     serde::{ts_milliseconds, ts_milliseconds_option},
     DateTime, Utc,
@@ -29,6 +29,9 @@ pub struct AdSlot {
     pub min_per_impression: Option<HashMap<String, BigNum>>,
     #[serde(default)]
     pub rules: Vec<Rule>,
+    /// The AdUnits which the publisher doesn't want to be shown in this slot
+    #[serde(default)]
+    pub blacklisted_units: Vec<IPFS>,
     /// Valid ipfs hash for Ad Unit object. It will be used as fallback data (optional)
     #[serde(default)]
     pub fallback_unit: Option<String>,
@@ -52,3 +55,63 @@ pub struct AdSlot {
     #[serde(with = "ts_milliseconds_option")]
     pub modified: Option<DateTime<Utc>>,
 }
+
+impl AdSlot {
+    /// Whether the publisher has blacklisted the AdUnit with the given `ipfs` for this slot
+    pub fn is_blacklisted(&self, ipfs: &IPFS) -> bool {
+        self.blacklisted_units.contains(ipfs)
+    }
+}
+
+/// Selects the [`AdUnit`]s that can be shown in the given [`AdSlot`]:
+/// the unit should be of the same type as the slot and it should not be blacklisted by the slot.
+pub fn match_units<'a>(slot: &AdSlot, units: &'a [AdUnit]) -> Vec<&'a AdUnit> {
+    units
+        .iter()
+        .filter(|unit| unit.ad_type == slot.ad_type && !slot.is_blacklisted(&unit.ipfs))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::tests::prep_db::{DUMMY_AD_UNITS, DUMMY_IPFS, IDS};
+    use chrono::TimeZone;
+
+    fn get_ad_slot(ad_type: &str) -> AdSlot {
+        AdSlot {
+            ipfs: DUMMY_IPFS[0].to_string(),
+            ad_type: ad_type.to_string(),
+            min_per_impression: None,
+            rules: vec![],
+            blacklisted_units: vec![],
+            fallback_unit: None,
+            owner: IDS["publisher"],
+            created: Utc.ymd(2020, 6, 6).and_hms(12, 0, 0),
+            title: None,
+            description: None,
+            website: None,
+            archived: false,
+            modified: None,
+        }
+    }
+
+    #[test]
+    fn matches_units_of_the_same_type_which_are_not_blacklisted() {
+        let mut ad_slot = get_ad_slot("legacy_250x250");
+
+        let mut units = DUMMY_AD_UNITS.to_vec();
+        units[1].ad_type = "legacy_300x100".to_string();
+        ad_slot.blacklisted_units = vec![units[2].ipfs.clone()];
+
+        assert_eq!(vec![&units[0], &units[3]], match_units(&ad_slot, &units));
+    }
+
+    #[test]
+    fn no_matching_units() {
+        let ad_slot = get_ad_slot("legacy_728x90");
+
+        assert!(match_units(&ad_slot, &DUMMY_AD_UNITS[..]).is_empty());
+        assert!(match_units(&ad_slot, &[]).is_empty());
+    }
+}
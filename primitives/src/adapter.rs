@@ -9,7 +9,13 @@ use std::fmt;
 
 pub type AdapterResult<T, AE> = Result<T, Error<AE>>;
 
-pub trait AdapterErrorKind: fmt::Debug + fmt::Display {}
+pub trait AdapterErrorKind: fmt::Debug + fmt::Display {
+    /// Whether the error is caused by the input, e.g. an invalid or inactive `Channel`,
+    /// rather than by the Adapter itself.
+    fn is_client_error(&self) -> bool {
+        false
+    }
+}
 
 #[derive(Debug)]
 pub enum Error<AE: AdapterErrorKind> {
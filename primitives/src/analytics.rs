@@ -1,6 +1,8 @@
 use crate::ChannelId;
 use crate::DomainError;
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize, Serializer};
+use std::{convert::TryFrom, fmt, str::FromStr};
 
 pub const ANALYTICS_QUERY_LIMIT: u32 = 200;
 
@@ -35,46 +37,163 @@ pub mod postgres {
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// Generates an enum of the whitelisted values of an [`AnalyticsQuery`] parameter.
+/// Only these values can reach the SQL queries, any other value is rejected when deserializing.
+macro_rules! query_parameter {
+    ($(#[$meta:meta])* $name:ident ($parameter:literal) { $($variant:ident => $value:literal),+ $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+        #[serde(try_from = "String")]
+        pub enum $name {
+            $($variant),+
+        }
+
+        impl $name {
+            pub const ALL: &[$name] = &[$($name::$variant),+];
+
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $value),+
+                }
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = DomainError;
+
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                Self::ALL
+                    .iter()
+                    .find(|variant| variant.as_str() == value)
+                    .copied()
+                    .ok_or_else(|| {
+                        let possible_values: Vec<_> =
+                            Self::ALL.iter().map(|variant| variant.as_str()).collect();
+
+                        DomainError::InvalidArgument(format!(
+                            "invalid {}, possible values are: {}",
+                            $parameter,
+                            possible_values.join(" ,")
+                        ))
+                    })
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = DomainError;
+
+            fn try_from(value: String) -> Result<Self, Self::Error> {
+                value.parse()
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+    };
+}
+
+query_parameter!(EventType("event_type") {
+    Impression => "IMPRESSION",
+    Click => "CLICK",
+});
+
+query_parameter!(Metric("metric") {
+    EventPayouts => "eventPayouts",
+    EventCounts => "eventCounts",
+});
+
+query_parameter!(Timeframe("timeframe") {
+    Year => "year",
+    Month => "month",
+    Week => "week",
+    Day => "day",
+    Hour => "hour",
+});
+
+impl Timeframe {
+    /// Returns the (interval, period) of the timeframe in milliseconds
+    pub fn time_frame(&self) -> (i64, i64) {
+        let minute = 60 * 1000;
+        let hour = 60 * minute;
+        let day = 24 * hour;
+
+        match self {
+            Timeframe::Year => (30 * day, 365 * day),
+            Timeframe::Month => (day, 30 * day),
+            Timeframe::Week => (6 * hour, 7 * day),
+            Timeframe::Day => (hour, day),
+            Timeframe::Hour => (minute, hour),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AnalyticsQuery {
     #[serde(default = "default_limit")]
     pub limit: u32,
     #[serde(default = "default_event_type")]
-    pub event_type: String,
+    pub event_type: EventType,
     #[serde(default = "default_metric")]
-    pub metric: String,
+    pub metric: Metric,
     #[serde(default = "default_timeframe")]
-    pub timeframe: String,
+    pub timeframe: Timeframe,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub segment_by_channel: Option<String>,
+    /// The start of the time range, if not set the `timeframe` period before `end` (or now) is used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<DateTime<Utc>>,
+    /// The end of the time range, if not set it defaults to now
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Utc>>,
 }
 
 impl AnalyticsQuery {
+    /// The `event_type`, `metric` and `timeframe` are validated when deserializing the query
     pub fn is_valid(&self) -> Result<(), DomainError> {
-        let valid_event_types = ["IMPRESSION", "CLICK"];
-        let valid_metric = ["eventPayouts", "eventCounts"];
-        let valid_timeframe = ["year", "month", "week", "day", "hour"];
-
-        if !valid_event_types.contains(&self.event_type.as_str()) {
-            Err(DomainError::InvalidArgument(format!(
-                "invalid event_type, possible values are: {}",
-                valid_event_types.join(" ,")
-            )))
-        } else if !valid_metric.contains(&self.metric.as_str()) {
-            Err(DomainError::InvalidArgument(format!(
-                "invalid metric, possible values are: {}",
-                valid_metric.join(" ,")
-            )))
-        } else if !valid_timeframe.contains(&self.timeframe.as_str()) {
-            Err(DomainError::InvalidArgument(format!(
-                "invalid timeframe, possible values are: {}",
-                valid_timeframe.join(" ,")
-            )))
-        } else if self.limit > ANALYTICS_QUERY_LIMIT {
+        if self.limit > ANALYTICS_QUERY_LIMIT {
             Err(DomainError::InvalidArgument(format!(
                 "invalid limit {}, maximum value 200",
                 self.limit
             )))
+        } else {
+            self.is_time_range_valid()
+        }
+    }
+
+    fn is_time_range_valid(&self) -> Result<(), DomainError> {
+        let (start, end) = match (self.start, self.end) {
+            (Some(start), Some(end)) => (start, end),
+            (Some(start), None) => (start, Utc::now()),
+            _ => return Ok(()),
+        };
+
+        if start >= end {
+            return Err(DomainError::InvalidArgument(
+                "invalid time range, start should be before end".to_string(),
+            ));
+        }
+
+        // a single row is returned for each interval, so the range should fit in the limit
+        let (interval, _) = self.timeframe.time_frame();
+        let max_range = Duration::milliseconds(interval * i64::from(self.limit));
+
+        if end - start > max_range {
+            Err(DomainError::InvalidArgument(format!(
+                "invalid time range, maximum range for timeframe {} and limit {} is {} hours",
+                self.timeframe,
+                self.limit,
+                max_range.num_hours()
+            )))
         } else {
             Ok(())
         }
@@ -85,14 +204,105 @@ fn default_limit() -> u32 {
     100
 }
 
-fn default_event_type() -> String {
-    "IMPRESSION".into()
+fn default_event_type() -> EventType {
+    EventType::Impression
+}
+
+fn default_metric() -> Metric {
+    Metric::EventCounts
 }
 
-fn default_metric() -> String {
-    "eventCounts".into()
+fn default_timeframe() -> Timeframe {
+    Timeframe::Hour
 }
 
-fn default_timeframe() -> String {
-    "hour".into()
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn query_with_range(
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> AnalyticsQuery {
+        AnalyticsQuery {
+            limit: 100,
+            event_type: default_event_type(),
+            metric: default_metric(),
+            timeframe: Timeframe::Day,
+            segment_by_channel: None,
+            start,
+            end,
+        }
+    }
+
+    #[test]
+    fn analytics_query_with_a_valid_time_range() {
+        let start = Utc.ymd(2020, 10, 1).and_hms(0, 0, 0);
+        let end = Utc.ymd(2020, 10, 2).and_hms(0, 0, 0);
+
+        assert_eq!(Ok(()), query_with_range(Some(start), Some(end)).is_valid());
+
+        let query: AnalyticsQuery = serde_json::from_str(
+            r#"{"timeframe":"day","start":"2020-10-01T00:00:00Z","end":"2020-10-02T00:00:00Z"}"#,
+        )
+        .expect("Should deserialize the query");
+        assert_eq!(Some(start), query.start);
+        assert_eq!(Some(end), query.end);
+        assert_eq!(Ok(()), query.is_valid());
+    }
+
+    #[test]
+    fn analytics_query_with_an_inverted_time_range() {
+        let start = Utc.ymd(2020, 10, 2).and_hms(0, 0, 0);
+        let end = Utc.ymd(2020, 10, 1).and_hms(0, 0, 0);
+
+        assert_eq!(
+            Err(DomainError::InvalidArgument(
+                "invalid time range, start should be before end".to_string()
+            )),
+            query_with_range(Some(start), Some(end)).is_valid()
+        );
+    }
+
+    #[test]
+    fn analytics_query_with_a_too_large_time_range() {
+        // timeframe `day` has an interval of 1 hour and the limit is 100
+        let start = Utc.ymd(2020, 10, 1).and_hms(0, 0, 0);
+        let end = start + Duration::hours(101);
+
+        assert!(query_with_range(Some(start), Some(end)).is_valid().is_err());
+
+        let end = start + Duration::hours(100);
+        assert_eq!(Ok(()), query_with_range(Some(start), Some(end)).is_valid());
+    }
+
+    #[test]
+    fn analytics_query_parameters_are_whitelisted() {
+        let query: AnalyticsQuery = serde_json::from_value(serde_json::json!({
+            "eventType": "CLICK",
+            "metric": "eventPayouts",
+            "timeframe": "week",
+        }))
+        .expect("Should deserialize the query");
+        assert_eq!(EventType::Click, query.event_type);
+        assert_eq!(Metric::EventPayouts, query.metric);
+        assert_eq!(Timeframe::Week, query.timeframe);
+
+        let malicious = serde_json::from_value::<AnalyticsQuery>(serde_json::json!({
+            "timeframe": "hour', created) AS time FROM channels; DROP TABLE event_aggregates; --",
+        }))
+        .expect_err("Should reject the malicious timeframe");
+        assert!(malicious
+            .to_string()
+            .starts_with("invalid timeframe, possible values are: year ,month ,week ,day ,hour"));
+
+        assert_eq!(
+            Err(DomainError::InvalidArgument(
+                "invalid metric, possible values are: eventPayouts ,eventCounts".to_string()
+            )),
+            "count; --".parse::<Metric>()
+        );
+        assert!("impression".parse::<EventType>().is_err());
+    }
 }
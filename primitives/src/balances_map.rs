@@ -115,4 +115,34 @@ mod test {
 
         assert_eq!(expected_deserialized, actual_deserialized);
     }
+
+    #[test]
+    fn test_balances_map_serialization_is_ordered_by_address() {
+        let ordered: BalancesMap = vec![
+            (IDS["leader"], BigNum::from(50_u64)),
+            (IDS["follower"], BigNum::from(100_u64)),
+            (IDS["publisher"], BigNum::from(10_u64)),
+        ]
+        .into_iter()
+        .collect();
+
+        let reversed: BalancesMap = vec![
+            (IDS["publisher"], BigNum::from(10_u64)),
+            (IDS["follower"], BigNum::from(100_u64)),
+            (IDS["leader"], BigNum::from(50_u64)),
+        ]
+        .into_iter()
+        .collect();
+
+        let ordered_json = serde_json::to_string(&ordered).expect("Should serialize it");
+        let reversed_json = serde_json::to_string(&reversed).expect("Should serialize it");
+
+        assert_eq!(ordered_json, reversed_json);
+
+        let keys: Vec<ValidatorId> = ordered.iter().map(|(key, _)| *key).collect();
+        let mut sorted_keys = keys.clone();
+        sorted_keys.sort();
+
+        assert_eq!(sorted_keys, keys, "Keys should be sorted by address");
+    }
 }
@@ -29,6 +29,21 @@ impl BigNum {
         Self(self.0.div_floor(&other.0))
     }
 
+    /// Raises the number to the power of `exp`, `pow(0)` is always `1`
+    pub fn pow(&self, exp: u32) -> Self {
+        Self(self.0.pow(exp))
+    }
+
+    /// The number of bits needed to represent the number, `0` for zero
+    pub fn bits(&self) -> u64 {
+        self.0.bits()
+    }
+
+    /// The integer square root, rounded down (floor) for non-perfect squares
+    pub fn sqrt(&self) -> Self {
+        Self(self.0.sqrt())
+    }
+
     pub fn to_f64(&self) -> Option<f64> {
         use num::traits::cast::ToPrimitive;
 
@@ -48,6 +63,10 @@ impl BigNum {
     pub fn from_bytes_be(buf: &[u8]) -> Self {
         Self(BigUint::from_bytes_be(buf))
     }
+
+    pub fn to_bytes_be(&self) -> Vec<u8> {
+        self.0.to_bytes_be()
+    }
 }
 
 impl fmt::Debug for BigNum {
@@ -297,4 +316,32 @@ mod test {
         let expected: BigNum = 11.into();
         assert_eq!(expected, &big_num * &ratio);
     }
+
+    #[test]
+    fn bignum_pow() {
+        assert_eq!(BigNum::from(1), BigNum::from(0).pow(0));
+        assert_eq!(BigNum::from(1), BigNum::from(42).pow(0));
+        assert_eq!(BigNum::from(42), BigNum::from(42).pow(1));
+        assert_eq!(BigNum::from(1_024), BigNum::from(2).pow(10));
+
+        let expected = BigNum::from_str(&format!("1{}", "0".repeat(37))).expect("Should be valid");
+        assert_eq!(
+            expected,
+            BigNum::from(10u64.pow(18)).pow(2) * &BigNum::from(10)
+        );
+    }
+
+    #[test]
+    fn bignum_sqrt() {
+        assert_eq!(BigNum::from(0), BigNum::from(0).sqrt());
+        assert_eq!(BigNum::from(1), BigNum::from(1).sqrt());
+        assert_eq!(BigNum::from(4), BigNum::from(16).sqrt());
+        // non-perfect squares are floored
+        assert_eq!(BigNum::from(4), BigNum::from(24).sqrt());
+        assert_eq!(BigNum::from(5), BigNum::from(35).sqrt());
+
+        let large = BigNum::from(u64::MAX).pow(2);
+        assert_eq!(BigNum::from(u64::MAX), large.sqrt());
+        assert_eq!(BigNum::from(u64::MAX), (large + BigNum::from(1)).sqrt());
+    }
 }
@@ -1,13 +1,15 @@
 use std::error::Error;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::ops::Deref;
 use std::str::FromStr;
 
-use chrono::serde::{ts_milliseconds, ts_milliseconds_option, ts_seconds};
+use chrono::serde::ts_seconds;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_hex::{SerHex, StrictPfx};
 
+use crate::util::serde::{ts_milliseconds_or_rfc3339, ts_milliseconds_or_rfc3339_option};
 use crate::{targeting::Rules, AdUnit, BigNum, EventSubmission, ValidatorDesc, ValidatorId};
 use hex::{FromHex, FromHexError};
 
@@ -102,6 +104,16 @@ pub struct Channel {
     pub exhausted: Vec<bool>,
 }
 
+impl Eq for Channel {}
+
+/// Only the `id` is hashed, since it's the identity of the `Channel`.
+/// Equal channels have the same `id`, so it's consistent with `Eq`.
+impl Hash for Channel {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
+
 pub fn channel_exhausted(channel: &Channel) -> bool {
     channel.exhausted.len() == 2 && channel.exhausted.iter().all(|&x| x)
 }
@@ -163,15 +175,15 @@ pub struct ChannelSpec {
     /// EventSubmission object, applies to event submission (POST /channel/:id/events)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub event_submission: Option<EventSubmission>,
-    /// A millisecond timestamp of when the campaign was created
-    #[serde(with = "ts_milliseconds")]
+    /// A millisecond timestamp (or an RFC3339 string) of when the campaign was created
+    #[serde(with = "ts_milliseconds_or_rfc3339")]
     pub created: DateTime<Utc>,
     /// A millisecond timestamp representing the time you want this campaign to become active (optional)
     /// Used by the AdViewManager & Targeting AIP#31
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
-        with = "ts_milliseconds_option"
+        with = "ts_milliseconds_or_rfc3339_option"
     )]
     pub active_from: Option<DateTime<Utc>>,
     /// A random number to ensure the campaignSpec hash is unique
@@ -181,7 +193,7 @@ pub struct ChannelSpec {
     /// (no longer accept any events other than CHANNEL_CLOSE)
     /// A sane value should be lower than channel.validUntil * 1000 and higher than created
     /// It's recommended to set this at least one month prior to channel.validUntil * 1000
-    #[serde(with = "ts_milliseconds")]
+    #[serde(with = "ts_milliseconds_or_rfc3339")]
     pub withdraw_period_start: DateTime<Utc>,
     /// An array of AdUnit (optional)
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -194,6 +206,22 @@ pub struct ChannelSpec {
 /// A (leader, follower) tuple
 pub struct SpecValidators(ValidatorDesc, ValidatorDesc);
 
+/// The role of the validator in the channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Leader,
+    Follower,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Leader => write!(f, "Leader"),
+            Role::Follower => write!(f, "Follower"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum SpecValidator<'a> {
     Leader(&'a ValidatorDesc),
@@ -232,6 +260,15 @@ impl SpecValidators {
         }
     }
 
+    /// The same as [`SpecValidators::find`], but with the `Role` and the `ValidatorDesc` separately,
+    /// so they don't have to be matched again
+    pub fn find_with_role(&self, validator_id: &ValidatorId) -> Option<(Role, &ValidatorDesc)> {
+        match self.find(validator_id)? {
+            SpecValidator::Leader(validator) => Some((Role::Leader, validator)),
+            SpecValidator::Follower(validator) => Some((Role::Follower, validator)),
+        }
+    }
+
     pub fn find_index(&self, validator_id: &ValidatorId) -> Option<i32> {
         if &self.leader().id == validator_id {
             Some(0)
@@ -344,6 +381,8 @@ impl Error for ChannelError {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::util::tests::prep_db::{DUMMY_CHANNEL, IDS};
+    use std::collections::HashSet;
 
     #[test]
     fn test_channel_id_() {
@@ -380,6 +419,39 @@ mod test {
             serde_json::Value::String(prefixed_string)
         )
     }
+
+    #[test]
+    fn find_with_role_returns_the_role_and_the_validator() {
+        let validators = &DUMMY_CHANNEL.spec.validators;
+
+        assert_eq!(
+            Some((Role::Leader, validators.leader())),
+            validators.find_with_role(&IDS["leader"])
+        );
+        assert_eq!(
+            Some((Role::Follower, validators.follower())),
+            validators.find_with_role(&IDS["follower"])
+        );
+        assert_eq!(None, validators.find_with_role(&IDS["publisher"]));
+    }
+
+    #[test]
+    fn duplicate_channels_are_deduplicated_in_a_hash_set() {
+        let mut other = DUMMY_CHANNEL.clone();
+        other.id = ChannelId::from([1; 32]);
+
+        let channels = vec![
+            DUMMY_CHANNEL.clone(),
+            other.clone(),
+            DUMMY_CHANNEL.clone(),
+            other.clone(),
+        ];
+        let unique = channels.into_iter().collect::<HashSet<_>>();
+
+        assert_eq!(2, unique.len());
+        assert!(unique.contains(&*DUMMY_CHANNEL));
+        assert!(unique.contains(&other));
+    }
 }
 
 #[cfg(feature = "postgres")]
@@ -403,7 +475,13 @@ pub mod postgres {
                 valid_until: row.get("valid_until"),
                 targeting_rules: row.get::<_, Json<Rules>>("targeting_rules").0,
                 spec: row.get::<_, Json<ChannelSpec>>("spec").0,
-                exhausted: row.get("exhausted"),
+                // the column is `NULL` until the channel gets exhausted
+                // and it is not selected by all queries
+                exhausted: row
+                    .try_get::<_, Option<Vec<bool>>>("exhausted")
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default(),
             }
         }
     }
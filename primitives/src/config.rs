@@ -1,9 +1,12 @@
 use crate::event_submission::RateLimit;
+use crate::util::serde::deserialize_milliseconds;
 use crate::{BigNum, ValidatorId};
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use serde_hex::{SerHex, StrictPfx};
 use std::fs;
+use std::net::IpAddr;
+use std::num::NonZeroU32;
 
 lazy_static! {
     static ref DEVELOPMENT_CONFIG: Config =
@@ -18,19 +21,49 @@ lazy_static! {
 #[serde(rename_all(serialize = "SCREAMING_SNAKE_CASE"))]
 pub struct Config {
     pub max_channels: u32,
+    /// The minimum time (in milliseconds) between two iterations over the channels of the worker
+    #[serde(deserialize_with = "deserialize_milliseconds")]
     pub wait_time: u32,
+    /// In milliseconds
+    #[serde(deserialize_with = "deserialize_milliseconds")]
     pub aggr_throttle: u32,
-    pub heartbeat_time: u32, // in milliseconds
+    /// In milliseconds
+    #[serde(deserialize_with = "deserialize_milliseconds")]
+    pub heartbeat_time: u32,
     pub channels_find_limit: u32,
     pub events_find_limit: u32,
     pub msgs_find_limit: u32,
+    /// The maximum number of validator messages in a single propagation request
+    pub msgs_propagate_limit: u32,
+    /// The maximum number of balances entries in a single propagated validator message
+    pub msgs_balances_limit: u32,
+    /// The maximum size (in bytes) of a request body, bigger requests are rejected
+    pub max_body_size: u32,
     pub health_threshold_promilles: u32,
     pub health_unsignable_promilles: u32,
+    /// All the times are in milliseconds and accept durations like `"500ms"` or `"2s"` as well
+    #[serde(deserialize_with = "deserialize_milliseconds")]
     pub propagation_timeout: u32,
+    #[serde(deserialize_with = "deserialize_milliseconds")]
     pub fetch_timeout: u32,
+    #[serde(deserialize_with = "deserialize_milliseconds")]
     pub validator_tick_timeout: u32,
+    /// The URLs of read-only replicas of our validator's Sentry.
+    /// The worker distributes its channel and analytics reads between them
+    /// and the Sentry of the channel spec, while the validator messages,
+    /// the event aggregates and the writes always go to the latter.
+    /// It's not exposed with the rest of the config by the Sentry.
+    #[serde(skip_serializing)]
+    pub sentry_read_replicas: Vec<String>,
+    /// Whether the Sentry should apply the database migrations on startup
+    pub run_migrations: bool,
     pub ip_rate_limit: RateLimit,  // HashMap??
     pub sid_rate_limit: RateLimit, // HashMap ??
+    /// The token bucket of each client IP for the event submission & analytics requests
+    pub requests_rate_limit: RequestsRateLimit,
+    /// The proxies in front of the Sentry, only for their requests the client IP
+    /// is taken from the `X-Forwarded-For` header
+    pub trusted_proxies: Vec<IpAddr>,
     pub creators_whitelist: Vec<ValidatorId>,
     pub minimal_deposit: BigNum,
     pub minimal_fee: BigNum,
@@ -38,8 +71,41 @@ pub struct Config {
     #[serde(with = "SerHex::<StrictPfx>")]
     pub ethereum_core_address: [u8; 20],
     pub ethereum_network: String,
+    /// Fallback providers which are used in order when querying the `ethereum_network` fails
+    pub ethereum_network_fallbacks: Vec<String>,
+    /// For how long (in milliseconds) the on-chain state of a validated channel is cached.
+    /// Only the Active state is cached, the rest are queried every time.
+    #[serde(deserialize_with = "deserialize_milliseconds")]
+    pub channel_state_cache_ttl: u32,
     pub ethereum_adapter_relayer: String,
+    /// Whether to check the `identity` privileges of tokens with the relayer
+    pub use_relayer: bool,
     pub validators_whitelist: Vec<ValidatorId>,
+    /// The adapter of the validator worker, the `--adapter` CLI flag and its options override it.
+    /// It's not exposed with the rest of the config by the Sentry.
+    #[serde(skip_serializing)]
+    pub adapter: Option<AdapterConfig>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestsRateLimit {
+    /// The maximum number of requests in a burst, i.e. the capacity of the bucket
+    pub burst: u32,
+    /// The number of requests (tokens) added back to the bucket every second,
+    /// it can't be `0`, since an empty bucket would never be refilled
+    pub per_second: NonZeroU32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AdapterConfig {
+    /// The password of the keystore is always taken from the `KEYSTORE_PWD` environment variable
+    Ethereum {
+        keystore_file: String,
+    },
+    Dummy {
+        dummy_identity: ValidatorId,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
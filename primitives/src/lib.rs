@@ -26,6 +26,7 @@ pub mod util {
     pub mod tests {
         use slog::{o, Discard, Drain, Logger};
 
+        pub mod fixtures;
         pub mod prep_db;
         pub mod time;
 
@@ -37,16 +38,17 @@ pub mod util {
     }
 
     pub mod logging;
+    pub mod serde;
 }
 pub mod analytics;
 mod eth_checksum;
 pub mod validator;
 
-pub use self::ad_slot::AdSlot;
+pub use self::ad_slot::{match_units, AdSlot};
 pub use self::ad_unit::AdUnit;
 pub use self::balances_map::BalancesMap;
 pub use self::big_num::BigNum;
-pub use self::channel::{Channel, ChannelId, ChannelSpec, SpecValidator, SpecValidators};
+pub use self::channel::{Channel, ChannelId, ChannelSpec, Role, SpecValidator, SpecValidators};
 pub use self::config::Config;
 pub use self::event_submission::EventSubmission;
 pub use self::ipfs::IPFS;
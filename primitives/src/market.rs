@@ -26,6 +26,36 @@ pub enum StatusType {
     Withdraw,
 }
 
+impl StatusType {
+    pub const ALL: [StatusType; 12] = [
+        StatusType::Active,
+        StatusType::Ready,
+        StatusType::Pending,
+        StatusType::Initializing,
+        StatusType::Waiting,
+        StatusType::Offline,
+        StatusType::Disconnected,
+        StatusType::Unhealthy,
+        StatusType::Invalid,
+        StatusType::Expired,
+        StatusType::Exhausted,
+        StatusType::Withdraw,
+    ];
+
+    /// Whether the ads of the campaign can be shown: it's `Active` or `Ready`
+    pub fn is_active(&self) -> bool {
+        matches!(self, StatusType::Active | StatusType::Ready)
+    }
+
+    /// Whether the campaign will never become active again: it's `Expired`, `Exhausted` or in `Withdraw`
+    pub fn is_finalized(&self) -> bool {
+        matches!(
+            self,
+            StatusType::Expired | StatusType::Exhausted | StatusType::Withdraw
+        )
+    }
+}
+
 impl fmt::Display for StatusType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Debug::fmt(self, f)
@@ -58,6 +88,28 @@ pub struct Campaign {
     pub status: Status,
 }
 
+/// Keeps the campaigns whose `min_per_impression` meets the `price_floor`
+/// and sorts them by `max_per_impression`, highest first.
+/// Campaigns with the same `max_per_impression` keep their original order.
+pub fn campaigns_by_price<'a>(
+    campaigns: &'a [Campaign],
+    price_floor: &BigNum,
+) -> Vec<&'a Campaign> {
+    let mut filtered = campaigns
+        .iter()
+        .filter(|campaign| &campaign.channel.spec.min_per_impression >= price_floor)
+        .collect::<Vec<_>>();
+
+    filtered.sort_by(|a, b| {
+        b.channel
+            .spec
+            .max_per_impression
+            .cmp(&a.channel.spec.max_per_impression)
+    });
+
+    filtered
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(
     rename_all = "camelCase",
@@ -198,7 +250,7 @@ mod ad_slot {
     use serde::{Deserialize, Serialize};
     use url::Url;
 
-    use crate::{targeting::Rule, AdSlot, BigNum, ValidatorId};
+    use crate::{targeting::Rule, AdSlot, BigNum, ValidatorId, IPFS};
 
     #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
     #[serde(rename_all = "camelCase")]
@@ -245,6 +297,8 @@ mod ad_slot {
         #[serde(default)]
         pub rules: Vec<Rule>,
         #[serde(default)]
+        pub blacklisted_units: Vec<IPFS>,
+        #[serde(default)]
         pub fallback_unit: Option<String>,
         pub owner: ValidatorId,
         /// DateTime uses `RFC 3339` by default
@@ -272,6 +326,7 @@ mod ad_slot {
                 ad_type: ad_slot.ad_type,
                 min_per_impression: ad_slot.min_per_impression,
                 rules: ad_slot.rules,
+                blacklisted_units: ad_slot.blacklisted_units,
                 fallback_unit: ad_slot.fallback_unit,
                 owner: ad_slot.owner,
                 created: ad_slot.created,
@@ -291,6 +346,7 @@ mod ad_slot {
                 ad_type: self.ad_type,
                 min_per_impression: self.min_per_impression,
                 rules: self.rules,
+                blacklisted_units: self.blacklisted_units,
                 fallback_unit: self.fallback_unit,
                 owner: self.owner,
                 created: self.created,
@@ -303,3 +359,109 @@ mod ad_slot {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::util::tests::prep_db::DUMMY_CHANNEL;
+    use crate::ChannelId;
+
+    fn get_campaign(id: u8, min_per_impression: u64, max_per_impression: u64) -> Campaign {
+        let mut channel = DUMMY_CHANNEL.clone();
+        channel.id = ChannelId::from([id; 32]);
+        channel.spec.min_per_impression = min_per_impression.into();
+        channel.spec.max_per_impression = max_per_impression.into();
+
+        Campaign {
+            channel,
+            status: Status {
+                status_type: StatusType::Active,
+                usd_estimate: None,
+                balances: Default::default(),
+                last_checked: Utc::now(),
+            },
+        }
+    }
+
+    #[test]
+    fn campaigns_are_filtered_and_sorted_by_price() {
+        let campaigns = vec![
+            get_campaign(1, 5, 10),
+            get_campaign(2, 1, 100),
+            get_campaign(3, 10, 30),
+            get_campaign(4, 3, 30),
+            get_campaign(5, 20, 20),
+        ];
+
+        let ids = |price_floor: u64| {
+            campaigns_by_price(&campaigns, &price_floor.into())
+                .into_iter()
+                .map(|campaign| campaign.channel.id)
+                .collect::<Vec<_>>()
+        };
+
+        // all campaigns are kept, sorted by `max_per_impression` and
+        // the ones with equal `max_per_impression` keep their order
+        assert_eq!(
+            vec![
+                ChannelId::from([2; 32]),
+                ChannelId::from([3; 32]),
+                ChannelId::from([4; 32]),
+                ChannelId::from([5; 32]),
+                ChannelId::from([1; 32]),
+            ],
+            ids(1)
+        );
+
+        // the floor is inclusive
+        assert_eq!(
+            vec![
+                ChannelId::from([3; 32]),
+                ChannelId::from([5; 32]),
+                ChannelId::from([1; 32]),
+            ],
+            ids(5)
+        );
+
+        assert_eq!(vec![ChannelId::from([5; 32])], ids(11));
+        assert!(ids(21).is_empty());
+    }
+
+    #[test]
+    fn status_type_serde_round_trip() {
+        for status_type in StatusType::ALL.iter() {
+            let json = serde_json::to_string(status_type).expect("Should serialize");
+            assert_eq!(format!(r#""{}""#, status_type), json);
+
+            let deserialized: StatusType = serde_json::from_str(&json).expect("Should deserialize");
+            assert_eq!(status_type, &deserialized);
+        }
+
+        assert!(serde_json::from_str::<StatusType>(r#""Closed""#).is_err());
+    }
+
+    #[test]
+    fn status_type_classification() {
+        let active = StatusType::ALL
+            .iter()
+            .filter(|status_type| status_type.is_active())
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(vec![StatusType::Active, StatusType::Ready], active);
+
+        let finalized = StatusType::ALL
+            .iter()
+            .filter(|status_type| status_type.is_finalized())
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                StatusType::Expired,
+                StatusType::Exhausted,
+                StatusType::Withdraw
+            ],
+            finalized
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::{BalancesMap, BigNum, ChannelId, ValidatorId};
 use merkletree::{hash::Algorithm, merkle, merkle::VecStore, proof::Proof};
 use std::fmt;
 use std::hash::Hasher;
@@ -79,6 +80,7 @@ type ExternalMerkleTree =
 
 #[derive(Debug, Clone)]
 enum Tree {
+    Empty,
     SingleItem(MerkleItem),
     MerkleTree(ExternalMerkleTree),
 }
@@ -87,8 +89,15 @@ enum Tree {
 pub enum Error {
     #[error("No leaves were provided")]
     ZeroLeaves,
+    #[error("Balance amount does not fit in 256 bits")]
+    AmountOverflow,
 }
 
+/// The leaves are sorted and deduplicated, then every node is the hash of its sorted children.
+///
+/// The edge cases:
+/// - without any leaves, the root is the zero hash
+/// - with a single leaf, the root is the leaf itself
 #[derive(Debug)]
 pub struct MerkleTree {
     tree: Tree,
@@ -104,7 +113,7 @@ impl MerkleTree {
         leaves.dedup_by(|a, b| a == b);
 
         let tree = match leaves.len() {
-            0 => return Err(Error::ZeroLeaves),
+            0 => Tree::Empty,
             // should never `panic!`, we have a single leaf after all
             1 => Tree::SingleItem(leaves.remove(0)),
             _ => {
@@ -115,6 +124,7 @@ impl MerkleTree {
         };
 
         let root: MerkleItem = match &tree {
+            Tree::Empty => [0; 32],
             Tree::SingleItem(root) => root.to_owned(),
             Tree::MerkleTree(merkletree) => merkletree.root(),
         };
@@ -133,7 +143,7 @@ impl MerkleTree {
 
     pub fn proof(&self, i: usize) -> (Vec<MerkleItem>, Vec<bool>) {
         match &self.tree {
-            Tree::SingleItem(_) => (vec![], vec![]),
+            Tree::Empty | Tree::SingleItem(_) => (vec![], vec![]),
             Tree::MerkleTree(merkle) => {
                 let proof = merkle.gen_proof(i);
                 let path = proof.path();
@@ -144,15 +154,122 @@ impl MerkleTree {
     }
 }
 
+fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut keccak = Keccak::new_keccak256();
+    keccak.update(data);
+
+    let mut hash = [0_u8; 32];
+    keccak.finalize(&mut hash);
+
+    hash
+}
+
+/// The ABI encoded `(address, uint256)` of the balance hashed with keccak256,
+/// i.e. `keccak256(abi.encode(address, amount))` of the contract.
+///
+/// The 64 bytes which are hashed:
+///
+/// - `0..12`: zeroes
+/// - `12..32`: the 20 bytes of the `address`
+/// - `32..64`: the big-endian `amount`, left-padded with zeroes
+///
+/// Note: this is `abi.encode` and not `abi.encodePacked`, i.e. the `address` is padded to 32 bytes
+/// as well, otherwise the leaves won't match the ones the withdrawals are verified against.
+///
+/// Errors with [`Error::AmountOverflow`] if the `amount` doesn't fit in an `uint256`.
+pub fn balance_leaf(address: &ValidatorId, amount: &BigNum) -> Result<MerkleItem, Error> {
+    let amount_bytes = amount.to_bytes_be();
+    if amount_bytes.len() > 32 {
+        return Err(Error::AmountOverflow);
+    }
+
+    let mut encoded = [0_u8; 64];
+    // `address` is left-padded to 32 bytes
+    encoded[12..32].copy_from_slice(address.inner());
+    // `uint256` is big-endian and left-padded to 32 bytes
+    encoded[64 - amount_bytes.len()..].copy_from_slice(&amount_bytes);
+
+    Ok(keccak256(&encoded))
+}
+
+/// Computes the signable state root, i.e. `keccak256(channelId, balanceRoot)`,
+/// where `balanceRoot` is the root of the `MerkleTree` of the balance leaves.
+///
+/// Empty balances have nothing to sign, so they result in [`Error::ZeroLeaves`].
+pub fn state_root(channel_id: &ChannelId, balances: &BalancesMap) -> Result<[u8; 32], Error> {
+    if balances.is_empty() {
+        return Err(Error::ZeroLeaves);
+    }
+
+    // Note: MerkleTree takes care of deduplicating and sorting
+    let leaves = balances
+        .iter()
+        .map(|(address, amount)| balance_leaf(address, amount))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let tree = MerkleTree::new(&leaves)?;
+
+    Ok(signable_state_root(channel_id, &tree.root()))
+}
+
+/// Hashes the `channel_id` together with the `balance_root`, i.e. `keccak256(abi.encode(channelId, balanceRoot))`
+/// of the contract, where both are `bytes32`.
+pub fn signable_state_root(channel_id: &[u8; 32], balance_root: &[u8; 32]) -> [u8; 32] {
+    let mut encoded = [0_u8; 64];
+    encoded[..32].copy_from_slice(channel_id);
+    encoded[32..].copy_from_slice(balance_root);
+
+    keccak256(&encoded)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use hex::FromHex;
 
     #[test]
-    fn it_returns_error_on_zero_leaves() {
-        let error = MerkleTree::new(&[]).expect_err("ZeroLeaves error expected");
-        assert_eq!(Error::ZeroLeaves, error);
+    fn empty_tree_has_the_zero_hash_as_root() {
+        let tree = MerkleTree::new(&[]).expect("Should create an empty MerkleTree");
+
+        assert_eq!([0; 32], tree.root());
+        assert_eq!((vec![], vec![]), tree.proof(0));
+    }
+
+    #[test]
+    fn single_leaf_is_the_root() {
+        let leaf = <[u8; 32]>::from_hex(
+            "71b1b2ad4db89eea341553b718f51f4f0aac03c6a596c4c0e1697f7b9d9da337",
+        )
+        .unwrap();
+
+        let tree = MerkleTree::new(&[leaf]).expect("Should create MerkleTree");
+        assert_eq!(leaf, tree.root());
+        assert_eq!((vec![], vec![]), tree.proof(0));
+
+        // duplicates of the leaf are the same as a single leaf
+        let duplicates = MerkleTree::new(&[leaf, leaf]).expect("Should create MerkleTree");
+        assert_eq!(leaf, duplicates.root());
+    }
+
+    #[test]
+    fn two_leaves_root_is_the_hash_of_the_sorted_leaves() {
+        let h1 = <[u8; 32]>::from_hex(
+            "71b1b2ad4db89eea341553b718f51f4f0aac03c6a596c4c0e1697f7b9d9da337",
+        )
+        .unwrap();
+        let h2 = <[u8; 32]>::from_hex(
+            "778b613574ae22c119efb252f2a56cb05b0d137f8494c0193f4e015c49f43453",
+        )
+        .unwrap();
+
+        let expected = keccak256(&[h1, h2].concat());
+
+        let tree = MerkleTree::new(&[h1, h2]).expect("Should create MerkleTree");
+        assert_eq!(expected, tree.root());
+
+        // the order of the leaves doesn't matter
+        let reversed = MerkleTree::new(&[h2, h1]).expect("Should create MerkleTree");
+        assert_eq!(expected, reversed.root());
     }
 
     #[test]
@@ -239,4 +356,134 @@ mod test {
 
         assert_eq!(verify, true, "should verify proof successfully");
     }
+
+    #[test]
+    fn state_root_is_stable_and_aligned_with_js_impl() {
+        use crate::util::tests::prep_db::{DUMMY_CHANNEL, IDS};
+
+        let balances: BalancesMap = vec![(IDS["publisher"], 1.into()), (IDS["tester"], 2.into())]
+            .into_iter()
+            .collect();
+
+        let root = state_root(&DUMMY_CHANNEL.id, &balances).expect("Should get state root");
+
+        assert_eq!(
+            "d6c784be61c4d2c47a52cc72af6c133d24b163ad053ac7f0a65091001f43dda1",
+            hex::encode(root)
+        );
+
+        let zero_balance: BalancesMap = vec![(IDS["publisher"], 0.into())].into_iter().collect();
+        let root = state_root(&DUMMY_CHANNEL.id, &zero_balance).expect("Should get state root");
+
+        assert_eq!(
+            "4fad5375c3ef5f8a9d23a8276fed0151164dea72a5891cec8b43e1d190ed430e",
+            hex::encode(root)
+        );
+    }
+
+    #[test]
+    fn state_root_errors_on_empty_balances_and_overflowing_amounts() {
+        use crate::util::tests::prep_db::{DUMMY_CHANNEL, IDS};
+
+        assert_eq!(
+            Err(Error::ZeroLeaves),
+            state_root(&DUMMY_CHANNEL.id, &BalancesMap::default())
+        );
+
+        let overflowing = BigNum::from_bytes_be(&[1_u8; 33]);
+        assert_eq!(
+            Err(Error::AmountOverflow),
+            balance_leaf(&IDS["publisher"], &overflowing)
+        );
+    }
+
+    /// The balance sets are in `test/resources/fixtures/outpace_state_roots.json`, with the roots
+    /// computed as `MerkleTree.js`, `getBalanceLeaf` and `getSignableStateRoot` of `adex-protocol-eth`,
+    /// which the OUTPACE contract verifies the withdrawals against:
+    /// sorted and deduplicated leaves, sorted pairs and the odd node moved up to the next layer.
+    /// The 2 leaves set is the one of `state_root_is_stable_and_aligned_with_js_impl`.
+    ///
+    /// They cover 1 to 7 leaves, i.e. odd layers at different heights of the tree,
+    /// as well as zero and `uint256::MAX` amounts.
+    #[test]
+    fn state_root_conforms_to_the_outpace_vectors() {
+        use crate::util::tests::fixtures::load_fixture;
+        use serde::Deserialize;
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct StateRootVector {
+            channel_id: ChannelId,
+            balances: BalancesMap,
+            balance_root: String,
+            state_root: String,
+        }
+
+        let vectors = load_fixture::<Vec<StateRootVector>>("outpace_state_roots")
+            .expect("Should load the OUTPACE vectors");
+        assert_eq!(7, vectors.len());
+
+        for vector in vectors {
+            let leaves = vector
+                .balances
+                .iter()
+                .map(|(address, amount)| balance_leaf(address, amount))
+                .collect::<Result<Vec<_>, _>>()
+                .expect("Should encode the balance leaves");
+            let tree = MerkleTree::new(&leaves).expect("Should create MerkleTree");
+
+            assert_eq!(
+                vector.balance_root,
+                hex::encode(tree.root()),
+                "balance root of {} leaves",
+                leaves.len()
+            );
+            assert_eq!(
+                vector.state_root,
+                hex::encode(
+                    state_root(&vector.channel_id, &vector.balances)
+                        .expect("Should get state root")
+                ),
+                "state root of {} leaves",
+                leaves.len()
+            );
+        }
+    }
+
+    #[test]
+    fn balance_leaf_is_the_hash_of_the_abi_encoded_balance() {
+        use crate::util::tests::prep_db::IDS;
+
+        let cases = vec![
+            (
+                IDS["publisher"],
+                BigNum::from(1),
+                "c498fcc3d2068b1a3222421df5d76a3f4eb6c0838c27de30cccaa381de47dc10",
+            ),
+            (
+                IDS["tester"],
+                BigNum::from(1_000_000_000_000_000_000_u64),
+                "6e0cb8c6547d33eea82493c469521cf710a9eb561b0f17cb1c321662cf028f60",
+            ),
+            (
+                IDS["leader"],
+                BigNum::from(0),
+                "e2f2e599d1a2b755d671737c66712489c7ecd9d62737293539975f0e779ac60b",
+            ),
+        ];
+
+        for (address, amount, expected) in cases {
+            let leaf = balance_leaf(&address, &amount).expect("Should encode the balance leaf");
+            assert_eq!(expected, hex::encode(leaf));
+        }
+
+        // the packed encoding, i.e. the address without the padding, is a different leaf
+        let mut packed = IDS["publisher"].inner().to_vec();
+        packed.extend_from_slice(&[0_u8; 31]);
+        packed.push(1);
+        assert_ne!(
+            keccak256(&packed),
+            balance_leaf(&IDS["publisher"], &BigNum::from(1)).expect("Should encode")
+        );
+    }
 }
@@ -87,7 +87,7 @@ pub struct Earner {
     pub promilles: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EventAggregate {
     pub channel_id: ChannelId,
@@ -112,6 +112,17 @@ pub struct ChannelListResponse {
     pub page: u64,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChannelBatchRequest {
+    pub ids: Vec<ChannelId>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChannelBatchResponse {
+    /// The channels which exist, in no particular order
+    pub channels: Vec<Channel>,
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct LastApprovedResponse {
@@ -154,13 +165,65 @@ pub struct ValidationErrorResponse {
     pub validation: Vec<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct AdvancedAnalyticsResponse {
     pub by_channel_stats: HashMap<ChannelId, HashMap<ChannelReport, HashMap<String, f64>>>,
     pub publisher_stats: HashMap<PublisherReport, HashMap<String, f64>>,
 }
 
+/// A single stat of an advanced analytics report, e.g. the value for a given AdUnit or hostname
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdvancedAnalyticsRow {
+    Channel {
+        channel_id: ChannelId,
+        report: ChannelReport,
+        key: String,
+        value: f64,
+    },
+    Publisher {
+        report: PublisherReport,
+        key: String,
+        value: f64,
+    },
+}
+
+impl AdvancedAnalyticsResponse {
+    /// Groups the rows by `ChannelId` and report.
+    /// If the same key is found more than once for a report, the last value is used.
+    pub fn from_rows(rows: impl IntoIterator<Item = AdvancedAnalyticsRow>) -> Self {
+        let mut response = Self::default();
+
+        for row in rows {
+            match row {
+                AdvancedAnalyticsRow::Channel {
+                    channel_id,
+                    report,
+                    key,
+                    value,
+                } => {
+                    response
+                        .by_channel_stats
+                        .entry(channel_id)
+                        .or_default()
+                        .entry(report)
+                        .or_default()
+                        .insert(key, value);
+                }
+                AdvancedAnalyticsRow::Publisher { report, key, value } => {
+                    response
+                        .publisher_stats
+                        .entry(report)
+                        .or_default()
+                        .insert(key, value);
+                }
+            }
+        }
+
+        response
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Hash, PartialEq, Eq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum PublisherReport {
@@ -194,7 +257,7 @@ pub enum ChannelReport {
 impl fmt::Display for ChannelReport {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
-            ChannelReport::AdUnit => write!(f, "reportPublisherToAdUnit"),
+            ChannelReport::AdUnit => write!(f, "reportChannelToAdUnit"),
             ChannelReport::Hostname => write!(f, "reportChannelToHostname"),
             ChannelReport::HostnamePay => write!(f, "reportChannelToHostnamePay"),
         }
@@ -306,3 +369,108 @@ mod postgres {
         to_sql_checked!();
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::tests::prep_db::DUMMY_CHANNEL;
+
+    #[test]
+    fn advanced_analytics_response_from_rows_groups_the_stats() {
+        let other_channel_id = ChannelId::from([1_u8; 32]);
+
+        let rows = vec![
+            AdvancedAnalyticsRow::Channel {
+                channel_id: DUMMY_CHANNEL.id,
+                report: ChannelReport::AdUnit,
+                key: "unit_1".to_string(),
+                value: 10.0,
+            },
+            AdvancedAnalyticsRow::Channel {
+                channel_id: DUMMY_CHANNEL.id,
+                report: ChannelReport::AdUnit,
+                key: "unit_2".to_string(),
+                value: 5.0,
+            },
+            AdvancedAnalyticsRow::Channel {
+                channel_id: DUMMY_CHANNEL.id,
+                report: ChannelReport::Hostname,
+                key: "adex.network".to_string(),
+                value: 3.0,
+            },
+            AdvancedAnalyticsRow::Channel {
+                channel_id: other_channel_id,
+                report: ChannelReport::HostnamePay,
+                key: "adex.network".to_string(),
+                value: 1.5,
+            },
+            AdvancedAnalyticsRow::Publisher {
+                report: PublisherReport::Country,
+                key: "BG".to_string(),
+                value: 2.0,
+            },
+        ];
+
+        let response = AdvancedAnalyticsResponse::from_rows(rows);
+
+        assert_eq!(2, response.by_channel_stats.len());
+
+        let dummy_channel_stats = &response.by_channel_stats[&DUMMY_CHANNEL.id];
+        assert_eq!(2, dummy_channel_stats.len());
+        assert_eq!(2, dummy_channel_stats[&ChannelReport::AdUnit].len());
+        assert_eq!(
+            Some(&5.0),
+            dummy_channel_stats[&ChannelReport::AdUnit].get("unit_2")
+        );
+        assert_eq!(
+            Some(&3.0),
+            dummy_channel_stats[&ChannelReport::Hostname].get("adex.network")
+        );
+
+        let other_channel_stats = &response.by_channel_stats[&other_channel_id];
+        assert_eq!(
+            Some(&1.5),
+            other_channel_stats[&ChannelReport::HostnamePay].get("adex.network")
+        );
+
+        assert_eq!(1, response.publisher_stats.len());
+        assert_eq!(
+            Some(&2.0),
+            response.publisher_stats[&PublisherReport::Country].get("BG")
+        );
+    }
+
+    #[test]
+    fn report_display_strings() {
+        assert_eq!("reportChannelToAdUnit", ChannelReport::AdUnit.to_string());
+        assert_eq!(
+            "reportChannelToHostname",
+            ChannelReport::Hostname.to_string()
+        );
+        assert_eq!(
+            "reportChannelToHostnamePay",
+            ChannelReport::HostnamePay.to_string()
+        );
+
+        assert_eq!(
+            "reportPublisherToAdUnit",
+            PublisherReport::AdUnit.to_string()
+        );
+        assert_eq!(
+            "reportPublisherToAdSlot",
+            PublisherReport::AdSlot.to_string()
+        );
+        assert_eq!(
+            "reportPublisherToAdSlotPay",
+            PublisherReport::AdSlotPay.to_string()
+        );
+        assert_eq!(
+            "reportPublisherToCountry",
+            PublisherReport::Country.to_string()
+        );
+        assert_eq!(
+            "reportPublisherToHostname",
+            PublisherReport::Hostname.to_string()
+        );
+    }
+}
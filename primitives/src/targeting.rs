@@ -38,6 +38,7 @@ pub struct Output {
     pub show: bool,
     /// The boost is a number between 0 and 5 that increases the likelyhood for the ad
     /// to be chosen if there is random selection applied on the AdView (multiple ad candidates with the same price)
+    /// A boost of 0 disables the ad, see [`Output::boosted_score`]
     /// Default: 1.0
     pub boost: f64,
     /// price.{eventType}
@@ -48,6 +49,19 @@ pub struct Output {
 }
 
 impl Output {
+    /// The lower bound of the `boost` applied to a score
+    pub const MIN_BOOST: f64 = 0.0;
+    /// The upper bound of the `boost` applied to a score
+    pub const MAX_BOOST: f64 = 5.0;
+
+    /// Multiplies the `score` by the `boost`, clamped between [`Output::MIN_BOOST`] and [`Output::MAX_BOOST`].
+    /// An invalid (`NaN`) boost is treated as [`Output::MIN_BOOST`].
+    pub fn boosted_score(&self, score: f64) -> f64 {
+        let boost = self.boost.max(Self::MIN_BOOST).min(Self::MAX_BOOST);
+
+        score * boost
+    }
+
     fn try_get(&self, key: &str) -> Result<Value, Error> {
         match key {
             "show" => Ok(Value::Bool(self.show)),
@@ -110,6 +124,23 @@ mod test {
         assert_eq!(Err(Error::UnknownVariable), output.try_get("unknown"));
     }
 
+    #[test]
+    fn boost_is_applied_to_the_score() {
+        let output = |boost| Output {
+            show: true,
+            boost,
+            price: Default::default(),
+        };
+
+        assert_eq!(100.0, output(1.0).boosted_score(100.0));
+        assert_eq!(200.0, output(2.0).boosted_score(100.0));
+        assert_eq!(0.0, output(0.0).boosted_score(100.0));
+        // clamped to the bounds
+        assert_eq!(500.0, output(10.0).boosted_score(100.0));
+        assert_eq!(0.0, output(-1.0).boosted_score(100.0));
+        assert_eq!(0.0, output(f64::NAN).boosted_score(100.0));
+    }
+
     #[test]
     fn test_output_from_channel() {
         use crate::channel::{Pricing, PricingBounds};
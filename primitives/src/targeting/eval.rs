@@ -1,5 +1,6 @@
-use crate::BigNum;
+use crate::{BigNum, DomainError};
 use lazy_static::lazy_static;
+use num::CheckedSub;
 use serde::{Deserialize, Serialize};
 use serde_json::{value::Value as SerdeValue, Number};
 use std::{
@@ -13,7 +14,7 @@ use std::{
 pub use rules::Rules;
 
 use super::{
-    input::{channel::Getter as ChannelGetter, Get},
+    input::{channel::Getter as ChannelGetter, field::Field, Get},
     Input, Output,
 };
 
@@ -29,6 +30,9 @@ pub enum Error {
 pub const DAI_ADDR: &str = "0x89d24A6b4CcB1B6fAA2625fE562bDD9a23260359";
 pub const USDT_ADDR: &str = "0xdac17f958d2ee523a2206206994597c13d831ec7";
 pub const USDC_ADDR: &str = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+/// The maximum bit length of the result of `Function::Pow`,
+/// since the rules come from the users and a huge exponent would allocate gigabytes
+pub const MAX_POW_BITS: u64 = 1024;
 
 lazy_static! {
     pub static ref DEPOSIT_ASSETS_MAP: HashMap<String, BigNum> = {
@@ -42,24 +46,81 @@ lazy_static! {
 }
 
 trait Eval {
-    fn eval(self, input: &Input, output: &mut Output) -> Result<Option<Value>, Error>;
+    fn eval_with_cache(
+        self,
+        input: &Input,
+        output: &mut Output,
+        cache: &mut EvalCache,
+    ) -> Result<Option<Value>, Error>;
+
+    fn eval(self, input: &Input, output: &mut Output) -> Result<Option<Value>, Error>
+    where
+        Self: Sized,
+    {
+        self.eval_with_cache(input, output, &mut EvalCache::default())
+    }
 }
 
 impl Eval for Value {
-    fn eval(self, input: &Input, output: &mut Output) -> Result<Option<Value>, Error> {
-        eval(input, output, &Rule::Value(self))
+    fn eval_with_cache(
+        self,
+        input: &Input,
+        output: &mut Output,
+        cache: &mut EvalCache,
+    ) -> Result<Option<Value>, Error> {
+        eval(input, output, &Rule::Value(self), cache)
     }
 }
 
 impl Eval for Function {
-    fn eval(self, input: &Input, output: &mut Output) -> Result<Option<Value>, Error> {
-        eval(input, output, &Rule::Function(self))
+    fn eval_with_cache(
+        self,
+        input: &Input,
+        output: &mut Output,
+        cache: &mut EvalCache,
+    ) -> Result<Option<Value>, Error> {
+        eval(input, output, &Rule::Function(self), cache)
     }
 }
 
 impl Eval for &Rule {
-    fn eval(self, input: &Input, output: &mut Output) -> Result<Option<Value>, Error> {
-        eval(input, output, self)
+    fn eval_with_cache(
+        self,
+        input: &Input,
+        output: &mut Output,
+        cache: &mut EvalCache,
+    ) -> Result<Option<Value>, Error> {
+        eval(input, output, self, cache)
+    }
+}
+
+/// Memoizes the pure sub-rules while evaluating a single `Rule`.
+///
+/// Only `Function::Get` of `Input` variables is cached,
+/// since `Output` variables can be changed by `Function::Set` during the evaluation.
+/// The rest of the pure functions (e.g. `Function::Bn`) are cheaper to evaluate than to cache.
+#[derive(Debug, Default)]
+struct EvalCache {
+    input_values: HashMap<String, Value>,
+    /// How many times the `Input` was queried for a variable
+    #[cfg(test)]
+    input_lookups: usize,
+}
+
+impl EvalCache {
+    fn get_input(&mut self, input: &Input, key: &str) -> Result<Value, Error> {
+        if let Some(value) = self.input_values.get(key) {
+            return Ok(value.clone());
+        }
+
+        #[cfg(test)]
+        {
+            self.input_lookups += 1;
+        }
+        let value = input.try_get(key)?;
+        self.input_values.insert(key.to_string(), value.clone());
+
+        Ok(value)
     }
 }
 
@@ -159,7 +220,21 @@ pub enum Rule {
 
 impl Rule {
     pub fn eval(&self, input: &Input, output: &mut Output) -> Result<Option<Value>, Error> {
-        eval(input, output, self)
+        eval(input, output, self, &mut EvalCache::default())
+    }
+
+    /// Parses a `Rule` from its JSON representation, see [`Rule::from_str`]
+    pub fn from_json(json: &str) -> Result<Self, DomainError> {
+        json.parse()
+    }
+}
+
+impl FromStr for Rule {
+    type Err = DomainError;
+
+    fn from_str(json: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(json)
+            .map_err(|err| DomainError::InvalidArgument(format!("Invalid rule: {}", err)))
     }
 }
 
@@ -219,9 +294,11 @@ impl Into<SerdeValue> for Value {
     }
 }
 
+/// Functions are (de)serialized in the same format as the JS implementation:
+/// - functions with multiple arguments - `{"div": [lhs, rhs]}`
+/// - functions with a single argument - `{"get": "country"}` or `{"onlyShowIf": rule}`
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
-// TODO: https://github.com/AdExNetwork/adex-validator-stack-rust/issues/296
 pub enum Function {
     /// Multiplies first two values and then divides product by third value
     MulDiv(Box<Rule>, Box<Rule>, Box<Rule>),
@@ -232,9 +309,15 @@ pub enum Function {
     Sub(Box<Rule>, Box<Rule>),
     Max(Box<Rule>, Box<Rule>),
     Min(Box<Rule>, Box<Rule>),
+    /// Raises the first value (a `BigNum`) to the power of the second value (a `u32` number),
+    /// the result can have at most [`MAX_POW_BITS`] bits
+    Pow(Box<Rule>, Box<Rule>),
+    /// The integer square root (floor) of a `BigNum` value
+    Sqrt(Box<Rule>),
     If(Box<Rule>, Box<Rule>),
     IfNot(Box<Rule>, Box<Rule>),
     IfElse(Box<Rule>, Box<Rule>, Box<Rule>),
+    /// Short-circuits: the second value is not evaluated if the first value is `false`
     And(Box<Rule>, Box<Rule>),
     Or(Box<Rule>, Box<Rule>),
     Xor(Box<Rule>, Box<Rule>),
@@ -251,9 +334,12 @@ pub enum Function {
     Eq(Box<Rule>, Box<Rule>),
     /// Are values NOT equal
     Neq(Box<Rule>, Box<Rule>),
-    /// Is first value included in an array (second value)
+    /// Is the second value included in the array (first value),
+    /// e.g. `{"in": [["us", "ca"], {"get": "country"}]}`
+    ///
+    /// The first value must be an array, an empty one never includes the value
     In(Box<Rule>, Box<Rule>),
-    /// Is first value NOT included in an array (second value)
+    /// Is the second value NOT included in the array (first value)
     Nin(Box<Rule>, Box<Rule>),
     /// Gets the element at a certain position (second value) of an array (first value)
     At(Box<Rule>, Box<Rule>),
@@ -323,6 +409,12 @@ impl Function {
     pub fn new_max(lhs: impl Into<Rule>, rhs: impl Into<Rule>) -> Self {
         Self::Max(Box::new(lhs.into()), Box::new(rhs.into()))
     }
+    pub fn new_pow(base: impl Into<Rule>, exponent: impl Into<Rule>) -> Self {
+        Self::Pow(Box::new(base.into()), Box::new(exponent.into()))
+    }
+    pub fn new_sqrt(value: impl Into<Rule>) -> Self {
+        Self::Sqrt(Box::new(value.into()))
+    }
     pub fn new_lt(lhs: impl Into<Rule>, rhs: impl Into<Rule>) -> Self {
         Self::Lt(Box::new(lhs.into()), Box::new(rhs.into()))
     }
@@ -476,6 +568,31 @@ impl Value {
             _ => Err(Error::TypeError),
         }
     }
+
+    /// Returns the `u64` if the value is a `Value::Number` representable as `u64`
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Number(number) => number.as_u64(),
+            _ => None,
+        }
+    }
+
+    /// Returns the `i64` if the value is a `Value::Number` representable as `i64`
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Number(number) => number.as_i64(),
+            _ => None,
+        }
+    }
+
+    /// Returns the `f64` if the value is a `Value::Number`,
+    /// integers are converted and might lose precision
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(number) => number.as_f64(),
+            _ => None,
+        }
+    }
 }
 
 impl TryFrom<Value> for BigNum {
@@ -502,7 +619,12 @@ impl TryFrom<Value> for BigNum {
 ///     - BigNum
 /// - Mutates output
 /// - Throws an error
-fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>, Error> {
+fn eval(
+    input: &Input,
+    output: &mut Output,
+    rule: &Rule,
+    cache: &mut EvalCache,
+) -> Result<Option<Value>, Error> {
     let function = match rule {
         Rule::Value(value) => return Ok(Some(value.clone())),
         Rule::Function(function) => function,
@@ -512,15 +634,19 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
     let value = match function {
         Function::MulDiv(first_rule, second_rule, third_rule) => {
             let product = Function::Mul(first_rule.clone(), second_rule.clone())
-                .eval(input, output)?
+                .eval_with_cache(input, output, cache)?
                 .ok_or(Error::TypeError)?;
             let product_rule = Rule::Value(product);
             let boxed_rule = Box::new(product_rule);
-            Function::Div(boxed_rule, third_rule.clone()).eval(input, output)?
+            Function::Div(boxed_rule, third_rule.clone()).eval_with_cache(input, output, cache)?
         }
         Function::Div(first_rule, second_rule) => {
-            let first_eval = first_rule.eval(input, output)?.ok_or(Error::TypeError)?;
-            let second_eval = second_rule.eval(input, output)?.ok_or(Error::TypeError)?;
+            let first_eval = first_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
+            let second_eval = second_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
 
             let value = match (first_eval, second_eval) {
                 (Value::BigNum(bignum), second_value) => {
@@ -533,8 +659,8 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
 
                     Value::BigNum(lhs_bignum.div(rhs_bignum))
                 }
-                (Value::Number(lhs), Value::Number(rhs)) => {
-                    Value::Number(math_operator(lhs, rhs, MathOperator::Division)?)
+                (lhs @ Value::Number(_), rhs @ Value::Number(_)) => {
+                    Value::Number(math_operator(&lhs, &rhs, MathOperator::Division)?)
                 }
                 _ => return Err(Error::TypeError),
             };
@@ -542,8 +668,12 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
             Some(value)
         }
         Function::Mul(first_rule, second_rule) => {
-            let first_eval = first_rule.eval(input, output)?.ok_or(Error::TypeError)?;
-            let second_eval = second_rule.eval(input, output)?.ok_or(Error::TypeError)?;
+            let first_eval = first_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
+            let second_eval = second_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
 
             let value = match (first_eval, second_eval) {
                 (Value::BigNum(bignum), rhs_value) => {
@@ -556,8 +686,8 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
 
                     Value::BigNum(lhs_bignum.mul(rhs_bignum))
                 }
-                (Value::Number(lhs), Value::Number(rhs)) => {
-                    Value::Number(math_operator(lhs, rhs, MathOperator::Multiplication)?)
+                (lhs @ Value::Number(_), rhs @ Value::Number(_)) => {
+                    Value::Number(math_operator(&lhs, &rhs, MathOperator::Multiplication)?)
                 }
                 _ => return Err(Error::TypeError),
             };
@@ -565,8 +695,12 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
             Some(value)
         }
         Function::Mod(first_rule, second_rule) => {
-            let first_eval = first_rule.eval(input, output)?.ok_or(Error::TypeError)?;
-            let second_eval = second_rule.eval(input, output)?.ok_or(Error::TypeError)?;
+            let first_eval = first_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
+            let second_eval = second_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
 
             let value = match (first_eval, second_eval) {
                 (Value::BigNum(bignum), rhs_value) => {
@@ -579,8 +713,8 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
 
                     Value::BigNum(lhs_bignum.rem(rhs_bignum))
                 }
-                (Value::Number(lhs), Value::Number(rhs)) => {
-                    Value::Number(math_operator(lhs, rhs, MathOperator::Modulus)?)
+                (lhs @ Value::Number(_), rhs @ Value::Number(_)) => {
+                    Value::Number(math_operator(&lhs, &rhs, MathOperator::Modulus)?)
                 }
                 _ => return Err(Error::TypeError),
             };
@@ -588,8 +722,12 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
             Some(value)
         }
         Function::Add(first_rule, second_rule) => {
-            let first_eval = first_rule.eval(input, output)?.ok_or(Error::TypeError)?;
-            let second_eval = second_rule.eval(input, output)?.ok_or(Error::TypeError)?;
+            let first_eval = first_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
+            let second_eval = second_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
 
             let value = match (first_eval, second_eval) {
                 (Value::BigNum(bignum), rhs_value) => {
@@ -602,8 +740,8 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
 
                     Value::BigNum(lhs_bignum.add(rhs_bignum))
                 }
-                (Value::Number(lhs), Value::Number(rhs)) => {
-                    Value::Number(math_operator(lhs, rhs, MathOperator::Addition)?)
+                (lhs @ Value::Number(_), rhs @ Value::Number(_)) => {
+                    Value::Number(math_operator(&lhs, &rhs, MathOperator::Addition)?)
                 }
                 _ => return Err(Error::TypeError),
             };
@@ -611,22 +749,31 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
             Some(value)
         }
         Function::Sub(first_rule, second_rule) => {
-            let first_eval = first_rule.eval(input, output)?.ok_or(Error::TypeError)?;
-            let second_eval = second_rule.eval(input, output)?.ok_or(Error::TypeError)?;
+            let first_eval = first_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
+            let second_eval = second_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
 
             let value = match (first_eval, second_eval) {
                 (Value::BigNum(bignum), rhs_value) => {
                     let rhs_bignum = BigNum::try_from(rhs_value)?;
 
-                    Value::BigNum(bignum.sub(rhs_bignum))
+                    // `BigNum` is unsigned, so it can't go below zero
+                    Value::BigNum(bignum.checked_sub(&rhs_bignum).ok_or(Error::TypeError)?)
                 }
                 (lhs_value, Value::BigNum(rhs_bignum)) => {
                     let lhs_bignum = BigNum::try_from(lhs_value)?;
 
-                    Value::BigNum(lhs_bignum.sub(rhs_bignum))
+                    Value::BigNum(
+                        lhs_bignum
+                            .checked_sub(&rhs_bignum)
+                            .ok_or(Error::TypeError)?,
+                    )
                 }
-                (Value::Number(lhs), Value::Number(rhs)) => {
-                    Value::Number(math_operator(lhs, rhs, MathOperator::Subtraction)?)
+                (lhs @ Value::Number(_), rhs @ Value::Number(_)) => {
+                    Value::Number(math_operator(&lhs, &rhs, MathOperator::Subtraction)?)
                 }
                 _ => return Err(Error::TypeError),
             };
@@ -634,8 +781,12 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
             Some(value)
         }
         Function::Max(first_rule, second_rule) => {
-            let first_eval = first_rule.eval(input, output)?.ok_or(Error::TypeError)?;
-            let second_eval = second_rule.eval(input, output)?.ok_or(Error::TypeError)?;
+            let first_eval = first_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
+            let second_eval = second_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
 
             let value = match (first_eval, second_eval) {
                 (Value::BigNum(bignum), rhs_value) => {
@@ -648,8 +799,8 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
 
                     Value::BigNum(lhs_bignum.max(rhs_bignum))
                 }
-                (Value::Number(lhs), Value::Number(rhs)) => {
-                    Value::Number(math_operator(lhs, rhs, MathOperator::Max)?)
+                (lhs @ Value::Number(_), rhs @ Value::Number(_)) => {
+                    Value::Number(math_operator(&lhs, &rhs, MathOperator::Max)?)
                 }
                 _ => return Err(Error::TypeError),
             };
@@ -657,8 +808,12 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
             Some(value)
         }
         Function::Min(first_rule, second_rule) => {
-            let first_eval = first_rule.eval(input, output)?.ok_or(Error::TypeError)?;
-            let second_eval = second_rule.eval(input, output)?.ok_or(Error::TypeError)?;
+            let first_eval = first_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
+            let second_eval = second_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
 
             let value = match (first_eval, second_eval) {
                 (Value::BigNum(bignum), rhs_value) => {
@@ -671,87 +826,130 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
 
                     Value::BigNum(lhs_bignum.min(rhs_bignum))
                 }
-                (Value::Number(lhs), Value::Number(rhs)) => {
-                    Value::Number(math_operator(lhs, rhs, MathOperator::Min)?)
+                (lhs @ Value::Number(_), rhs @ Value::Number(_)) => {
+                    Value::Number(math_operator(&lhs, &rhs, MathOperator::Min)?)
                 }
                 _ => return Err(Error::TypeError),
             };
 
             Some(value)
         }
+        Function::Pow(base_rule, exponent_rule) => {
+            let base = base_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?
+                .try_bignum()?;
+            let exponent = exponent_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?
+                .as_u64()
+                .ok_or(Error::TypeError)?;
+            let exponent = u32::try_from(exponent).map_err(|_| Error::TypeError)?;
+
+            // the result has at most `base.bits() * exponent` bits
+            if base.bits().saturating_mul(u64::from(exponent)) > MAX_POW_BITS {
+                return Err(Error::TypeError);
+            }
+
+            Some(Value::BigNum(base.pow(exponent)))
+        }
+        Function::Sqrt(rule) => {
+            let value = rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?
+                .try_bignum()?;
+
+            Some(Value::BigNum(value.sqrt()))
+        }
         Function::If(first_rule, second_rule) => {
-            let eval_if = eval(input, output, first_rule)?
+            let eval_if = eval(input, output, first_rule, cache)?
                 .ok_or(Error::TypeError)?
                 .try_bool()?;
 
             if eval_if {
-                eval(input, output, second_rule)?
+                eval(input, output, second_rule, cache)?
             } else {
                 None
             }
         }
         Function::IfNot(if_rule, else_rule) => {
-            let eval_if = eval(input, output, if_rule)?
+            let eval_if = eval(input, output, if_rule, cache)?
                 .ok_or(Error::TypeError)?
                 .try_bool()?;
 
             if !eval_if {
-                eval(input, output, else_rule)?
+                eval(input, output, else_rule, cache)?
             } else {
                 None
             }
         }
         Function::IfElse(if_rule, then_rule, else_rule) => {
-            let eval_if = eval(input, output, if_rule)?
+            let eval_if = eval(input, output, if_rule, cache)?
                 .ok_or(Error::TypeError)?
                 .try_bool()?;
 
             if eval_if {
-                eval(input, output, then_rule)?
+                eval(input, output, then_rule, cache)?
             } else {
-                eval(input, output, else_rule)?
+                eval(input, output, else_rule, cache)?
             }
         }
         Function::And(first_rule, second_rule) => {
-            let a = eval(input, output, first_rule)?
+            let a = eval(input, output, first_rule, cache)?
                 .ok_or(Error::TypeError)?
                 .try_bool()?;
-            let b = eval(input, output, second_rule)?
+
+            // short-circuits: the second rule is not evaluated if the first one is `false`
+            if !a {
+                return Ok(Some(Value::Bool(false)));
+            }
+
+            let b = eval(input, output, second_rule, cache)?
                 .ok_or(Error::TypeError)?
                 .try_bool()?;
 
-            Some(Value::Bool(a && b))
+            Some(Value::Bool(b))
         }
         Function::Or(first_rule, second_rule) => {
-            let a = eval(input, output, first_rule)?
+            let a = eval(input, output, first_rule, cache)?
                 .ok_or(Error::TypeError)?
                 .try_bool()?;
-            let b = eval(input, output, second_rule)?
+
+            // short-circuits: the second rule is not evaluated if the first one is `true`
+            if a {
+                return Ok(Some(Value::Bool(true)));
+            }
+
+            let b = eval(input, output, second_rule, cache)?
                 .ok_or(Error::TypeError)?
                 .try_bool()?;
 
-            Some(Value::Bool(a || b))
+            Some(Value::Bool(b))
         }
         Function::Xor(first_rule, second_rule) => {
-            let a = eval(input, output, first_rule)?
+            let a = eval(input, output, first_rule, cache)?
                 .ok_or(Error::TypeError)?
                 .try_bool()?;
-            let b = eval(input, output, second_rule)?
+            let b = eval(input, output, second_rule, cache)?
                 .ok_or(Error::TypeError)?
                 .try_bool()?;
 
             Some(Value::Bool(a ^ b))
         }
         Function::Not(first_rule) => {
-            let a = eval(input, output, first_rule)?
+            let a = eval(input, output, first_rule, cache)?
                 .ok_or(Error::TypeError)?
                 .try_bool()?;
 
             Some(Value::Bool(!a))
         }
         Function::Lt(first_rule, second_rule) => {
-            let first_eval = first_rule.eval(input, output)?.ok_or(Error::TypeError)?;
-            let second_eval = second_rule.eval(input, output)?.ok_or(Error::TypeError)?;
+            let first_eval = first_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
+            let second_eval = second_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
 
             let value = match (first_eval, second_eval) {
                 (Value::BigNum(bignum), rhs_value) => {
@@ -764,8 +962,8 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
 
                     Value::Bool(lhs_bignum.lt(&rhs_bignum))
                 }
-                (Value::Number(lhs), Value::Number(rhs)) => {
-                    Value::Bool(compare_numbers(lhs, rhs, ComparisonOperator::Lt)?)
+                (lhs @ Value::Number(_), rhs @ Value::Number(_)) => {
+                    Value::Bool(compare_numbers(&lhs, &rhs, ComparisonOperator::Lt)?)
                 }
                 _ => return Err(Error::TypeError),
             };
@@ -773,8 +971,12 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
             Some(value)
         }
         Function::Lte(first_rule, second_rule) => {
-            let first_eval = first_rule.eval(input, output)?.ok_or(Error::TypeError)?;
-            let second_eval = second_rule.eval(input, output)?.ok_or(Error::TypeError)?;
+            let first_eval = first_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
+            let second_eval = second_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
 
             let value = match (first_eval, second_eval) {
                 (Value::BigNum(bignum), rhs_value) => {
@@ -787,8 +989,8 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
 
                     Value::Bool(lhs_bignum.le(&rhs_bignum))
                 }
-                (Value::Number(lhs), Value::Number(rhs)) => {
-                    Value::Bool(compare_numbers(lhs, rhs, ComparisonOperator::Lte)?)
+                (lhs @ Value::Number(_), rhs @ Value::Number(_)) => {
+                    Value::Bool(compare_numbers(&lhs, &rhs, ComparisonOperator::Lte)?)
                 }
                 _ => return Err(Error::TypeError),
             };
@@ -796,8 +998,12 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
             Some(value)
         }
         Function::Gt(first_rule, second_rule) => {
-            let first_eval = first_rule.eval(input, output)?.ok_or(Error::TypeError)?;
-            let second_eval = second_rule.eval(input, output)?.ok_or(Error::TypeError)?;
+            let first_eval = first_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
+            let second_eval = second_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
 
             let value = match (first_eval, second_eval) {
                 (Value::BigNum(bignum), rhs_value) => {
@@ -810,8 +1016,8 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
 
                     Value::Bool(lhs_bignum.gt(&rhs_bignum))
                 }
-                (Value::Number(lhs), Value::Number(rhs)) => {
-                    Value::Bool(compare_numbers(lhs, rhs, ComparisonOperator::Gt)?)
+                (lhs @ Value::Number(_), rhs @ Value::Number(_)) => {
+                    Value::Bool(compare_numbers(&lhs, &rhs, ComparisonOperator::Gt)?)
                 }
                 _ => return Err(Error::TypeError),
             };
@@ -819,8 +1025,12 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
             Some(value)
         }
         Function::Gte(first_rule, second_rule) => {
-            let first_eval = first_rule.eval(input, output)?.ok_or(Error::TypeError)?;
-            let second_eval = second_rule.eval(input, output)?.ok_or(Error::TypeError)?;
+            let first_eval = first_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
+            let second_eval = second_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
 
             let value = match (first_eval, second_eval) {
                 (Value::BigNum(bignum), rhs_value) => {
@@ -833,8 +1043,8 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
 
                     Value::Bool(lhs_bignum.ge(&rhs_bignum))
                 }
-                (Value::Number(lhs), Value::Number(rhs)) => {
-                    Value::Bool(compare_numbers(lhs, rhs, ComparisonOperator::Gte)?)
+                (lhs @ Value::Number(_), rhs @ Value::Number(_)) => {
+                    Value::Bool(compare_numbers(&lhs, &rhs, ComparisonOperator::Gte)?)
                 }
                 _ => return Err(Error::TypeError),
             };
@@ -842,8 +1052,12 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
             Some(value)
         }
         Function::Eq(first_rule, second_rule) => {
-            let first_eval = first_rule.eval(input, output)?.ok_or(Error::TypeError)?;
-            let second_eval = second_rule.eval(input, output)?.ok_or(Error::TypeError)?;
+            let first_eval = first_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
+            let second_eval = second_rule
+                .eval_with_cache(input, output, cache)?
+                .ok_or(Error::TypeError)?;
 
             let value = match (first_eval, second_eval) {
                 (Value::BigNum(bignum), rhs_value) => {
@@ -856,8 +1070,8 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
 
                     Value::Bool(lhs_bignum.eq(&rhs_bignum))
                 }
-                (Value::Number(lhs), Value::Number(rhs)) => {
-                    Value::Bool(compare_numbers(lhs, rhs, ComparisonOperator::Eq)?)
+                (lhs @ Value::Number(_), rhs @ Value::Number(_)) => {
+                    Value::Bool(compare_numbers(&lhs, &rhs, ComparisonOperator::Eq)?)
                 }
                 (Value::Bool(lhs), Value::Bool(rhs)) => Value::Bool(lhs == rhs),
                 (Value::String(lhs), Value::String(rhs)) => Value::Bool(lhs == rhs),
@@ -876,44 +1090,44 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
         }
         Function::Neq(first_rule, second_rule) => {
             let is_equal = Function::Eq(first_rule.clone(), second_rule.clone())
-                .eval(input, output)?
+                .eval_with_cache(input, output, cache)?
                 .ok_or(Error::TypeError)?
                 .try_bool()?;
             Some(Value::Bool(!is_equal))
         }
         Function::Intersects(first_rule, second_rule) => {
-            let a = eval(input, output, first_rule)?
+            let a = eval(input, output, first_rule, cache)?
                 .ok_or(Error::TypeError)?
                 .try_array()?;
-            let b = eval(input, output, second_rule)?
+            let b = eval(input, output, second_rule, cache)?
                 .ok_or(Error::TypeError)?
                 .try_array()?;
 
             Some(Value::Bool(a.iter().any(|x| b.contains(x))))
         }
         Function::In(array_value, search_value) => {
-            let a = eval(input, output, array_value)?
+            let a = eval(input, output, array_value, cache)?
                 .ok_or(Error::TypeError)?
                 .try_array()?;
-            let b = eval(input, output, search_value)?.ok_or(Error::TypeError)?;
+            let b = eval(input, output, search_value, cache)?.ok_or(Error::TypeError)?;
 
             Some(Value::Bool(a.contains(&b)))
         }
         Function::Nin(array_value, search_value) => {
             let is_in = Function::In(array_value.clone(), search_value.clone())
-                .eval(input, output)?
+                .eval_with_cache(input, output, cache)?
                 .ok_or(Error::TypeError)?
                 .try_bool()?;
             Some(Value::Bool(!is_in))
         }
         Function::Between(min_rule, max_rule, value_rule) => {
             let is_gte_start = Function::Gte(value_rule.clone(), min_rule.clone())
-                .eval(input, output)?
+                .eval_with_cache(input, output, cache)?
                 .ok_or(Error::TypeError)?
                 .try_bool()?;
 
             let is_lte_end = Function::Lte(value_rule.clone(), max_rule.clone())
-                .eval(input, output)?
+                .eval_with_cache(input, output, cache)?
                 .ok_or(Error::TypeError)?
                 .try_bool()?;
 
@@ -921,13 +1135,12 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
         }
         Function::At(array_rule, index_rule) => {
             let mut array_value = array_rule
-                .eval(input, output)?
+                .eval_with_cache(input, output, cache)?
                 .ok_or(Error::TypeError)?
                 .try_array()?;
             let index_value = index_rule
-                .eval(input, output)?
+                .eval_with_cache(input, output, cache)?
                 .ok_or(Error::TypeError)?
-                .try_number()?
                 .as_u64()
                 .ok_or(Error::TypeError)?;
             let index = usize::try_from(index_value).map_err(|_| Error::TypeError)?;
@@ -940,11 +1153,11 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
         }
         Function::Split(string_rule, pattern_rule) => {
             let string_value = string_rule
-                .eval(input, output)?
+                .eval_with_cache(input, output, cache)?
                 .ok_or(Error::TypeError)?
                 .try_string()?;
             let pattern_value = pattern_rule
-                .eval(input, output)?
+                .eval_with_cache(input, output, cache)?
                 .ok_or(Error::TypeError)?
                 .try_string()?;
 
@@ -957,11 +1170,11 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
         }
         Function::StartsWith(string_rule, starts_with_rule) => {
             let string_value = string_rule
-                .eval(input, output)?
+                .eval_with_cache(input, output, cache)?
                 .ok_or(Error::TypeError)?
                 .try_string()?;
             let starts_with_value = starts_with_rule
-                .eval(input, output)?
+                .eval_with_cache(input, output, cache)?
                 .ok_or(Error::TypeError)?
                 .try_string()?;
 
@@ -969,11 +1182,11 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
         }
         Function::EndsWith(string_rule, ends_with_rule) => {
             let string_value = string_rule
-                .eval(input, output)?
+                .eval_with_cache(input, output, cache)?
                 .ok_or(Error::TypeError)?
                 .try_string()?;
             let ends_with_value = ends_with_rule
-                .eval(input, output)?
+                .eval_with_cache(input, output, cache)?
                 .ok_or(Error::TypeError)?
                 .try_string()?;
 
@@ -981,16 +1194,16 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
         }
         Function::OnlyShowIf(rule) => {
             let eval = rule
-                .eval(input, output)?
+                .eval_with_cache(input, output, cache)?
                 .ok_or(Error::TypeError)?
                 .try_bool()?;
             let new_rule = Box::new(Rule::Value(Value::Bool(eval)));
 
-            Function::Set(String::from("show"), new_rule).eval(input, output)?
+            Function::Set(String::from("show"), new_rule).eval_with_cache(input, output, cache)?
         }
         Function::GetPriceInUsd(amount_rule) => {
             let amount = amount_rule
-                .eval(input, output)?
+                .eval_with_cache(input, output, cache)?
                 .ok_or(Error::TypeError)?
                 .try_bignum()?;
 
@@ -1015,22 +1228,23 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
             let amount_as_number = Number::from_f64(amount_in_usd).ok_or(Error::TypeError)?;
             Some(Value::Number(amount_as_number))
         }
-        Function::Do(first_rule) => eval(input, output, first_rule)?,
+        Function::Do(first_rule) => eval(input, output, first_rule, cache)?,
         Function::Set(key, rule) => {
             // Output variables can be set any number of times by different rules, except `show`
             // if `show` is at any point set to `false`, we stop executing rules and don't show the ad.
             match key.as_str() {
                 "boost" => {
-                    let boost_num = rule
-                        .eval(input, output)?
+                    let boost = rule
+                        .eval_with_cache(input, output, cache)?
                         .ok_or(Error::TypeError)?
-                        .try_number()?;
+                        .as_f64()
+                        .ok_or(Error::TypeError)?;
 
-                    output.boost = boost_num.as_f64().ok_or(Error::TypeError)?;
+                    output.boost = boost;
                 }
                 "show" => {
                     let show_value = rule
-                        .eval(input, output)?
+                        .eval_with_cache(input, output, cache)?
                         .ok_or(Error::TypeError)?
                         .try_bool()?;
 
@@ -1038,7 +1252,7 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
                 }
                 "price.IMPRESSION" => {
                     let price = rule
-                        .eval(input, output)?
+                        .eval_with_cache(input, output, cache)?
                         .ok_or(Error::TypeError)?
                         .try_bignum()?;
 
@@ -1047,7 +1261,7 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
                 }
                 "price.CLICK" => {
                     let price = rule
-                        .eval(input, output)?
+                        .eval_with_cache(input, output, cache)?
                         .ok_or(Error::TypeError)?
                         .try_bignum()?;
 
@@ -1059,7 +1273,7 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
 
             return Ok(None);
         }
-        Function::Get(key) => match input.try_get(key) {
+        Function::Get(key) => match cache.get_input(input, key) {
             Ok(value) => Some(value),
             Err(Error::UnknownVariable) => Some(output.try_get(key)?),
             Err(e) => return Err(e),
@@ -1074,6 +1288,112 @@ fn eval(input: &Input, output: &mut Output, rule: &Rule) -> Result<Option<Value>
     Ok(value)
 }
 
+/// The `Output` variables which can be changed using `Function::Set`
+pub const OUTPUT_SET_KEYS: [&str; 4] = ["boost", "show", "price.IMPRESSION", "price.CLICK"];
+
+/// Validates the `rules` before accepting them (e.g. on `UpdateTargeting`),
+/// instead of discovering the errors when evaluating them:
+/// - every `Function::Get` key should be a known `Input` or `Output` variable
+/// - every `Function::Set` key should be one of the [`OUTPUT_SET_KEYS`]
+/// - every `Function::Bn` value should be a valid `BigNum`
+/// - every `Function::Pow` exponent value shouldn't exceed the [`MAX_POW_BITS`]
+///
+/// The arity of the functions is checked when deserializing the `Rule`s.
+/// Returns all the errors found in the `rules`.
+pub fn validate_rules(rules: &[Rule]) -> Result<(), Vec<String>> {
+    let mut errors = vec![];
+
+    for rule in rules {
+        validate_rule(rule, &mut errors);
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn validate_rule(rule: &Rule, errors: &mut Vec<String>) {
+    let function = match rule {
+        Rule::Value(_) => return,
+        Rule::Function(function) => function,
+    };
+
+    match function {
+        Function::Get(key) => {
+            let is_output_key = key == "show" || key == "boost" || key.starts_with("price.");
+
+            if key.parse::<Field>().is_err() && !is_output_key {
+                errors.push(format!("Unknown variable in get: {}", key));
+            }
+        }
+        Function::Set(key, _) if !OUTPUT_SET_KEYS.contains(&key.as_str()) => {
+            errors.push(format!("Unknown variable in set: {}", key));
+        }
+        Function::Bn(value) if value.clone().try_bignum().is_err() => {
+            errors.push(format!("Invalid BigNum value in bn: {:?}", value));
+        }
+        Function::Pow(_, exponent) => {
+            if let Rule::Value(value) = exponent.as_ref() {
+                // the result of any base but `0` would exceed the `MAX_POW_BITS`
+                if value.as_u64().map_or(true, |exp| exp > MAX_POW_BITS) {
+                    errors.push(format!("Invalid exponent in pow: {:?}", value));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    for nested_rule in nested_rules(function) {
+        validate_rule(nested_rule, errors);
+    }
+}
+
+fn nested_rules(function: &Function) -> Vec<&Rule> {
+    match function {
+        Function::MulDiv(first, second, third)
+        | Function::IfElse(first, second, third)
+        | Function::Between(first, second, third) => vec![first, second, third],
+        Function::Div(first, second)
+        | Function::Mul(first, second)
+        | Function::Mod(first, second)
+        | Function::Add(first, second)
+        | Function::Sub(first, second)
+        | Function::Max(first, second)
+        | Function::Min(first, second)
+        | Function::Pow(first, second)
+        | Function::If(first, second)
+        | Function::IfNot(first, second)
+        | Function::And(first, second)
+        | Function::Or(first, second)
+        | Function::Xor(first, second)
+        | Function::Lt(first, second)
+        | Function::Lte(first, second)
+        | Function::Gt(first, second)
+        | Function::Gte(first, second)
+        | Function::Eq(first, second)
+        | Function::Neq(first, second)
+        | Function::In(first, second)
+        | Function::Nin(first, second)
+        | Function::At(first, second)
+        | Function::Split(first, second)
+        | Function::StartsWith(first, second)
+        | Function::EndsWith(first, second)
+        | Function::Intersects(first, second) => vec![first, second],
+        Function::Not(rule)
+        | Function::Sqrt(rule)
+        | Function::OnlyShowIf(rule)
+        | Function::GetPriceInUsd(rule)
+        | Function::Do(rule)
+        | Function::Set(_, rule) => vec![rule],
+        Function::Get(_) | Function::Bn(_) => vec![],
+    }
+    .into_iter()
+    .map(Box::as_ref)
+    .collect()
+}
+
 /// Stops (i.e. it short-circuits) evaluating `Rule`s when `Output.show` becomes `false`
 pub fn eval_multiple(
     rules: &[Rule],
@@ -1126,6 +1446,28 @@ enum MathOperator {
     Min,
 }
 
+/// Two `Value::Number`s in the first representation both of them fit in:
+/// `u64`, then `i64` and finally `f64`
+enum Numbers {
+    U64(u64, u64),
+    I64(i64, i64),
+    F64(f64, f64),
+}
+
+impl Numbers {
+    fn try_from_values(lhs: &Value, rhs: &Value) -> Result<Self, Error> {
+        if let (Some(lhs), Some(rhs)) = (lhs.as_u64(), rhs.as_u64()) {
+            Ok(Numbers::U64(lhs, rhs))
+        } else if let (Some(lhs), Some(rhs)) = (lhs.as_i64(), rhs.as_i64()) {
+            Ok(Numbers::I64(lhs, rhs))
+        } else if let (Some(lhs), Some(rhs)) = (lhs.as_f64(), rhs.as_f64()) {
+            Ok(Numbers::F64(lhs, rhs))
+        } else {
+            Err(Error::TypeError)
+        }
+    }
+}
+
 enum ComparisonOperator {
     /// First value is greater than second value
     Gt,
@@ -1139,16 +1481,11 @@ enum ComparisonOperator {
     Eq,
 }
 
-fn compare_numbers(lhs: Number, rhs: Number, ops: ComparisonOperator) -> Result<bool, Error> {
-    match (lhs.as_u64(), rhs.as_u64()) {
-        (Some(lhs), Some(rhs)) => Ok(handle_comparisons(lhs, rhs, ops)),
-        _ => match (lhs.as_i64(), rhs.as_i64()) {
-            (Some(lhs), Some(rhs)) => Ok(handle_comparisons(lhs, rhs, ops)),
-            _ => match (lhs.as_f64(), rhs.as_f64()) {
-                (Some(lhs), Some(rhs)) => Ok(handle_comparisons(lhs, rhs, ops)),
-                _ => Err(Error::TypeError),
-            },
-        },
+fn compare_numbers(lhs: &Value, rhs: &Value, ops: ComparisonOperator) -> Result<bool, Error> {
+    match Numbers::try_from_values(lhs, rhs)? {
+        Numbers::U64(lhs, rhs) => Ok(handle_comparisons(lhs, rhs, ops)),
+        Numbers::I64(lhs, rhs) => Ok(handle_comparisons(lhs, rhs, ops)),
+        Numbers::F64(lhs, rhs) => Ok(handle_comparisons(lhs, rhs, ops)),
     }
 }
 
@@ -1261,16 +1598,11 @@ fn handle_f64(lhs: f64, rhs: f64, ops: MathOperator) -> Result<Number, Error> {
     }
 }
 
-fn math_operator(lhs: Number, rhs: Number, ops: MathOperator) -> Result<Number, Error> {
-    match (lhs.as_u64(), rhs.as_u64()) {
-        (Some(lhs), Some(rhs)) => handle_u64(lhs, rhs, ops),
-        _ => match (lhs.as_i64(), rhs.as_i64()) {
-            (Some(lhs), Some(rhs)) => handle_i64(lhs, rhs, ops),
-            _ => match (lhs.as_f64(), rhs.as_f64()) {
-                (Some(lhs), Some(rhs)) => handle_f64(lhs, rhs, ops),
-                _ => Err(Error::TypeError),
-            },
-        },
+fn math_operator(lhs: &Value, rhs: &Value, ops: MathOperator) -> Result<Number, Error> {
+    match Numbers::try_from_values(lhs, rhs)? {
+        Numbers::U64(lhs, rhs) => handle_u64(lhs, rhs, ops),
+        Numbers::I64(lhs, rhs) => handle_i64(lhs, rhs, ops),
+        Numbers::F64(lhs, rhs) => handle_f64(lhs, rhs, ops),
     }
 }
 
@@ -40,8 +40,90 @@ fn get_default_input() -> Input {
 
 mod rules_test {
     use super::{Function, Rule, Rules, Value};
+    use crate::DomainError;
     use serde_json::{from_value, json};
 
+    /// Rules in the format of the JS implementation.
+    ///
+    /// They are written by hand after the rules of the JS engine (`adex-adview-manager`),
+    /// not generated from it, so when the JS format changes they should be updated
+    /// together with the (de)serialization of [`Function`].
+    ///
+    /// @TODO: Replace them with rules and their evaluated outputs exported from the JS engine,
+    /// recording the `adex-adview-manager` version they were exported from.
+    const GOLDEN_RULES: &str = include_str!("../../test/resources/targeting_rules.json");
+
+    #[test]
+    fn rules_serialization_matches_the_js_format() {
+        let golden_rules: Vec<serde_json::Value> =
+            serde_json::from_str(GOLDEN_RULES).expect("Should deserialize the golden rules");
+
+        for golden_rule in golden_rules {
+            let rule = from_value::<Rule>(golden_rule.clone())
+                .unwrap_or_else(|err| panic!("Should deserialize {}: {}", golden_rule, err));
+            let actual_json = serde_json::to_value(&rule).expect("Should serialize");
+
+            pretty_assertions::assert_eq!(golden_rule, actual_json);
+        }
+
+        let rules = serde_json::from_str::<Rules>(GOLDEN_RULES).expect("Should deserialize");
+        assert_eq!(13, rules.len(), "None of the rules should be skipped");
+
+        // multiple arguments
+        assert_eq!(
+            Rule::Function(Function::new_only_show_if(Function::new_intersects(
+                Function::new_get("adSlot.categories"),
+                Value::Array(vec![Value::new_string("IAB1"), Value::new_string("IAB10")]),
+            ))),
+            rules[0]
+        );
+        // single argument
+        assert_eq!(
+            Ok(Rule::Function(Function::new_get("country"))),
+            from_value::<Rule>(json!({ "get": "country" })).map_err(|err| err.to_string())
+        );
+        assert_eq!(
+            Ok(Rule::Function(Function::new_bn(Value::new_string("100")))),
+            from_value::<Rule>(json!({ "bn": "100" })).map_err(|err| err.to_string())
+        );
+    }
+
+    #[test]
+    fn rule_from_json_string() {
+        let json = r#"{
+            "onlyShowIf": {
+                "and": [
+                    {"gt": [{"get": "adView.secondsSinceCampaignImpression"}, 300]},
+                    {"in": [["bg", "us"], {"get": "country"}]}
+                ]
+            }
+        }"#;
+
+        let expected: Rule = Function::new_only_show_if(Function::new_and(
+            Function::new_gt(
+                Function::new_get("adView.secondsSinceCampaignImpression"),
+                Value::new_number(300),
+            ),
+            Function::new_in(
+                Value::Array(vec![Value::new_string("bg"), Value::new_string("us")]),
+                Function::new_get("country"),
+            ),
+        ))
+        .into();
+
+        assert_eq!(Ok(expected.clone()), json.parse::<Rule>());
+        assert_eq!(Ok(expected), Rule::from_json(json));
+
+        assert!(matches!(
+            Rule::from_json(r#"{"unknownFunction": [1, 2]}"#),
+            Err(DomainError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            Rule::from_json("not json"),
+            Err(DomainError::InvalidArgument(_))
+        ));
+    }
+
     #[test]
     fn test_rules_should_be_empty_when_single_invalid_rule() {
         let rule = json!([
@@ -92,6 +174,244 @@ mod rules_test {
         ));
         assert_eq!(expected, deser.0[0])
     }
+
+    #[test]
+    fn mul_and_sub_from_json() {
+        let mul = json!({"mul": [{"get": "adSlot.minPerImpression"}, 2]});
+        let sub = json!({"sub": [{"bn": "1000"}, 0.5]});
+
+        assert_eq!(
+            Rule::Function(Function::new_mul(
+                Function::new_get("adSlot.minPerImpression"),
+                Value::new_number(2),
+            )),
+            from_value::<Rule>(mul.clone()).expect("Should deserialize mul")
+        );
+        assert_eq!(
+            Rule::Function(Function::new_sub(
+                Function::new_bn(Value::new_string("1000")),
+                Value::Number(serde_json::Number::from_f64(0.5).expect("Should create float")),
+            )),
+            from_value::<Rule>(sub.clone()).expect("Should deserialize sub")
+        );
+
+        assert_eq!(
+            mul,
+            serde_json::to_value(from_value::<Rule>(mul.clone()).unwrap()).unwrap()
+        );
+        assert_eq!(
+            sub,
+            serde_json::to_value(from_value::<Rule>(sub.clone()).unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn in_from_json() {
+        let json = json!({"in": [["us", "ca"], {"get": "country"}]});
+
+        let expected = Rule::Function(Function::new_in(
+            Value::Array(vec![Value::new_string("us"), Value::new_string("ca")]),
+            Function::new_get("country"),
+        ));
+
+        assert_eq!(
+            expected,
+            from_value::<Rule>(json.clone()).expect("Should deserialize in")
+        );
+        assert_eq!(
+            json,
+            serde_json::to_value(&expected).expect("Should serialize")
+        );
+    }
+
+    #[test]
+    fn or_and_not_round_trip() {
+        let or = Rule::Function(Function::new_or(
+            Function::new_eq(Function::new_get("country"), Value::new_string("bg")),
+            Value::Bool(false),
+        ));
+        let not = Rule::Function(Function::new_not(Value::Bool(true)));
+
+        let or_json = json!({"or": [{"eq": [{"get": "country"}, "bg"]}, false]});
+        let not_json = json!({"not": true});
+
+        assert_eq!(
+            or_json,
+            serde_json::to_value(&or).expect("Should serialize")
+        );
+        assert_eq!(
+            not_json,
+            serde_json::to_value(&not).expect("Should serialize")
+        );
+
+        assert_eq!(
+            or,
+            from_value::<Rule>(or_json).expect("Should deserialize or")
+        );
+        assert_eq!(
+            not,
+            from_value::<Rule>(not_json).expect("Should deserialize not")
+        );
+    }
+}
+
+mod eval_cache_test {
+    use super::*;
+
+    #[test]
+    fn repeated_get_is_resolved_once() {
+        let input = get_default_input();
+        let mut output = Output {
+            show: true,
+            boost: 1.0,
+            price: Default::default(),
+        };
+
+        let rule: Rule = Function::new_and(
+            Function::new_eq(Function::new_get("country"), Value::new_string("bg")),
+            Function::new_in(
+                Value::Array(vec![Value::new_string("bg")]),
+                Function::new_get("country"),
+            ),
+        )
+        .into();
+
+        let mut cache = EvalCache::default();
+        let result = eval(&input, &mut output, &rule, &mut cache);
+
+        assert_eq!(Ok(Some(Value::Bool(true))), result);
+        assert_eq!(
+            1, cache.input_lookups,
+            "country should be resolved only once"
+        );
+
+        // Output variables are not cached, since they can be changed by `Function::Set`
+        let boost = eval(
+            &input,
+            &mut output,
+            &Function::new_get("boost").into(),
+            &mut cache,
+        )
+        .expect("Should get output.boost");
+        let expected_boost = Number::from_f64(1.0).expect("Should create Number");
+
+        assert_eq!(Some(Value::Number(expected_boost)), boost);
+        assert!(cache.input_values.get("boost").is_none());
+    }
+}
+
+mod value_test {
+    use super::*;
+
+    #[test]
+    fn numeric_representations() {
+        let unsigned = Value::new_number(u64::MAX);
+        assert_eq!(Some(u64::MAX), unsigned.as_u64());
+        assert_eq!(None, unsigned.as_i64());
+        assert_eq!(Some(u64::MAX as f64), unsigned.as_f64());
+
+        let positive = Value::new_number(42);
+        assert_eq!(Some(42), positive.as_u64());
+        assert_eq!(Some(42), positive.as_i64());
+        assert_eq!(Some(42.0), positive.as_f64());
+
+        let negative = Value::new_number(-42);
+        assert_eq!(None, negative.as_u64());
+        assert_eq!(Some(-42), negative.as_i64());
+        assert_eq!(Some(-42.0), negative.as_f64());
+
+        let float = Value::Number(Number::from_f64(0.5).expect("Should make a number"));
+        assert_eq!(None, float.as_u64());
+        assert_eq!(None, float.as_i64());
+        assert_eq!(Some(0.5), float.as_f64());
+
+        for not_a_number in &[
+            Value::new_string("42"),
+            Value::BigNum(42.into()),
+            Value::Bool(true),
+            Value::Array(vec![]),
+        ] {
+            assert_eq!(None, not_a_number.as_u64());
+            assert_eq!(None, not_a_number.as_i64());
+            assert_eq!(None, not_a_number.as_f64());
+        }
+    }
+}
+
+mod validate_rules_test {
+    use super::*;
+
+    #[test]
+    fn well_formed_rules_are_valid() {
+        let rules = vec![
+            Function::new_only_show_if(Function::new_intersects(
+                Function::new_get("adSlot.categories"),
+                Value::Array(vec![Value::new_string("News")]),
+            ))
+            .into(),
+            Function::new_set(
+                "price.IMPRESSION",
+                Function::new_mul(
+                    Function::new_get("price.IMPRESSION"),
+                    Function::new_bn(Value::new_number(2)),
+                ),
+            )
+            .into(),
+            Function::new_if(
+                Function::new_gt(
+                    Function::new_get("adView.secondsSinceCampaignImpression"),
+                    Value::new_number(300),
+                ),
+                Function::new_set("boost", Value::new_number(2)),
+            )
+            .into(),
+        ];
+
+        assert_eq!(Ok(()), validate_rules(&rules));
+    }
+
+    #[test]
+    fn unknown_keys_and_invalid_values_are_reported() {
+        let rules = vec![
+            Function::new_only_show_if(Function::new_eq(
+                Function::new_get("unknownVariable"),
+                Value::new_string("value"),
+            ))
+            .into(),
+            Function::new_set("unknownOutput", Value::Bool(true)).into(),
+            Function::new_set(
+                "price.IMPRESSION",
+                Function::new_bn(Value::new_string("not a number")),
+            )
+            .into(),
+            Function::new_set(
+                "price.IMPRESSION",
+                Function::new_pow(
+                    Function::new_get("price.IMPRESSION"),
+                    Value::new_number(4_000_000_000_u64),
+                ),
+            )
+            .into(),
+        ];
+
+        let errors = validate_rules(&rules).expect_err("Should be invalid");
+
+        assert_eq!(
+            vec![
+                "Unknown variable in get: unknownVariable".to_string(),
+                "Unknown variable in set: unknownOutput".to_string(),
+                format!(
+                    "Invalid BigNum value in bn: {:?}",
+                    Value::new_string("not a number")
+                ),
+                format!(
+                    "Invalid exponent in pow: {:?}",
+                    Value::new_number(4_000_000_000_u64)
+                ),
+            ],
+            errors
+        );
+    }
 }
 
 mod dsl_test {
@@ -195,6 +515,29 @@ mod dsl_test {
         }
     }
 
+    #[test]
+    fn test_and_short_circuits() {
+        let input = get_default_input();
+        let mut output = Output {
+            show: true,
+            boost: 1.0,
+            price: Default::default(),
+        };
+
+        // the second rule would fail with `Error::UnknownVariable`
+        let erroring_rule = Function::new_get("unknownVariable");
+        assert_eq!(
+            Err(Error::UnknownVariable),
+            Rule::Function(erroring_rule.clone()).eval(&input, &mut output)
+        );
+
+        let rule = Rule::Function(Function::new_and(Value::Bool(false), erroring_rule.clone()));
+        assert_eq!(Ok(Some(Value::Bool(false))), rule.eval(&input, &mut output));
+
+        let rule = Rule::Function(Function::new_and(Value::Bool(true), erroring_rule));
+        assert_eq!(Err(Error::UnknownVariable), rule.eval(&input, &mut output));
+    }
+
     #[test]
     fn test_if_eval() {
         let input = get_default_input();
@@ -411,6 +754,17 @@ mod math_functions {
 
             assert_eq!(Ok(Some(expected)), rule.eval(&input, &mut output));
         }
+
+        let overflows = vec![
+            (Value::new_number(u64::MAX), Value::new_number(2)),
+            (Value::new_number(i64::MIN), Value::new_number(2)),
+        ];
+
+        for (lhs, rhs) in overflows.into_iter() {
+            let rule = Rule::Function(Function::new_mul(lhs, rhs));
+
+            assert_eq!(Err(Error::TypeError), rule.eval(&input, &mut output));
+        }
     }
     #[test]
     fn test_mod_eval() {
@@ -456,6 +810,82 @@ mod math_functions {
         }
     }
     #[test]
+    fn test_pow_and_sqrt_eval() {
+        let input = get_default_input();
+        let mut output = Output {
+            show: true,
+            boost: 1.0,
+            price: Default::default(),
+        };
+
+        let cases = vec![
+            (
+                Value::BigNum(3.into()),
+                Value::new_number(0),
+                BigNum::from(1),
+            ),
+            (Value::new_number(3), Value::new_number(4), BigNum::from(81)),
+            (
+                Value::new_string("1000000000000000000"),
+                Value::new_number(2),
+                BigNum::from(10u64.pow(18)).pow(2),
+            ),
+        ];
+
+        for (base, exponent, expected) in cases.into_iter() {
+            let rule = Function::new_pow(base, exponent);
+
+            assert_eq!(
+                Ok(Some(Value::BigNum(expected))),
+                rule.eval(&input, &mut output)
+            );
+        }
+
+        let negative_exponent = Function::new_pow(Value::BigNum(3.into()), Value::new_number(-1));
+        assert_eq!(
+            Err(Error::TypeError),
+            negative_exponent.eval(&input, &mut output)
+        );
+
+        // the result would have more than `MAX_POW_BITS` bits
+        let huge_exponent = Function::new_pow(
+            Function::new_bn(Value::new_string("10")),
+            Value::new_number(4_000_000_000_u64),
+        );
+        assert_eq!(
+            Err(Error::TypeError),
+            huge_exponent.eval(&input, &mut output)
+        );
+        let huge_base = Function::new_pow(
+            Value::BigNum(BigNum::from(2).pow(600)),
+            Value::new_number(2),
+        );
+        assert_eq!(Err(Error::TypeError), huge_base.eval(&input, &mut output));
+
+        // `2` has 2 bits, so this is right at the `MAX_POW_BITS` bound
+        let max_result = Function::new_pow(Value::BigNum(2.into()), Value::new_number(512));
+        assert_eq!(
+            Ok(Some(Value::BigNum(BigNum::from(2).pow(512)))),
+            max_result.eval(&input, &mut output)
+        );
+
+        let sqrt = Function::new_sqrt(Value::BigNum(24.into()));
+        assert_eq!(
+            Ok(Some(Value::BigNum(4.into()))),
+            sqrt.eval(&input, &mut output)
+        );
+
+        // quadratic scaling of the price
+        let scaled_price = Function::new_sqrt(Function::new_pow(
+            Value::BigNum(12.into()),
+            Value::new_number(2),
+        ));
+        assert_eq!(
+            Ok(Some(Value::BigNum(12.into()))),
+            scaled_price.eval(&input, &mut output)
+        );
+    }
+    #[test]
     fn test_add_eval() {
         let input = get_default_input();
         let mut output = Output {
@@ -540,6 +970,19 @@ mod math_functions {
 
             assert_eq!(Ok(Some(expected)), rule.eval(&input, &mut output));
         }
+
+        let overflows = vec![
+            (Value::new_number(i64::MIN), Value::new_number(1)),
+            (Value::BigNum(2.into()), Value::BigNum(3.into())),
+            (Value::new_number(2), Value::BigNum(3.into())),
+            (Value::BigNum(2.into()), Value::new_number(3)),
+        ];
+
+        for (lhs, rhs) in overflows.into_iter() {
+            let rule = Rule::Function(Function::new_sub(lhs, rhs));
+
+            assert_eq!(Err(Error::TypeError), rule.eval(&input, &mut output));
+        }
     }
     #[test]
     fn test_min_eval() {
@@ -941,6 +1384,25 @@ mod control_flow_and_logic {
             assert_eq!(Ok(expected), rule.eval(&input, &mut output));
         }
     }
+
+    #[test]
+    fn test_or_short_circuits() {
+        let input = get_default_input();
+        let mut output = Output {
+            show: true,
+            boost: 1.0,
+            price: Default::default(),
+        };
+
+        // the second rule would fail with `Error::UnknownVariable`
+        let erroring_rule = Function::new_get("unknownVariable");
+
+        let rule = Rule::Function(Function::new_or(Value::Bool(true), erroring_rule.clone()));
+        assert_eq!(Ok(Some(Value::Bool(true))), rule.eval(&input, &mut output));
+
+        let rule = Rule::Function(Function::new_or(Value::Bool(false), erroring_rule));
+        assert_eq!(Err(Error::UnknownVariable), rule.eval(&input, &mut output));
+    }
     #[test]
     fn test_xor_eval() {
         let input = get_default_input();
@@ -1193,6 +1655,34 @@ mod string_and_array {
 
             assert_eq!(Ok(expected), rule.eval(&input, &mut output));
         }
+
+        let country_in = |countries: &[&str]| {
+            Rule::Function(Function::new_in(
+                Value::Array(countries.iter().map(|c| Value::new_string(c)).collect()),
+                Function::new_get("country"),
+            ))
+        };
+        // the country of the input is `bg`
+        assert_eq!(
+            Ok(Some(Value::Bool(true))),
+            country_in(&["us", "bg"]).eval(&input, &mut output)
+        );
+        assert_eq!(
+            Ok(Some(Value::Bool(false))),
+            country_in(&["us", "ca"]).eval(&input, &mut output)
+        );
+        // an empty array never includes the value
+        assert_eq!(
+            Ok(Some(Value::Bool(false))),
+            country_in(&[]).eval(&input, &mut output)
+        );
+
+        // the first value is not an array
+        let rule = Rule::Function(Function::new_in(
+            Value::new_string("bg"),
+            Value::new_string("bg"),
+        ));
+        assert_eq!(Err(Error::TypeError), rule.eval(&input, &mut output));
     }
     #[test]
     fn test_nin_eval() {
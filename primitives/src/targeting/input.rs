@@ -45,6 +45,49 @@ pub struct Input {
 }
 
 impl Input {
+    /// Builds the `Input` from the data of the request for an ad:
+    /// - the `ad_slot` for which the ad is requested, the slot owner is the publisher
+    /// - the `ad_unit` which will be shown, if it's already known
+    /// - the `country` of the user
+    /// - and the time of the request
+    ///
+    /// The `eventType` is `IMPRESSION` and the `adSlot.hostname` is taken from the AdSlot `website`.
+    /// Missing optional data (e.g. no `website`) and the `adView` scope are left empty (`None`).
+    pub fn from_request(
+        ad_slot: &crate::AdSlot,
+        ad_unit: Option<&crate::AdUnit>,
+        country: Option<String>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let hostname = ad_slot
+            .website
+            .as_deref()
+            .and_then(|website| url::Url::parse(website).ok())
+            .and_then(|url| url.host_str().map(ToString::to_string));
+
+        Self {
+            ad_view: None,
+            global: Global {
+                ad_slot_id: ad_slot.ipfs.clone(),
+                ad_slot_type: ad_slot.ad_type.clone(),
+                publisher_id: ad_slot.owner,
+                country,
+                event_type: "IMPRESSION".to_string(),
+                seconds_since_epoch: now,
+                user_agent_os: None,
+                user_agent_browser_family: None,
+            },
+            channel: None,
+            balances: None,
+            ad_unit_id: ad_unit.map(|ad_unit| ad_unit.ipfs.clone()),
+            ad_slot: hostname.map(|hostname| AdSlot {
+                categories: vec![],
+                hostname,
+                alexa_rank: Some(AdSlot::UNRANKED_ALEXA_RANK),
+            }),
+        }
+    }
+
     /// Sets the Channel Getter
     pub fn with_channel(mut self, channel: crate::Channel) -> Self {
         self.channel = Some(Get::Getter(channel::Getter::from_channel(&self, channel)));
@@ -182,12 +225,27 @@ impl GetField for Global {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 /// AdSlot scope, accessible on Supermarket and AdView
+///
+/// Incomplete AdSlot data is deserialized with defaults:
+/// no `categories`, an empty `hostname` and an [`AdSlot::UNRANKED_ALEXA_RANK`]
 pub struct AdSlot {
+    #[serde(default)]
     pub categories: Vec<String>,
+    #[serde(default)]
     pub hostname: String,
+    #[serde(default = "AdSlot::unranked_alexa_rank")]
     pub alexa_rank: Option<f64>,
 }
 
+impl AdSlot {
+    /// The Alexa rank of websites that are not ranked, i.e. ranked lower than any ranked website
+    pub const UNRANKED_ALEXA_RANK: f64 = 100_000_000.0;
+
+    fn unranked_alexa_rank() -> Option<f64> {
+        Some(Self::UNRANKED_ALEXA_RANK)
+    }
+}
+
 impl GetField for AdSlot {
     type Output = Option<Value>;
     type Field = field::AdSlot;
@@ -426,7 +484,7 @@ pub mod balances {
 mod test {
     use super::*;
     pub use crate::{
-        util::tests::prep_db::{DUMMY_CHANNEL as CHANNEL, DUMMY_IPFS as IPFS, IDS},
+        util::tests::prep_db::{DUMMY_AD_UNITS, DUMMY_CHANNEL as CHANNEL, DUMMY_IPFS as IPFS, IDS},
         AdUnit, BalancesMap,
     };
     use chrono::{TimeZone, Utc};
@@ -527,4 +585,116 @@ mod test {
             "Comparing the output Maps of the Inputs failed"
         );
     }
+
+    #[test]
+    fn input_from_request() {
+        let now = Utc.ymd(2020, 6, 6).and_hms(12, 0, 0);
+        let mut ad_slot = crate::AdSlot {
+            ipfs: IPFS[0].to_string(),
+            ad_type: "legacy_300x100".to_string(),
+            min_per_impression: None,
+            rules: vec![],
+            blacklisted_units: vec![],
+            fallback_unit: None,
+            owner: IDS["publisher"],
+            created: now,
+            title: None,
+            description: None,
+            website: Some("https://adex.network/blog".to_string()),
+            archived: false,
+            modified: None,
+        };
+
+        let input = Input::from_request(
+            &ad_slot,
+            Some(&DUMMY_AD_UNITS[0]),
+            Some("BG".to_string()),
+            now,
+        );
+
+        assert_eq!(
+            Ok(Value::String(IPFS[0].to_string())),
+            input.try_get("adSlotId")
+        );
+        assert_eq!(
+            Ok(Value::String("legacy_300x100".to_string())),
+            input.try_get("adSlotType")
+        );
+        assert_eq!(
+            Ok(Value::String(IDS["publisher"].to_checksum())),
+            input.try_get("publisherId")
+        );
+        assert_eq!(
+            Ok(Value::String("BG".to_string())),
+            input.try_get("country")
+        );
+        assert_eq!(
+            Ok(Value::String("IMPRESSION".to_string())),
+            input.try_get("eventType")
+        );
+        assert_eq!(
+            Ok(Value::new_number(now.timestamp())),
+            input.try_get("secondsSinceEpoch")
+        );
+        assert_eq!(
+            Ok(Value::String(DUMMY_AD_UNITS[0].ipfs.to_string())),
+            input.try_get("adUnitId")
+        );
+        assert_eq!(
+            Ok(Value::String("adex.network".to_string())),
+            input.try_get("adSlot.hostname")
+        );
+        assert_eq!(
+            Ok(Value::Number(
+                serde_json::Number::from_f64(AdSlot::UNRANKED_ALEXA_RANK)
+                    .expect("Should be a valid number")
+            )),
+            input.try_get("adSlot.alexaRank")
+        );
+        assert_eq!(Err(Error::UnknownVariable), input.try_get("userAgentOS"));
+        assert_eq!(
+            Err(Error::UnknownVariable),
+            input.try_get("adView.navigatorLanguage")
+        );
+
+        // missing optional data
+        ad_slot.website = None;
+        let input = Input::from_request(&ad_slot, None, None, now);
+
+        assert_eq!(Err(Error::UnknownVariable), input.try_get("country"));
+        assert_eq!(Err(Error::UnknownVariable), input.try_get("adUnitId"));
+        assert_eq!(
+            Err(Error::UnknownVariable),
+            input.try_get("adSlot.hostname")
+        );
+        assert_eq!(None, input.ad_slot);
+    }
+
+    #[test]
+    fn ad_slot_deserialization_with_defaults() {
+        let minimal = serde_json::from_value::<AdSlot>(json!({})).expect("Should deserialize");
+        assert_eq!(
+            AdSlot {
+                categories: vec![],
+                hostname: String::new(),
+                alexa_rank: Some(AdSlot::UNRANKED_ALEXA_RANK),
+            },
+            minimal
+        );
+
+        let full = serde_json::from_value::<AdSlot>(json!({
+            "categories": ["IAB3", "IAB13-7"],
+            "hostname": "adex.network",
+            "alexaRank": 2.0,
+        }))
+        .expect("Should deserialize");
+        assert_eq!(
+            AdSlot {
+                categories: vec!["IAB3".into(), "IAB13-7".into()],
+                hostname: "adex.network".into(),
+                alexa_rank: Some(2.0),
+            },
+            full
+        );
+    }
 }
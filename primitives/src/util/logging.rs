@@ -1,12 +1,17 @@
-use slog::{Drain, OwnedKVList, Record, KV};
+use slog::{Drain, Level, OwnedKVList, Record, KV};
 use slog_term::{
     timestamp_local, CompactFormatSerializer, CountingWriter, Decorator, RecordDecorator,
     Serializer, ThreadSafeTimestampFn,
 };
 use std::cell::RefCell;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
 use std::{io, io::Write};
 
 pub use slog_async::Async;
+pub use slog_json::Json;
 pub use slog_term::TermDecorator;
 
 pub struct PrefixedCompactFormat<D>
@@ -110,3 +115,64 @@ pub fn print_msg_header(
     write!(count_rd, "{}", record.msg())?;
     Ok(count_rd.count() != 0)
 }
+
+/// The minimum level of the logged records, which can be changed while the logger is running.
+///
+/// The clones share the same level.
+#[derive(Debug, Clone)]
+pub struct LogLevel(Arc<AtomicUsize>);
+
+impl LogLevel {
+    pub fn new(level: Level) -> Self {
+        Self(Arc::new(AtomicUsize::new(level.as_usize())))
+    }
+
+    pub fn get(&self) -> Level {
+        Level::from_usize(self.0.load(Ordering::Relaxed)).unwrap_or(Level::Info)
+    }
+
+    pub fn set(&self, level: Level) {
+        self.0.store(level.as_usize(), Ordering::Relaxed)
+    }
+
+    /// Switches to the next more verbose level and returns it.
+    /// After `Trace` it starts over from `Info`.
+    pub fn cycle(&self) -> Level {
+        let next = match self.get() {
+            Level::Trace => Level::Info,
+            level => Level::from_usize(level.as_usize() + 1).unwrap_or(Level::Info),
+        };
+        self.set(next);
+
+        next
+    }
+}
+
+/// Drops the records which are less severe than the current [`LogLevel`]
+pub struct LogLevelFilter<D> {
+    drain: D,
+    level: LogLevel,
+}
+
+impl<D> LogLevelFilter<D> {
+    pub fn new(drain: D, level: LogLevel) -> Self {
+        Self { drain, level }
+    }
+}
+
+impl<D: Drain> Drain for LogLevelFilter<D> {
+    type Ok = ();
+    type Err = D::Err;
+
+    fn log(&self, record: &Record<'_>, values: &OwnedKVList) -> Result<Self::Ok, Self::Err> {
+        if self.is_enabled(record.level()) {
+            self.drain.log(record, values).map(|_| ())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn is_enabled(&self, level: Level) -> bool {
+        level.is_at_least(self.level.get()) && self.drain.is_enabled(level)
+    }
+}
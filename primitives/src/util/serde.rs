@@ -0,0 +1,229 @@
+//! Serde helpers for the fields which accept more than a single format, to be used with `#[serde(with = "...")]`
+
+/// Deserializes a `DateTime<Utc>` from either a millisecond timestamp or an RFC3339 string,
+/// e.g. `1564383600000` or `"2019-07-29T07:00:00Z"`.
+///
+/// It's always serialized as a millisecond timestamp.
+pub mod ts_milliseconds_or_rfc3339 {
+    use chrono::{DateTime, TimeZone, Utc};
+    use serde::de::{self, Deserializer, Visitor};
+    use std::convert::TryFrom;
+    use std::fmt;
+
+    pub use chrono::serde::ts_milliseconds::serialize;
+
+    struct MillisecondsOrRfc3339Visitor;
+
+    impl<'de> Visitor<'de> for MillisecondsOrRfc3339Visitor {
+        type Value = DateTime<Utc>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str("a millisecond timestamp or an RFC3339 string")
+        }
+
+        fn visit_i64<E: de::Error>(self, millis: i64) -> Result<Self::Value, E> {
+            Utc.timestamp_millis_opt(millis)
+                .single()
+                .ok_or_else(|| E::custom(format!("timestamp out of range: {}", millis)))
+        }
+
+        fn visit_u64<E: de::Error>(self, millis: u64) -> Result<Self::Value, E> {
+            let millis = i64::try_from(millis)
+                .map_err(|_| E::custom(format!("timestamp out of range: {}", millis)))?;
+
+            self.visit_i64(millis)
+        }
+
+        fn visit_str<E: de::Error>(self, rfc3339: &str) -> Result<Self::Value, E> {
+            DateTime::parse_from_rfc3339(rfc3339)
+                .map(|datetime| datetime.with_timezone(&Utc))
+                .map_err(E::custom)
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(MillisecondsOrRfc3339Visitor)
+    }
+}
+
+/// The same as [`ts_milliseconds_or_rfc3339`] for an optional `DateTime<Utc>`
+pub mod ts_milliseconds_or_rfc3339_option {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer};
+
+    pub use chrono::serde::ts_milliseconds_option::serialize;
+
+    #[derive(Deserialize)]
+    struct MillisecondsOrRfc3339(
+        #[serde(with = "super::ts_milliseconds_or_rfc3339")] DateTime<Utc>,
+    );
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let datetime = Option::<MillisecondsOrRfc3339>::deserialize(deserializer)?;
+
+        Ok(datetime.map(|MillisecondsOrRfc3339(datetime)| datetime))
+    }
+}
+
+/// Deserializes a number of milliseconds from either a plain number, e.g. `1500`,
+/// or a duration string with a `ms`, `s`, `m` or `h` unit, e.g. `"500ms"` or `"2s"`.
+///
+/// Meant to be used with `#[serde(deserialize_with = "...")]`, the field is serialized as a plain number.
+pub fn deserialize_milliseconds<'de, D>(deserializer: D) -> Result<u32, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::{self, Visitor};
+    use std::convert::TryFrom;
+    use std::fmt;
+
+    struct MillisecondsVisitor;
+
+    impl<'de> Visitor<'de> for MillisecondsVisitor {
+        type Value = u32;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+            formatter.write_str("a number of milliseconds or a duration like \"500ms\" or \"2s\"")
+        }
+
+        fn visit_i64<E: de::Error>(self, millis: i64) -> Result<Self::Value, E> {
+            u32::try_from(millis)
+                .map_err(|_| E::custom(format!("invalid milliseconds: {}", millis)))
+        }
+
+        fn visit_u64<E: de::Error>(self, millis: u64) -> Result<Self::Value, E> {
+            u32::try_from(millis)
+                .map_err(|_| E::custom(format!("invalid milliseconds: {}", millis)))
+        }
+
+        fn visit_str<E: de::Error>(self, duration: &str) -> Result<Self::Value, E> {
+            let invalid = || E::custom(format!("invalid duration: {}", duration));
+
+            let unit_position = duration
+                .find(|c: char| !c.is_ascii_digit())
+                .ok_or_else(invalid)?;
+            let (amount, unit) = duration.split_at(unit_position);
+            let amount: u32 = amount.parse().map_err(|_| invalid())?;
+
+            let multiplier = match unit {
+                "ms" => 1,
+                "s" => 1_000,
+                "m" => 60 * 1_000,
+                "h" => 60 * 60 * 1_000,
+                _ => return Err(invalid()),
+            };
+
+            amount.checked_mul(multiplier).ok_or_else(invalid)
+        }
+    }
+
+    deserializer.deserialize_any(MillisecondsVisitor)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::{DateTime, TimeZone, Utc};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Deserialize, Debug)]
+    struct Timeout {
+        #[serde(deserialize_with = "deserialize_milliseconds")]
+        timeout: u32,
+    }
+
+    fn timeout(value: serde_json::Value) -> Result<u32, serde_json::Error> {
+        serde_json::from_value::<Timeout>(json!({ "timeout": value })).map(|parsed| parsed.timeout)
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Timestamps {
+        #[serde(with = "ts_milliseconds_or_rfc3339")]
+        created: DateTime<Utc>,
+        #[serde(default, with = "ts_milliseconds_or_rfc3339_option")]
+        active_from: Option<DateTime<Utc>>,
+    }
+
+    #[test]
+    fn deserializes_milliseconds_and_rfc3339() {
+        let expected = Timestamps {
+            created: Utc.ymd(2019, 7, 29).and_hms_milli(7, 0, 0, 123),
+            active_from: Some(Utc.ymd(2019, 7, 29).and_hms(7, 0, 0)),
+        };
+
+        let from_millis: Timestamps = serde_json::from_value(
+            json!({"created": 1564383600123_u64, "active_from": 1564383600000_u64}),
+        )
+        .expect("Should deserialize millisecond timestamps");
+        assert_eq!(expected, from_millis);
+
+        let from_rfc3339: Timestamps = serde_json::from_value(json!({
+            "created": "2019-07-29T07:00:00.123Z",
+            "active_from": "2019-07-29T09:00:00+02:00",
+        }))
+        .expect("Should deserialize RFC3339 strings");
+        assert_eq!(expected, from_rfc3339);
+
+        let without_active_from: Timestamps = serde_json::from_value(
+            json!({"created": "2019-07-29T07:00:00.123Z", "active_from": null}),
+        )
+        .expect("Should deserialize a null optional timestamp");
+        assert_eq!(None, without_active_from.active_from);
+
+        // it's always serialized as a millisecond timestamp
+        assert_eq!(
+            json!({"created": 1564383600123_u64, "active_from": 1564383600000_u64}),
+            serde_json::to_value(&from_rfc3339).expect("Should serialize")
+        );
+    }
+
+    #[test]
+    fn invalid_timestamps_are_rejected() {
+        for invalid in &[
+            json!("29.07.2019"),
+            json!("1564383600000"),
+            json!(true),
+            json!(u64::MAX),
+        ] {
+            let result = serde_json::from_value::<Timestamps>(json!({ "created": invalid }));
+            assert!(result.is_err(), "{} should be rejected", invalid);
+        }
+    }
+
+    #[test]
+    fn deserializes_milliseconds_from_numbers_and_durations() {
+        assert_eq!(2_000, timeout(json!("2s")).expect("Should parse seconds"));
+        assert_eq!(
+            500,
+            timeout(json!("500ms")).expect("Should parse milliseconds")
+        );
+        assert_eq!(120_000, timeout(json!("2m")).expect("Should parse minutes"));
+        assert_eq!(
+            5_000,
+            timeout(json!(5000)).expect("Should accept a plain number")
+        );
+
+        for invalid in &[
+            json!("2"),
+            json!("s"),
+            json!("2 s"),
+            json!("2days"),
+            json!("-1s"),
+            json!("5000000h"),
+            json!(-1),
+        ] {
+            assert!(
+                timeout(invalid.clone()).is_err(),
+                "{} should be rejected",
+                invalid
+            );
+        }
+    }
+}
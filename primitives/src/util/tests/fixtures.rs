@@ -0,0 +1,58 @@
+use serde::de::DeserializeOwned;
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum FixtureError {
+    #[error("Unable to read fixture {0}: {1}")]
+    Read(String, #[source] std::io::Error),
+    #[error("Unable to deserialize fixture {0}: {1}")]
+    Deserialize(String, #[source] serde_json::Error),
+}
+
+/// The directory of the JSON fixtures, shared with the golden files of the JS implementation
+pub fn fixtures_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test/resources/fixtures")
+}
+
+/// Loads and deserializes the `{name}.json` fixture from the [`fixtures_dir`],
+/// e.g. `load_fixture::<Channel>("channel")`
+pub fn load_fixture<T: DeserializeOwned>(name: &str) -> Result<T, FixtureError> {
+    let path = fixtures_dir().join(format!("{}.json", name));
+
+    let json = std::fs::read_to_string(&path)
+        .map_err(|error| FixtureError::Read(path.display().to_string(), error))?;
+
+    serde_json::from_str(&json)
+        .map_err(|error| FixtureError::Deserialize(path.display().to_string(), error))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::tests::prep_db::DUMMY_CHANNEL;
+    use crate::Channel;
+
+    #[test]
+    fn loads_the_channel_fixture() {
+        let channel = load_fixture::<Channel>("channel").expect("Should load the channel fixture");
+
+        let mut expected = DUMMY_CHANNEL.clone();
+        expected.spec.nonce = Some(987_654_321.into());
+        assert_eq!(expected, channel);
+    }
+
+    #[test]
+    fn missing_and_invalid_fixtures_are_errors() {
+        assert!(matches!(
+            load_fixture::<Channel>("missing"),
+            Err(FixtureError::Read(..))
+        ));
+
+        // it's a valid fixture, but not of a `Vec<Channel>`
+        assert!(matches!(
+            load_fixture::<Vec<Channel>>("channel"),
+            Err(FixtureError::Deserialize(..))
+        ));
+    }
+}
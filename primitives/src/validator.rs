@@ -14,15 +14,10 @@ pub enum ValidatorError {
     InvalidTransition,
 }
 
-#[derive(Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-#[serde(transparent)]
-pub struct ValidatorId(
-    #[serde(
-        deserialize_with = "validator_id_from_str",
-        serialize_with = "SerHex::<StrictPfx>::serialize"
-    )]
-    [u8; 20],
-);
+/// It's serialized as a `0x` prefixed checksummed address.
+/// Both the checksummed and the lowercase (or uppercase) `0x` prefixed forms are deserialized.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ValidatorId([u8; 20]);
 
 impl fmt::Debug for ValidatorId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -30,21 +25,37 @@ impl fmt::Debug for ValidatorId {
     }
 }
 
-fn validator_id_from_str<'de, D>(deserializer: D) -> Result<[u8; 20], D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let validator_id = String::deserialize(deserializer)?;
-    if validator_id.is_empty() || validator_id.len() != 42 {
-        return Err(serde::de::Error::custom(
-            "invalid validator id length".to_string(),
-        ));
-    }
+impl<'de> Deserialize<'de> for ValidatorId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let validator_id = String::deserialize(deserializer)?;
+        if validator_id.len() != 42 {
+            return Err(serde::de::Error::custom(
+                "invalid validator id length".to_string(),
+            ));
+        }
+
+        let hex_value = validator_id.strip_prefix("0x").ok_or_else(|| {
+            serde::de::Error::custom("the validator id should be 0x prefixed".to_string())
+        })?;
 
-    <[u8; 20] as FromHex>::from_hex(&validator_id[2..]).map_err(serde::de::Error::custom)
+        // the hex decoding is case insensitive, so the checksum is not verified
+        <[u8; 20] as FromHex>::from_hex(hex_value)
+            .map(ValidatorId)
+            .map_err(serde::de::Error::custom)
+    }
 }
 
 impl ValidatorId {
+    /// The zero address, e.g. the `whoami` of an adapter which hasn't been unlocked
+    pub const ZERO: ValidatorId = ValidatorId([0; 20]);
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::ZERO
+    }
+
     pub fn inner(&self) -> &[u8; 20] {
         &self.0
     }
@@ -148,6 +159,14 @@ pub struct ValidatorDesc {
     pub fee: BigNum,
 }
 
+impl ValidatorDesc {
+    /// The address which receives the validator fees,
+    /// the `fee_addr` if it's set, otherwise the validator `id`
+    pub fn fee_recipient(&self) -> ValidatorId {
+        self.fee_addr.unwrap_or(self.id)
+    }
+}
+
 // Validator Message Types
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -280,4 +299,61 @@ mod test {
         let expected_json = format!(r#""{}""#, validator_id_checksum_str);
         assert_eq!(expected_json, actual_json);
     }
+
+    #[test]
+    fn fee_recipient_falls_back_to_the_validator_id() {
+        let id = ValidatorId::from(&[1; 20]);
+        let fee_addr = ValidatorId::from(&[2; 20]);
+
+        let mut validator = ValidatorDesc {
+            id,
+            fee_addr: None,
+            url: "http://localhost:8005".to_string(),
+            fee: 100.into(),
+        };
+        assert_eq!(id, validator.fee_recipient());
+
+        validator.fee_addr = Some(fee_addr);
+        assert_eq!(fee_addr, validator.fee_recipient());
+    }
+
+    #[test]
+    fn only_the_zero_validator_id_is_zero() {
+        assert!(ValidatorId::ZERO.is_zero());
+        assert!(ValidatorId::from(&[0; 20]).is_zero());
+
+        assert!(!ValidatorId::from(&[1; 20]).is_zero());
+        let mut last_byte = [0; 20];
+        last_byte[19] = 1;
+        assert!(!ValidatorId::from(&last_byte).is_zero());
+    }
+
+    #[test]
+    fn validator_id_is_deserialized_from_checksummed_and_lowercase() {
+        let checksummed: ValidatorId =
+            serde_json::from_str(r#""0xce07CbB7e054514D590a0262C93070D838bFBA2e""#)
+                .expect("Should deserialize a checksummed id");
+        let lowercase: ValidatorId =
+            serde_json::from_str(r#""0xce07cbb7e054514d590a0262c93070d838bfba2e""#)
+                .expect("Should deserialize a lowercase id");
+        assert_eq!(checksummed, lowercase);
+
+        assert_eq!(
+            r#""0xce07CbB7e054514D590a0262C93070D838bFBA2e""#,
+            serde_json::to_string(&lowercase).expect("Should serialize")
+        );
+
+        for invalid in &[
+            r#""ce07cbb7e054514d590a0262c93070d838bfba2e""#,
+            r#""00ce07cbb7e054514d590a0262c93070d838bfba2e""#,
+            r#""0xce07cbb7e054514d590a0262c93070d838bfba""#,
+            r#""0xzz07cbb7e054514d590a0262c93070d838bfba2e""#,
+        ] {
+            assert!(
+                serde_json::from_str::<ValidatorId>(invalid).is_err(),
+                "{} should be rejected",
+                invalid
+            );
+        }
+    }
 }
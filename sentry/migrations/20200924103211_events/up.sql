@@ -0,0 +1,11 @@
+CREATE TABLE events
+(
+    channel_id VARCHAR(66)              NOT NULL REFERENCES channels (id) ON DELETE RESTRICT,
+    created    TIMESTAMP(2) WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    event_type VARCHAR(255)             NOT NULL,
+    publisher  VARCHAR(255),
+    event      JSONB                    NOT NULL
+);
+
+CREATE INDEX idx_events_channel_created ON events (channel_id, created);
+CREATE INDEX idx_events_event_type ON events (event_type);
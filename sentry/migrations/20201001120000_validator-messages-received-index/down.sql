@@ -0,0 +1 @@
+DROP INDEX idx_validator_messages_channel_received;
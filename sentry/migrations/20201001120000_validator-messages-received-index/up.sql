@@ -0,0 +1 @@
+CREATE INDEX idx_validator_messages_channel_received ON validator_messages (channel_id, received DESC);
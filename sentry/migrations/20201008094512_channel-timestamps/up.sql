@@ -0,0 +1,9 @@
+ALTER TABLE channels
+    ADD COLUMN created TIMESTAMP(2) WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    ADD COLUMN updated TIMESTAMP(2) WITH TIME ZONE NOT NULL DEFAULT NOW();
+
+-- the existing channels don't have a row timestamp, so use the millisecond timestamp of their spec
+UPDATE channels
+SET created = to_timestamp((spec ->> 'created')::bigint / 1000.0),
+    updated = to_timestamp((spec ->> 'created')::bigint / 1000.0)
+WHERE spec ->> 'created' ~ '^[0-9]+$';
@@ -55,7 +55,7 @@ pub async fn record(
                     )
                     .ignore();
                     db.zincr(
-                        format!("{}:{}:{}", ChannelReport::AdUnit, event, publisher),
+                        format!("{}:{}:{}", ChannelReport::AdUnit, event, channel.id),
                         ad_unit,
                         1,
                     )
@@ -1,23 +1,46 @@
-use bb8::Pool;
+use bb8::{Pool, RunError};
 use bb8_postgres::tokio_postgres::NoTls;
 use bb8_postgres::PostgresConnectionManager;
 use redis::aio::MultiplexedConnection;
 use redis::RedisError;
 use std::env;
+use std::time::Duration;
+use thiserror::Error;
 
 use lazy_static::lazy_static;
 
 pub mod analytics;
 mod channel;
+mod event;
 pub mod event_aggregate;
 mod validator_message;
 
 pub use self::channel::*;
+pub use self::event::*;
 pub use self::event_aggregate::*;
 pub use self::validator_message::*;
 
 pub type DbPool = Pool<PostgresConnectionManager<NoTls>>;
 
+#[derive(Debug, Error)]
+pub enum PoolError {
+    /// No connection became available before the acquisition timeout of the pool,
+    /// see `POSTGRES_POOL_TIMEOUT`
+    #[error("Postgres pool exhausted: no connection became available in time")]
+    Exhausted,
+    #[error("Postgres: {0}")]
+    Postgres(#[from] bb8_postgres::tokio_postgres::Error),
+}
+
+impl From<RunError<bb8_postgres::tokio_postgres::Error>> for PoolError {
+    fn from(error: RunError<bb8_postgres::tokio_postgres::Error>) -> Self {
+        match error {
+            RunError::User(error) => PoolError::Postgres(error),
+            RunError::TimedOut => PoolError::Exhausted,
+        }
+    }
+}
+
 lazy_static! {
     static ref REDIS_URL: String =
         env::var("REDIS_URL").unwrap_or_else(|_| String::from("redis://127.0.0.1:6379"));
@@ -32,6 +55,13 @@ lazy_static! {
         .parse()
         .unwrap();
     static ref POSTGRES_DB: Option<String> = env::var("POSTGRES_DB").ok();
+    /// How long to wait for a connection of the pool, in milliseconds
+    static ref POSTGRES_POOL_TIMEOUT: Duration = Duration::from_millis(
+        env::var("POSTGRES_POOL_TIMEOUT")
+            .unwrap_or_else(|_| String::from("5000"))
+            .parse()
+            .unwrap()
+    );
 }
 
 pub async fn redis_connection() -> Result<MultiplexedConnection, RedisError> {
@@ -42,6 +72,15 @@ pub async fn redis_connection() -> Result<MultiplexedConnection, RedisError> {
 }
 
 pub async fn postgres_connection() -> Result<DbPool, bb8_postgres::tokio_postgres::Error> {
+    let pg_mgr = PostgresConnectionManager::new(postgres_config(), NoTls);
+
+    Pool::builder()
+        .connection_timeout(*POSTGRES_POOL_TIMEOUT)
+        .build(pg_mgr)
+        .await
+}
+
+fn postgres_config() -> bb8_postgres::tokio_postgres::Config {
     let mut config = bb8_postgres::tokio_postgres::Config::new();
 
     config
@@ -52,11 +91,84 @@ pub async fn postgres_connection() -> Result<DbPool, bb8_postgres::tokio_postgre
     if let Some(db) = POSTGRES_DB.clone() {
         config.dbname(&db);
     }
-    let pg_mgr = PostgresConnectionManager::new(config, NoTls);
 
-    Pool::builder().build(pg_mgr).await
+    config
+}
+
+macro_rules! migration {
+    ($tag:expr) => {
+        (
+            $tag,
+            include_str!(concat!("../migrations/", $tag, "/up.sql")),
+            include_str!(concat!("../migrations/", $tag, "/down.sql")),
+        )
+    };
+}
+
+/// The tag, `up.sql` and `down.sql` of all migrations, in the order they should be applied (without seeds).
+/// Both [`run_migrations`] and [`setup_migrations`] apply these migrations.
+const MIGRATIONS: [(&str, &str, &str); 5] = [
+    migration!("20190806011140_initial-tables"),
+    migration!("20200625092729_channel-targeting-rules"),
+    migration!("20200924103211_events"),
+    migration!("20201001120000_validator-messages-received-index"),
+    migration!("20201008094512_channel-timestamps"),
+];
+
+/// Applies all the migrations that haven't been applied yet, using a connection of the `pool`.
+///
+/// It is safe to call it multiple times (and from multiple Sentries), already applied migrations are skipped.
+/// The applied migrations are tracked in the same table as `migrant`,
+/// so the migrations applied by [`setup_migrations`] or the `migrant` CLI are skipped as well.
+/// Unlike [`setup_migrations`], it never reverts migrations nor seeds the database.
+pub async fn run_migrations(pool: &DbPool) -> Result<(), PoolError> {
+    pool.run(|mut connection| async move {
+        match apply_migrations(&mut connection).await {
+            Ok(()) => Ok(((), connection)),
+            Err(e) => Err((e, connection)),
+        }
+    })
+    .await
+    .map_err(PoolError::from)
+}
+
+async fn apply_migrations(
+    connection: &mut bb8_postgres::tokio_postgres::Client,
+) -> Result<(), bb8_postgres::tokio_postgres::Error> {
+    connection
+        .batch_execute("CREATE TABLE IF NOT EXISTS __migrant_migrations(tag text unique)")
+        .await?;
+
+    for (tag, up, _down) in MIGRATIONS.iter() {
+        let transaction = connection.transaction().await?;
+        // other Sentries wait for the migration to be applied, instead of applying it again
+        transaction
+            .batch_execute("LOCK TABLE __migrant_migrations IN EXCLUSIVE MODE")
+            .await?;
+
+        let applied = transaction
+            .query(
+                "SELECT tag FROM __migrant_migrations WHERE tag = $1",
+                &[tag],
+            )
+            .await?;
+        if applied.is_empty() {
+            transaction.batch_execute(up).await?;
+            transaction
+                .execute("INSERT INTO __migrant_migrations (tag) VALUES ($1)", &[tag])
+                .await?;
+        }
+
+        transaction.commit().await?;
+    }
+
+    Ok(())
 }
 
+/// Applies all the migrations that haven't been applied yet.
+///
+/// It is safe to call it multiple times, already applied migrations are skipped.
+/// In `development` all migrations are reverted first, in order to start with a clean (seeded) database.
 pub async fn setup_migrations(environment: &str) {
     use migrant_lib::{Config, Direction, Migrator, Settings};
 
@@ -75,23 +187,20 @@ pub async fn setup_migrations(environment: &str) {
     // This needs to happen before any call to `Config::use_migrations` or `Config::reload`
     config.use_cli_compatible_tags(true);
 
-    macro_rules! make_migration {
-        ($tag:expr) => {
-            migrant_lib::EmbeddedMigration::with_tag($tag)
-                .up(include_str!(concat!("../migrations/", $tag, "/up.sql")))
-                .down(include_str!(concat!("../migrations/", $tag, "/down.sql")))
-                .boxed()
-        };
-    }
+    let make_migration = |(tag, up, down): &(&str, &'static str, &'static str)| {
+        migrant_lib::EmbeddedMigration::with_tag(tag)
+            .up(*up)
+            .down(*down)
+            .boxed()
+    };
 
-    let mut migrations = vec![
-        make_migration!("20190806011140_initial-tables"),
-        make_migration!("20200625092729_channel-targeting-rules"),
-    ];
+    let mut migrations = MIGRATIONS.iter().map(make_migration).collect::<Vec<_>>();
 
     if environment == "development" {
         // seeds database tables for testing
-        migrations.push(make_migration!("20190806011140_initial-tables/seed"));
+        migrations.push(make_migration(&migration!(
+            "20190806011140_initial-tables/seed"
+        )));
     }
 
     // Define Migrations
@@ -128,3 +237,244 @@ pub async fn setup_migrations(environment: &str) {
         .reload()
         .expect("Reloading config for migration failed");
 }
+
+/// Helpers for running tests against an isolated Postgres schema.
+pub mod tests_postgres {
+    use std::future::Future;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    use bb8::Pool;
+    use bb8_postgres::tokio_postgres::NoTls;
+    use bb8_postgres::PostgresConnectionManager;
+
+    use super::{postgres_config, postgres_connection, run_migrations, DbPool};
+
+    static SCHEMA_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    /// Drops the test schema when it goes out of scope, even if the test panics.
+    struct TestSchema(String);
+
+    impl Drop for TestSchema {
+        fn drop(&mut self) {
+            let drop_schema = format!("DROP SCHEMA IF EXISTS {} CASCADE", self.0);
+
+            // the connections of the test runtime can't be used in `drop`,
+            // so the schema is dropped with a new connection on a separate runtime
+            let dropped = thread::spawn(move || -> Result<(), String> {
+                let mut runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+
+                runtime.block_on(async move {
+                    let pool = postgres_connection().await.map_err(|e| e.to_string())?;
+                    let connection = pool.get().await.map_err(|e| e.to_string())?;
+
+                    connection
+                        .batch_execute(&drop_schema)
+                        .await
+                        .map_err(|e| e.to_string())
+                })
+            })
+            .join();
+
+            match dropped {
+                Ok(Ok(())) => {}
+                Ok(Err(error)) => eprintln!("Failed to drop the test schema {}: {}", self.0, error),
+                Err(_) => eprintln!("Failed to drop the test schema {}", self.0),
+            }
+        }
+    }
+
+    /// Creates a uniquely named schema, applies the migrations to it
+    /// and calls `test` with a `DbPool` which uses this schema.
+    /// The schema is dropped once the returned `Future` of `test` has completed or has panicked.
+    ///
+    /// This allows for running tests against the database in parallel without them interfering.
+    /// # Panics
+    /// When connecting to Postgres, creating the schema or applying the migrations fails.
+    pub async fn with_test_database<F, Fut, T>(test: F) -> T
+    where
+        F: FnOnce(DbPool) -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let schema = format!(
+            "test_{}_{}",
+            std::process::id(),
+            SCHEMA_COUNTER.fetch_add(1, Ordering::SeqCst)
+        );
+
+        let admin_pool = postgres_connection()
+            .await
+            .expect("Should connect to Postgres");
+        execute(&admin_pool, format!("CREATE SCHEMA {}", schema)).await;
+        let _test_schema = TestSchema(schema.clone());
+
+        let mut config = postgres_config();
+        config.options(&format!("-c search_path={}", schema));
+        let pool = Pool::builder()
+            .connection_timeout(*super::POSTGRES_POOL_TIMEOUT)
+            .build(PostgresConnectionManager::new(config, NoTls))
+            .await
+            .expect("Should build the Pool for the test schema");
+
+        run_migrations(&pool)
+            .await
+            .expect("Should apply the migrations");
+
+        test(pool).await
+    }
+
+    async fn execute(pool: &DbPool, statements: String) {
+        pool.run(move |connection| async move {
+            match connection.batch_execute(&statements).await {
+                Ok(()) => Ok(((), connection)),
+                Err(e) => Err((e, connection)),
+            }
+        })
+        .await
+        .expect("Should execute the statements")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::tests_postgres::with_test_database;
+    use super::*;
+    use crate::db::{get_channel_by_id, insert_channel};
+    use futures::future::join;
+    use primitives::util::tests::prep_db::DUMMY_CHANNEL;
+
+    #[tokio::test]
+    async fn with_test_database_isolates_concurrent_tests() {
+        let channel = DUMMY_CHANNEL.clone();
+
+        let insert_channel_test = with_test_database(|pool| {
+            let channel = channel.clone();
+            async move {
+                let is_inserted = insert_channel(&pool, &channel)
+                    .await
+                    .expect("Should insert the channel");
+                assert!(is_inserted);
+
+                get_channel_by_id(&pool, &channel.id)
+                    .await
+                    .expect("Should query the channel")
+            }
+        });
+
+        let empty_test = with_test_database(|pool| {
+            let channel = channel.clone();
+            async move {
+                // the tables should be empty, since no seeds are applied
+                get_channel_by_id(&pool, &channel.id)
+                    .await
+                    .expect("Should query the channel")
+            }
+        });
+
+        let (inserted, empty) = join(insert_channel_test, empty_test).await;
+
+        assert_eq!(Some(channel.id), inserted.map(|channel| channel.id));
+        assert!(empty.is_none(), "Schemas should not share data");
+    }
+
+    #[tokio::test]
+    async fn with_test_database_drops_the_schema_of_a_panicking_test() {
+        use futures::FutureExt;
+        use std::panic::AssertUnwindSafe;
+        use std::sync::{Arc, Mutex};
+
+        let schema = Arc::new(Mutex::new(None));
+
+        let panicking_test = with_test_database(|pool| {
+            let schema = Arc::clone(&schema);
+            async move {
+                let connection = pool.get().await.expect("Should get a connection");
+                let row = connection
+                    .query_one("SELECT current_schema()", &[])
+                    .await
+                    .expect("Should query the current schema");
+                *schema.lock().unwrap() = Some(row.get::<_, String>(0));
+
+                panic!("The test fails");
+            }
+        });
+        assert!(AssertUnwindSafe(panicking_test)
+            .catch_unwind()
+            .await
+            .is_err());
+
+        let schema = schema
+            .lock()
+            .unwrap()
+            .clone()
+            .expect("Should have the schema of the test");
+        let pool = postgres_connection()
+            .await
+            .expect("Should connect to Postgres");
+        let connection = pool.get().await.expect("Should get a connection");
+        let exists: bool = connection
+            .query_one(
+                "SELECT EXISTS(SELECT 1 FROM information_schema.schemata WHERE schema_name = $1)",
+                &[&schema],
+            )
+            .await
+            .expect("Should query the schemas")
+            .get(0);
+
+        assert!(!exists, "The schema should have been dropped");
+    }
+
+    #[tokio::test]
+    async fn run_migrations_is_idempotent() {
+        with_test_database(|pool| async move {
+            // the migrations have already been applied by `with_test_database`
+            run_migrations(&pool)
+                .await
+                .expect("Should skip the applied migrations");
+            run_migrations(&pool)
+                .await
+                .expect("Should skip the applied migrations");
+
+            let channel = DUMMY_CHANNEL.clone();
+            assert!(insert_channel(&pool, &channel)
+                .await
+                .expect("Should insert the channel into the migrated tables"));
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn setup_migrations_is_idempotent() {
+        // the connection should be available before applying the migrations
+        let _pool = postgres_connection()
+            .await
+            .expect("Should connect to Postgres");
+
+        // any environment other than `development` does not revert the applied migrations
+        setup_migrations("production").await;
+        setup_migrations("production").await;
+    }
+
+    #[tokio::test]
+    async fn exhausted_pool_times_out_with_a_clear_error() {
+        let pool = Pool::builder()
+            .max_size(1)
+            .connection_timeout(Duration::from_millis(100))
+            .build(PostgresConnectionManager::new(postgres_config(), NoTls))
+            .await
+            .expect("Should build the Pool");
+
+        // holds the only connection of the pool while waiting for another one
+        let held = pool.get().await.expect("Should get the only connection");
+        let error = get_channel_by_id(&pool, &DUMMY_CHANNEL.id)
+            .await
+            .expect_err("Should time out waiting for a connection");
+        drop(held);
+
+        assert!(matches!(error, PoolError::Exhausted));
+        assert_eq!(
+            "Postgres pool exhausted: no connection became available in time",
+            error.to_string()
+        );
+    }
+}
@@ -1,10 +1,11 @@
-use crate::db::DbPool;
+use crate::db::{DbPool, PoolError};
 use crate::epoch;
 use crate::Auth;
-use bb8::RunError;
 use bb8_postgres::tokio_postgres::types::ToSql;
-use chrono::Utc;
-use primitives::analytics::{AnalyticsData, AnalyticsQuery, ANALYTICS_QUERY_LIMIT};
+use chrono::{Duration, Utc};
+use primitives::analytics::{
+    AnalyticsData, AnalyticsQuery, Metric, Timeframe, ANALYTICS_QUERY_LIMIT,
+};
 use primitives::sentry::{AdvancedAnalyticsResponse, ChannelReport, PublisherReport};
 use primitives::{ChannelId, ValidatorId};
 use redis::aio::MultiplexedConnection;
@@ -21,7 +22,7 @@ pub enum AnalyticsType {
 pub async fn advertiser_channel_ids(
     pool: &DbPool,
     creator: &ValidatorId,
-) -> Result<Vec<ChannelId>, RunError<bb8_postgres::tokio_postgres::Error>> {
+) -> Result<Vec<ChannelId>, PoolError> {
     pool.run(move |connection| async move {
         match connection
             .prepare("SELECT id FROM channels WHERE creator = $1")
@@ -38,13 +39,13 @@ pub async fn advertiser_channel_ids(
         }
     })
     .await
+    .map_err(PoolError::from)
 }
 
-fn metric_to_column(metric: &str) -> String {
+fn metric_to_column(metric: Metric) -> &'static str {
     match metric {
-        "eventCounts" => "count".to_string(),
-        "eventPayouts" => "payout".to_string(),
-        _ => "count".to_string(),
+        Metric::EventCounts => "count",
+        Metric::EventPayouts => "payout",
     }
 }
 
@@ -54,18 +55,26 @@ pub async fn get_analytics(
     analytics_type: AnalyticsType,
     segment_by_channel: bool,
     channel_id: Option<&ChannelId>,
-) -> Result<Vec<AnalyticsData>, RunError<bb8_postgres::tokio_postgres::Error>> {
+) -> Result<Vec<AnalyticsData>, PoolError> {
     // converts metric to column
-    let metric = metric_to_column(&query.metric);
+    let metric = metric_to_column(query.metric);
 
     let mut params = Vec::<&(dyn ToSql + Sync)>::new();
     let applied_limit = query.limit.min(ANALYTICS_QUERY_LIMIT);
-    let (interval, period) = get_time_frame(&query.timeframe);
-    let time_limit = Utc::now().timestamp() - period;
+    let (interval, period) = query.timeframe.time_frame();
+    let time_limit = match query.start {
+        Some(start) => start.timestamp(),
+        None => query.end.unwrap_or_else(Utc::now).timestamp() - period,
+    };
 
     let mut where_clauses = vec![format!("created > to_timestamp({})", time_limit)];
 
-    params.push(&query.event_type);
+    if let Some(end) = query.end {
+        where_clauses.push(format!("created <= to_timestamp({})", end.timestamp()));
+    }
+
+    let event_type = query.event_type.as_str();
+    params.push(&event_type);
 
     where_clauses.extend(vec![
         format!("event_type = ${}", params.len()),
@@ -137,21 +146,59 @@ pub async fn get_analytics(
         }
     })
     .await
+    .map_err(PoolError::from)
 }
 
-fn get_time_frame(timeframe: &str) -> (i64, i64) {
-    let minute = 60 * 1000;
-    let hour = 60 * minute;
-    let day = 24 * hour;
-
-    match timeframe {
-        "year" => (30 * day, 365 * day),
-        "month" => (day, 30 * day),
-        "week" => (6 * hour, 7 * day),
-        "day" => (hour, day),
-        "hour" => (minute, hour),
-        _ => (hour, day),
-    }
+/// The size of the buckets of the `timeframe` in seconds, i.e. its interval, see [`Timeframe::time_frame`].
+fn timeframe_to_bucket_seconds(timeframe: Timeframe) -> f64 {
+    (timeframe.time_frame().0 / 1000) as f64
+}
+
+/// Fetches the global analytics for the `query`, grouped in buckets by its `timeframe`.
+/// The buckets are aligned to the Unix epoch, e.g. `6h` for a `Week` starts at 00:00, 06:00, etc.
+///
+/// All the user input is passed as query parameters,
+/// except for the metric column, which is a literal derived from the validated [`Metric`].
+pub async fn fetch_analytics(
+    pool: &DbPool,
+    query: &AnalyticsQuery,
+) -> Result<Vec<AnalyticsData>, PoolError> {
+    let metric = metric_to_column(query.metric);
+    let bucket = timeframe_to_bucket_seconds(query.timeframe);
+    let event_type = query.event_type.as_str();
+    let limit = i64::from(query.limit.min(ANALYTICS_QUERY_LIMIT));
+
+    let end = query.end.unwrap_or_else(Utc::now);
+    let start = match query.start {
+        Some(start) => start,
+        None => end - Duration::milliseconds(query.timeframe.time_frame().1),
+    };
+
+    let sql_query = format!(
+        "SELECT SUM({metric}::numeric)::varchar AS value, floor(extract(epoch FROM created)::float8 / $1) * $1 AS time \
+        FROM event_aggregates \
+        WHERE event_type = $2 AND earner IS NULL AND {metric} IS NOT NULL AND created > $3 AND created <= $4 \
+        GROUP BY time ORDER BY time LIMIT $5",
+        metric = metric
+    );
+
+    pool.run(move |connection| async move {
+        match connection.prepare(&sql_query).await {
+            Ok(stmt) => match connection
+                .query(&stmt, &[&bucket, &event_type, &start, &end, &limit])
+                .await
+            {
+                Ok(rows) => {
+                    let analytics = rows.iter().map(AnalyticsData::from).collect();
+                    Ok((analytics, connection))
+                }
+                Err(e) => Err((e, connection)),
+            },
+            Err(e) => Err((e, connection)),
+        }
+    })
+    .await
+    .map_err(PoolError::from)
 }
 
 async fn stat_pair(
@@ -236,3 +283,166 @@ pub async fn get_advanced_reports(
         by_channel_stats,
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::{insert_channel, tests_postgres::with_test_database};
+    use chrono::{DateTime, TimeZone};
+    use primitives::util::tests::prep_db::{DUMMY_CHANNEL, IDS};
+
+    async fn insert_aggregate(
+        pool: &DbPool,
+        channel_id: ChannelId,
+        created: DateTime<Utc>,
+        earner: Option<ValidatorId>,
+        count: &str,
+    ) {
+        let count = count.to_string();
+        pool.run(move |connection| async move {
+            match connection
+                .execute(
+                    "INSERT INTO event_aggregates (channel_id, created, event_type, earner, count, payout) VALUES ($1, $2, 'IMPRESSION', $3, $4, $4)",
+                    &[&channel_id, &created, &earner, &count],
+                )
+                .await
+            {
+                Ok(_) => Ok(((), connection)),
+                Err(e) => Err((e, connection)),
+            }
+        })
+        .await
+        .expect("Should insert the event aggregate")
+    }
+
+    #[tokio::test]
+    async fn fetch_analytics_aggregates_the_events_by_timeframe() {
+        with_test_database(|pool| async move {
+            let channel = DUMMY_CHANNEL.clone();
+            insert_channel(&pool, &channel)
+                .await
+                .expect("Should insert the channel");
+
+            let first_hour = Utc.ymd(2020, 10, 1).and_hms(10, 0, 0);
+            let second_hour = Utc.ymd(2020, 10, 1).and_hms(11, 0, 0);
+
+            insert_aggregate(
+                &pool,
+                channel.id,
+                first_hour + Duration::minutes(5),
+                None,
+                "2",
+            )
+            .await;
+            insert_aggregate(
+                &pool,
+                channel.id,
+                first_hour + Duration::minutes(45),
+                None,
+                "3",
+            )
+            .await;
+            insert_aggregate(
+                &pool,
+                channel.id,
+                second_hour + Duration::minutes(1),
+                None,
+                "7",
+            )
+            .await;
+            // the events of the earners are not part of the global analytics
+            insert_aggregate(
+                &pool,
+                channel.id,
+                first_hour + Duration::minutes(5),
+                Some(IDS["publisher"]),
+                "2",
+            )
+            .await;
+            // outside of the time range
+            insert_aggregate(
+                &pool,
+                channel.id,
+                first_hour - Duration::days(2),
+                None,
+                "100",
+            )
+            .await;
+
+            let query: AnalyticsQuery = serde_json::from_value(serde_json::json!({
+                "eventType": "IMPRESSION",
+                "metric": "eventCounts",
+                "timeframe": "day",
+                "start": "2020-10-01T00:00:00Z",
+                "end": "2020-10-02T00:00:00Z",
+            }))
+            .expect("Should deserialize the query");
+
+            let analytics = fetch_analytics(&pool, &query)
+                .await
+                .expect("Should fetch the analytics");
+
+            let buckets = analytics
+                .iter()
+                .map(|data| (data.time, data.value.as_str()))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                vec![
+                    (first_hour.timestamp() as f64, "5"),
+                    (second_hour.timestamp() as f64, "7"),
+                ],
+                buckets
+            );
+
+            let limited = AnalyticsQuery { limit: 1, ..query };
+            let analytics = fetch_analytics(&pool, &limited)
+                .await
+                .expect("Should fetch the analytics");
+            assert_eq!(1, analytics.len());
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn fetch_analytics_buckets_by_the_interval_of_the_timeframe() {
+        with_test_database(|pool| async move {
+            let channel = DUMMY_CHANNEL.clone();
+            insert_channel(&pool, &channel)
+                .await
+                .expect("Should insert the channel");
+
+            let day = Utc.ymd(2020, 10, 1).and_hms(0, 0, 0);
+
+            insert_aggregate(&pool, channel.id, day + Duration::hours(1), None, "2").await;
+            insert_aggregate(&pool, channel.id, day + Duration::hours(5), None, "3").await;
+            insert_aggregate(&pool, channel.id, day + Duration::hours(7), None, "7").await;
+
+            let query: AnalyticsQuery = serde_json::from_value(serde_json::json!({
+                "eventType": "IMPRESSION",
+                "metric": "eventCounts",
+                "timeframe": "week",
+                "start": "2020-09-28T00:00:00Z",
+                "end": "2020-10-05T00:00:00Z",
+            }))
+            .expect("Should deserialize the query");
+
+            let analytics = fetch_analytics(&pool, &query)
+                .await
+                .expect("Should fetch the analytics");
+
+            // the `Week` timeframe has `6h` buckets
+            let buckets = analytics
+                .iter()
+                .map(|data| (data.time, data.value.as_str()))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                vec![
+                    (day.timestamp() as f64, "5"),
+                    ((day + Duration::hours(6)).timestamp() as f64, "7"),
+                ],
+                buckets
+            );
+        })
+        .await
+    }
+}
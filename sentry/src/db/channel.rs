@@ -1,8 +1,9 @@
-use crate::db::DbPool;
-use bb8::RunError;
-use chrono::Utc;
+use crate::db::{DbPool, PoolError};
+use chrono::{DateTime, Utc};
 use primitives::validator::MessageTypes;
 use primitives::{targeting::Rules, Channel, ChannelId, ValidatorId};
+use serde::Serialize;
+use std::collections::HashMap;
 use std::str::FromStr;
 
 pub use list_channels::list_channels;
@@ -10,7 +11,7 @@ pub use list_channels::list_channels;
 pub async fn get_channel_by_id(
     pool: &DbPool,
     id: &ChannelId,
-) -> Result<Option<Channel>, RunError<bb8_postgres::tokio_postgres::Error>> {
+) -> Result<Option<Channel>, PoolError> {
     pool
         .run(move |connection| {
             async move {
@@ -24,13 +25,110 @@ pub async fn get_channel_by_id(
             }
         })
         .await
+        .map_err(PoolError::from)
+}
+
+/// Fetches the Channels with the given `ids`, the ones which don't exist are omitted.
+pub async fn fetch_channels(pool: &DbPool, ids: &[ChannelId]) -> Result<Vec<Channel>, PoolError> {
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    pool.run(move |connection| async move {
+        match connection
+            .prepare("SELECT id, creator, deposit_asset, deposit_amount, valid_until, targeting_rules, spec, exhausted FROM channels WHERE id = ANY($1)")
+            .await
+        {
+            Ok(select) => match connection.query(&select, &[&ids]).await {
+                Ok(rows) => Ok((rows.iter().map(Channel::from).collect(), connection)),
+                Err(e) => Err((e, connection)),
+            },
+            Err(e) => Err((e, connection)),
+        }
+    })
+    .await
+    .map_err(PoolError::from)
+}
+
+/// The timestamps of the database row of a `Channel`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ChannelTimestamps {
+    pub created: DateTime<Utc>,
+    /// Changes whenever the targeting rules or the exhausted state of the `Channel` are updated
+    pub updated: DateTime<Utc>,
+}
+
+pub async fn get_channel_timestamps(
+    pool: &DbPool,
+    id: &ChannelId,
+) -> Result<Option<ChannelTimestamps>, PoolError> {
+    pool.run(move |connection| async move {
+        match connection
+            .prepare("SELECT created, updated FROM channels WHERE id = $1 LIMIT 1")
+            .await
+        {
+            Ok(select) => match connection.query(&select, &[&id]).await {
+                Ok(results) => {
+                    let timestamps = results.get(0).map(|row| ChannelTimestamps {
+                        created: row.get("created"),
+                        updated: row.get("updated"),
+                    });
+
+                    Ok((timestamps, connection))
+                }
+                Err(e) => Err((e, connection)),
+            },
+            Err(e) => Err((e, connection)),
+        }
+    })
+    .await
+    .map_err(PoolError::from)
+}
+
+/// Fetches the timestamps of the Channels with the given `ids`, the ones which don't exist are omitted.
+pub async fn fetch_channel_timestamps(
+    pool: &DbPool,
+    ids: &[ChannelId],
+) -> Result<HashMap<ChannelId, ChannelTimestamps>, PoolError> {
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    pool.run(move |connection| async move {
+        match connection
+            .prepare("SELECT id, created, updated FROM channels WHERE id = ANY($1)")
+            .await
+        {
+            Ok(select) => match connection.query(&select, &[&ids]).await {
+                Ok(rows) => {
+                    let timestamps = rows
+                        .iter()
+                        .map(|row| {
+                            let timestamps = ChannelTimestamps {
+                                created: row.get("created"),
+                                updated: row.get("updated"),
+                            };
+
+                            (row.get("id"), timestamps)
+                        })
+                        .collect();
+
+                    Ok((timestamps, connection))
+                }
+                Err(e) => Err((e, connection)),
+            },
+            Err(e) => Err((e, connection)),
+        }
+    })
+    .await
+    .map_err(PoolError::from)
 }
 
 pub async fn get_channel_by_id_and_validator(
     pool: &DbPool,
     id: &ChannelId,
     validator_id: &ValidatorId,
-) -> Result<Option<Channel>, RunError<bb8_postgres::tokio_postgres::Error>> {
+) -> Result<Option<Channel>, PoolError> {
     pool
         .run(move |connection| {
             async move {
@@ -48,16 +146,17 @@ pub async fn get_channel_by_id_and_validator(
             }
         })
         .await
+        .map_err(PoolError::from)
 }
 
-pub async fn insert_channel(
-    pool: &DbPool,
-    channel: &Channel,
-) -> Result<bool, RunError<bb8_postgres::tokio_postgres::Error>> {
+/// Inserts the `Channel` if one with the same `id` does not exist yet.
+///
+/// Returns `false` if the channel already exists, since `ON CONFLICT DO NOTHING` makes the insert a no-op.
+pub async fn insert_channel(pool: &DbPool, channel: &Channel) -> Result<bool, PoolError> {
     pool
         .run(move |connection| {
             async move {
-                    match connection.prepare("INSERT INTO channels (id, creator, deposit_asset, deposit_amount, valid_until, targeting_rules, spec) values ($1, $2, $3, $4, $5, $6, $7)").await {
+                    match connection.prepare("INSERT INTO channels (id, creator, deposit_asset, deposit_amount, valid_until, targeting_rules, spec) values ($1, $2, $3, $4, $5, $6, $7) ON CONFLICT DO NOTHING").await {
                     Ok(stmt) => match connection.execute(&stmt, &[&channel.id, &channel.creator, &channel.deposit_asset, &channel.deposit_amount, &channel.valid_until, &channel.targeting_rules, &channel.spec]).await {
                         Ok(row) => {
                             let inserted = row == 1;
@@ -70,16 +169,17 @@ pub async fn insert_channel(
             }
         })
         .await
+        .map_err(PoolError::from)
 }
 
 pub async fn update_targeting_rules(
     pool: &DbPool,
     channel_id: &ChannelId,
     targeting_rules: &Rules,
-) -> Result<bool, RunError<bb8_postgres::tokio_postgres::Error>> {
+) -> Result<bool, PoolError> {
     pool.run(move |connection| async move {
         match connection
-            .prepare("UPDATE channels SET targeting_rules=$1 WHERE id=$2")
+            .prepare("UPDATE channels SET targeting_rules=$1, updated=NOW() WHERE id=$2")
             .await
         {
             Ok(stmt) => match connection
@@ -96,6 +196,7 @@ pub async fn update_targeting_rules(
         }
     })
     .await
+    .map_err(PoolError::from)
 }
 
 pub async fn insert_validator_messages(
@@ -103,7 +204,7 @@ pub async fn insert_validator_messages(
     channel: &Channel,
     from: &ValidatorId,
     validator_message: &MessageTypes,
-) -> Result<bool, RunError<bb8_postgres::tokio_postgres::Error>> {
+) -> Result<bool, PoolError> {
     pool
         .run(move | connection| {
             async move {
@@ -120,16 +221,17 @@ pub async fn insert_validator_messages(
             }
         })
         .await
+        .map_err(PoolError::from)
 }
 
 pub async fn update_exhausted_channel(
     pool: &DbPool,
     channel: &Channel,
     index: i32,
-) -> Result<bool, RunError<bb8_postgres::tokio_postgres::Error>> {
+) -> Result<bool, PoolError> {
     pool.run(move |connection| async move {
         match connection
-            .prepare("UPDATE channels SET exhausted[$1] = true WHERE id = $2")
+            .prepare("UPDATE channels SET exhausted[$1] = true, updated = NOW() WHERE id = $2")
             .await
         {
             Ok(stmt) => match connection.execute(&stmt, &[&index, &channel.id]).await {
@@ -143,11 +245,11 @@ pub async fn update_exhausted_channel(
         }
     })
     .await
+    .map_err(PoolError::from)
 }
 
 mod list_channels {
-    use crate::db::DbPool;
-    use bb8::RunError;
+    use crate::db::{DbPool, PoolError};
     use bb8_postgres::tokio_postgres::types::{accepts, FromSql, ToSql, Type};
     use chrono::{DateTime, Utc};
     use primitives::sentry::ChannelListResponse;
@@ -174,7 +276,7 @@ mod list_channels {
         creator: &Option<String>,
         validator: &Option<ValidatorId>,
         valid_until_ge: &DateTime<Utc>,
-    ) -> Result<ChannelListResponse, RunError<bb8_postgres::tokio_postgres::Error>> {
+    ) -> Result<ChannelListResponse, PoolError> {
         let validator = validator.as_ref().map(|validator_id| {
             serde_json::Value::from_str(&format!(r#"[{{"id": "{}"}}]"#, validator_id))
                 .expect("Not a valid json")
@@ -226,7 +328,7 @@ mod list_channels {
     async fn list_channels_total_count<'a>(
         pool: &DbPool,
         (where_clauses, params): (&'a [String], Vec<&'a (dyn ToSql + Sync)>),
-    ) -> Result<u64, RunError<bb8_postgres::tokio_postgres::Error>> {
+    ) -> Result<u64, PoolError> {
         pool.run(move |connection| async move {
             let statement = format!(
                 "SELECT COUNT(id)::varchar FROM channels WHERE {}",
@@ -241,6 +343,7 @@ mod list_channels {
             }
         })
         .await
+        .map_err(PoolError::from)
     }
 
     fn channel_list_query_params<'a>(
@@ -264,3 +367,123 @@ mod list_channels {
         (where_clauses, params)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::tests_postgres::with_test_database;
+    use chrono::Duration;
+    use primitives::util::tests::prep_db::DUMMY_CHANNEL;
+    use std::collections::HashSet;
+
+    #[tokio::test]
+    async fn insert_channel_is_idempotent_and_get_channel_by_id_returns_it() {
+        with_test_database(|pool| async move {
+            let channel = DUMMY_CHANNEL.clone();
+
+            let is_inserted = insert_channel(&pool, &channel)
+                .await
+                .expect("Should insert the channel");
+            assert!(is_inserted);
+
+            let is_inserted_again = insert_channel(&pool, &channel)
+                .await
+                .expect("Inserting the same channel should not error");
+            assert!(
+                !is_inserted_again,
+                "Second insert should be a no-op because of ON CONFLICT DO NOTHING"
+            );
+
+            let fetched = get_channel_by_id(&pool, &channel.id)
+                .await
+                .expect("Should fetch the channel")
+                .expect("Channel should exist");
+
+            assert_eq!(channel.id, fetched.id);
+            assert_eq!(channel.creator, fetched.creator);
+            assert_eq!(channel.deposit_asset, fetched.deposit_asset);
+            assert_eq!(channel.deposit_amount, fetched.deposit_amount);
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn channel_timestamps_are_populated_and_updated() {
+        with_test_database(|pool| async move {
+            let channel = DUMMY_CHANNEL.clone();
+            assert_eq!(
+                None,
+                get_channel_timestamps(&pool, &channel.id)
+                    .await
+                    .expect("Should query the timestamps")
+            );
+
+            let before_insert = Utc::now() - Duration::seconds(1);
+            insert_channel(&pool, &channel)
+                .await
+                .expect("Should insert the channel");
+
+            let inserted = get_channel_timestamps(&pool, &channel.id)
+                .await
+                .expect("Should query the timestamps")
+                .expect("Should have timestamps");
+            assert!(inserted.created > before_insert);
+            assert_eq!(inserted.created, inserted.updated);
+
+            // `NOW()` is the start time of the transaction, so wait for the next one
+            tokio::time::delay_for(std::time::Duration::from_millis(50)).await;
+            update_exhausted_channel(&pool, &channel, 1)
+                .await
+                .expect("Should update the channel");
+
+            let updated = get_channel_timestamps(&pool, &channel.id)
+                .await
+                .expect("Should query the timestamps")
+                .expect("Should have timestamps");
+            assert_eq!(inserted.created, updated.created);
+            assert!(updated.updated > inserted.updated);
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn fetch_channels_omits_the_missing_ids() {
+        with_test_database(|pool| async move {
+            let first = DUMMY_CHANNEL.clone();
+            let second = Channel {
+                id: ChannelId::from([2_u8; 32]),
+                ..DUMMY_CHANNEL.clone()
+            };
+            let missing = ChannelId::from([3_u8; 32]);
+
+            for channel in [&first, &second].iter() {
+                insert_channel(&pool, channel)
+                    .await
+                    .expect("Should insert the channel");
+            }
+
+            assert!(fetch_channels(&pool, &[])
+                .await
+                .expect("Should fetch nothing")
+                .is_empty());
+
+            let fetched = fetch_channels(&pool, &[missing, second.id, first.id])
+                .await
+                .expect("Should fetch the channels")
+                .into_iter()
+                .map(|channel| channel.id)
+                .collect::<HashSet<_>>();
+
+            let expected = vec![first.id, second.id]
+                .into_iter()
+                .collect::<HashSet<_>>();
+            assert_eq!(expected, fetched);
+
+            let fetched = fetch_channels(&pool, &[missing])
+                .await
+                .expect("Should fetch the channels");
+            assert!(fetched.is_empty());
+        })
+        .await
+    }
+}
@@ -0,0 +1,184 @@
+use crate::db::{DbPool, PoolError};
+use bb8_postgres::tokio_postgres::types::{Json, ToSql};
+use bb8_postgres::tokio_postgres::{Client, Error};
+use primitives::sentry::Event;
+use primitives::{ChannelId, ValidatorId};
+
+/// Postgres allows up to `u16::MAX` parameters in a statement,
+/// every event has 3 of them and all events share the `channel_id` one.
+const EVENTS_PER_INSERT: usize = (u16::MAX as usize - 1) / 3;
+
+type Statement<'a> = (String, Vec<&'a (dyn ToSql + Sync)>);
+
+/// Inserts all the `events` of the Channel with multi-row `INSERT`s
+/// of up to [`EVENTS_PER_INSERT`] events each, in a single transaction.
+///
+/// Returns the number of inserted rows.
+///
+/// The submitted events are not persisted with it yet, since the `events` table has no retention:
+/// before calling it on event submission, the table should be partitioned by `created`
+/// so the old partitions can be dropped, and the insert should happen before the events
+/// are recorded in Redis, so a failed insert doesn't leave them counted.
+pub async fn insert_events(
+    pool: &DbPool,
+    channel_id: &ChannelId,
+    events: &[Event],
+) -> Result<u64, PoolError> {
+    if events.is_empty() {
+        return Ok(0);
+    }
+
+    let rows: Vec<(String, Option<ValidatorId>, Json<&Event>)> = events
+        .iter()
+        .map(|event| {
+            let publisher = match event {
+                Event::Impression { publisher, .. } | Event::Click { publisher, .. } => {
+                    Some(*publisher)
+                }
+                Event::UpdateTargeting { .. } | Event::Close => None,
+            };
+
+            (event.to_string(), publisher, Json(event))
+        })
+        .collect();
+
+    let statements: Vec<Statement<'_>> = rows
+        .chunks(EVENTS_PER_INSERT)
+        .map(|chunk| {
+            let mut params = Vec::<&(dyn ToSql + Sync)>::with_capacity(1 + chunk.len() * 3);
+            params.push(channel_id);
+
+            let mut values = Vec::with_capacity(chunk.len());
+            for (event_type, publisher, event) in chunk.iter() {
+                params.push(event_type);
+                params.push(publisher);
+                params.push(event);
+
+                let len = params.len();
+                values.push(format!("($1, ${}, ${}, ${})", len - 2, len - 1, len));
+            }
+
+            let statement = format!(
+                "INSERT INTO events (channel_id, event_type, publisher, event) VALUES {}",
+                values.join(", ")
+            );
+
+            (statement, params)
+        })
+        .collect();
+
+    pool.run(move |mut connection| async move {
+        match execute_in_transaction(&mut connection, &statements).await {
+            Ok(inserted) => Ok((inserted, connection)),
+            Err(e) => Err((e, connection)),
+        }
+    })
+    .await
+    .map_err(PoolError::from)
+}
+
+/// Returns the total number of the affected rows
+async fn execute_in_transaction(
+    connection: &mut Client,
+    statements: &[Statement<'_>],
+) -> Result<u64, Error> {
+    let transaction = connection.transaction().await?;
+
+    let mut affected = 0;
+    for (statement, params) in statements {
+        affected += transaction
+            .execute(statement.as_str(), params.as_slice())
+            .await?;
+    }
+
+    transaction.commit().await?;
+
+    Ok(affected)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::{insert_channel, tests_postgres::with_test_database};
+    use primitives::util::tests::prep_db::{DUMMY_CHANNEL, IDS};
+
+    #[tokio::test]
+    async fn insert_events_inserts_the_whole_batch() {
+        with_test_database(|pool| async move {
+            let channel = DUMMY_CHANNEL.clone();
+            insert_channel(&pool, &channel)
+                .await
+                .expect("Should insert the channel");
+
+            let inserted = insert_events(&pool, &channel.id, &[])
+                .await
+                .expect("Should not insert anything");
+            assert_eq!(0, inserted);
+
+            let events = vec![
+                Event::Impression {
+                    publisher: IDS["publisher"],
+                    ad_unit: None,
+                    ad_slot: None,
+                    referrer: None,
+                },
+                Event::Click {
+                    publisher: IDS["publisher"],
+                    ad_unit: None,
+                    ad_slot: None,
+                    referrer: None,
+                },
+                Event::Impression {
+                    publisher: IDS["publisher2"],
+                    ad_unit: None,
+                    ad_slot: None,
+                    referrer: Some("https://adex.network".to_string()),
+                },
+                Event::Close,
+            ];
+
+            let inserted = insert_events(&pool, &channel.id, &events)
+                .await
+                .expect("Should insert the events");
+            assert_eq!(4, inserted);
+
+            let (total, impressions) = pool
+                .run(move |connection| async move {
+                    match connection
+                        .query_one(
+                            "SELECT COUNT(*), COUNT(*) FILTER (WHERE event_type = 'IMPRESSION') FROM events WHERE channel_id = $1",
+                            &[&channel.id],
+                        )
+                        .await
+                    {
+                        Ok(row) => Ok(((row.get::<_, i64>(0), row.get::<_, i64>(1)), connection)),
+                        Err(e) => Err((e, connection)),
+                    }
+                })
+                .await
+                .expect("Should count the events");
+
+            assert_eq!(4, total);
+            assert_eq!(2, impressions);
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn insert_events_splits_a_batch_over_the_parameters_limit() {
+        with_test_database(|pool| async move {
+            let channel = DUMMY_CHANNEL.clone();
+            insert_channel(&pool, &channel)
+                .await
+                .expect("Should insert the channel");
+
+            let events = vec![Event::Close; EVENTS_PER_INSERT + 1];
+
+            let inserted = insert_events(&pool, &channel.id, &events)
+                .await
+                .expect("Should insert the events in more than one statement");
+            assert_eq!(events.len() as u64, inserted);
+        })
+        .await
+    }
+}
@@ -1,5 +1,4 @@
-use crate::db::DbPool;
-use bb8::RunError;
+use crate::db::{DbPool, PoolError};
 use bb8_postgres::tokio_postgres::binary_copy::BinaryCopyInWriter;
 use bb8_postgres::tokio_postgres::types::{ToSql, Type};
 use bb8_postgres::tokio_postgres::Error;
@@ -16,7 +15,7 @@ use std::ops::Add;
 pub async fn latest_approve_state(
     pool: &DbPool,
     channel: &Channel,
-) -> Result<Option<ApproveStateValidatorMessage>, RunError<bb8_postgres::tokio_postgres::Error>> {
+) -> Result<Option<ApproveStateValidatorMessage>, PoolError> {
     pool
         .run(move |connection| {
             async move {
@@ -30,13 +29,14 @@ pub async fn latest_approve_state(
             }
         })
         .await
+        .map_err(PoolError::from)
 }
 
 pub async fn latest_new_state(
     pool: &DbPool,
     channel: &Channel,
     state_root: &str,
-) -> Result<Option<NewStateValidatorMessage>, RunError<bb8_postgres::tokio_postgres::Error>> {
+) -> Result<Option<NewStateValidatorMessage>, PoolError> {
     pool
     .run(move |connection| {
         async move {
@@ -50,13 +50,14 @@ pub async fn latest_new_state(
         }
     })
     .await
+    .map_err(PoolError::from)
 }
 
 pub async fn latest_heartbeats(
     pool: &DbPool,
     channel_id: &ChannelId,
     validator_id: &ValidatorId,
-) -> Result<Vec<HeartbeatValidatorMessage>, RunError<bb8_postgres::tokio_postgres::Error>> {
+) -> Result<Vec<HeartbeatValidatorMessage>, PoolError> {
     pool
     .run(move |connection| {
         async move {
@@ -70,6 +71,7 @@ pub async fn latest_heartbeats(
         }
     })
     .await
+    .map_err(PoolError::from)
 }
 
 pub async fn list_event_aggregates(
@@ -78,7 +80,7 @@ pub async fn list_event_aggregates(
     limit: u32,
     from: &Option<ValidatorId>,
     after: &Option<DateTime<Utc>>,
-) -> Result<Vec<EventAggregate>, RunError<bb8_postgres::tokio_postgres::Error>> {
+) -> Result<Vec<EventAggregate>, PoolError> {
     let (mut where_clauses, mut params) = (vec![], Vec::<&(dyn ToSql + Sync)>::new());
     let id = channel_id.to_string();
     params.push(&id);
@@ -164,7 +166,7 @@ pub async fn insert_event_aggregate(
     pool: &DbPool,
     channel_id: &ChannelId,
     event: &EventAggregate,
-) -> Result<bool, RunError<bb8_postgres::tokio_postgres::Error>> {
+) -> Result<bool, PoolError> {
     let mut data: Vec<EventData> = Vec::new();
 
     for (event_type, aggr) in &event.events {
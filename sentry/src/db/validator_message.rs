@@ -1,6 +1,6 @@
-use crate::db::DbPool;
-use bb8::RunError;
+use crate::db::{DbPool, PoolError};
 use bb8_postgres::tokio_postgres::types::ToSql;
+use chrono::{DateTime, Utc};
 use primitives::sentry::ValidatorMessage;
 use primitives::{ChannelId, ValidatorId};
 
@@ -10,7 +10,7 @@ pub async fn get_validator_messages(
     validator_id: &Option<ValidatorId>,
     message_types: &[String],
     limit: u64,
-) -> Result<Vec<ValidatorMessage>, RunError<bb8_postgres::tokio_postgres::Error>> {
+) -> Result<Vec<ValidatorMessage>, PoolError> {
     let mut where_clauses: Vec<String> = vec!["channel_id = $1".to_string()];
     let mut params: Vec<&(dyn ToSql + Sync)> = vec![&channel_id];
 
@@ -37,6 +37,41 @@ pub async fn get_validator_messages(
             }
         })
         .await
+        .map_err(PoolError::from)
+}
+
+/// The maximum number of messages returned by [`latest_messages_since`].
+pub const LATEST_MESSAGES_LIMIT: i64 = 100;
+
+/// Returns the latest messages of the Channel received after `since`, newest first.
+/// It backs the `since` query of the validator messages list route.
+///
+/// The query is covered by the `(channel_id, received DESC)` index of `validator_messages`.
+pub async fn latest_messages_since(
+    pool: &DbPool,
+    channel_id: &ChannelId,
+    since: DateTime<Utc>,
+) -> Result<Vec<ValidatorMessage>, PoolError> {
+    pool.run(move |connection| async move {
+        match connection
+            .prepare(r#"SELECT "from", msg, received FROM validator_messages WHERE channel_id = $1 AND received > $2 ORDER BY received DESC LIMIT $3"#)
+            .await
+        {
+            Ok(select) => match connection
+                .query(&select, &[channel_id, &since, &LATEST_MESSAGES_LIMIT])
+                .await
+            {
+                Ok(rows) => {
+                    let messages = rows.iter().map(ValidatorMessage::from).collect();
+                    Ok((messages, connection))
+                }
+                Err(e) => Err((e, connection)),
+            },
+            Err(e) => Err((e, connection)),
+        }
+    })
+    .await
+    .map_err(PoolError::from)
 }
 
 fn add_message_types_params<'a>(
@@ -54,3 +89,76 @@ fn add_message_types_params<'a>(
         where_clauses.push(format!("msg->>'type' IN ({})", msg_prep.join(",")));
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::{insert_channel, tests_postgres::with_test_database};
+    use chrono::{Duration, TimeZone};
+    use primitives::util::tests::prep_db::{DUMMY_CHANNEL, IDS};
+    use primitives::validator::{Heartbeat, MessageTypes};
+
+    #[tokio::test]
+    async fn latest_messages_since_returns_the_newer_messages() {
+        with_test_database(|pool| async move {
+            let channel = DUMMY_CHANNEL.clone();
+            insert_channel(&pool, &channel)
+                .await
+                .expect("Should insert the channel");
+
+            let start = Utc.ymd(2020, 10, 1).and_hms(12, 0, 0);
+            let received = vec![
+                start,
+                start + Duration::minutes(1),
+                start + Duration::minutes(2),
+                start + Duration::minutes(3),
+            ];
+
+            for received in received.iter().copied() {
+                let message = MessageTypes::Heartbeat(Heartbeat {
+                    signature: "signature".to_string(),
+                    state_root: "state_root".to_string(),
+                    timestamp: received,
+                });
+                let channel_id = channel.id;
+
+                pool.run(move |connection| async move {
+                    match connection
+                        .execute(
+                            r#"INSERT INTO validator_messages (channel_id, "from", msg, received) VALUES ($1, $2, $3, $4)"#,
+                            &[&channel_id, &IDS["leader"], &message, &received],
+                        )
+                        .await
+                    {
+                        Ok(_) => Ok(((), connection)),
+                        Err(e) => Err((e, connection)),
+                    }
+                })
+                .await
+                .expect("Should insert the validator message");
+            }
+
+            // `since` is exclusive
+            let messages = latest_messages_since(&pool, &channel.id, received[1])
+                .await
+                .expect("Should fetch the messages");
+
+            let messages_received = messages
+                .iter()
+                .map(|message| message.received)
+                .collect::<Vec<_>>();
+            assert_eq!(vec![received[3], received[2]], messages_received);
+
+            let messages = latest_messages_since(&pool, &channel.id, received[3])
+                .await
+                .expect("Should fetch the messages");
+            assert!(messages.is_empty());
+
+            let other_channel = latest_messages_since(&pool, &ChannelId::from([1; 32]), start - Duration::days(1))
+                .await
+                .expect("Should fetch the messages");
+            assert!(other_channel.is_empty());
+        })
+        .await
+    }
+}
@@ -13,6 +13,7 @@ use chrono::Utc;
 use lazy_static::lazy_static;
 use primitives::adapter::Adapter;
 use primitives::sentry::{Event, EventAggregate};
+use primitives::targeting::validate_rules;
 use primitives::{Channel, ChannelId};
 use slog::{error, Logger};
 use std::collections::HashMap;
@@ -152,6 +153,9 @@ impl EventAggregator {
         });
 
         if let Some(new_rules) = new_targeting_rules {
+            validate_rules(&new_rules)
+                .map_err(|errors| ResponseError::BadRequest(errors.join(", ")))?;
+
             update_targeting_rules(&app.pool, &channel_id, &new_rules).await?;
         }
 
@@ -1,33 +1,41 @@
 #![deny(clippy::all)]
 #![deny(rust_2018_idioms)]
 
-use crate::db::DbPool;
+use crate::db::{DbPool, PoolError};
 use crate::event_aggregator::EventAggregator;
 use crate::routes::channel::channel_status;
 use crate::routes::event_aggregate::list_channel_event_aggregates;
 use crate::routes::validator_message::{extract_params, list_validator_messages};
 use chrono::Utc;
-use hyper::{Body, Method, Request, Response, StatusCode};
+use hyper::{
+    body::HttpBody,
+    header::{ALLOW, ETAG, IF_NONE_MATCH},
+    Body, Method, Request, Response, StatusCode,
+};
 use lazy_static::lazy_static;
 use middleware::{
     auth::{AuthRequired, Authenticate},
     channel::{ChannelLoad, GetChannelId},
+    content_type::RequireJson,
     cors::{cors, Cors},
+    rate_limit::{RateLimit, RateLimiter},
 };
 use middleware::{Chain, Middleware};
-use primitives::adapter::Adapter;
+use primitives::adapter::{Adapter, AdapterErrorKind, Error as AdapterError};
 use primitives::sentry::ValidationErrorResponse;
 use primitives::{Config, ValidatorId};
 use redis::aio::MultiplexedConnection;
-use regex::Regex;
+use router::{RouteError, Router};
 use routes::analytics::{advanced_analytics, advertiser_analytics, analytics, publisher_analytics};
 use routes::cfg::config;
 use routes::channel::{
-    channel_list, channel_validate, create_channel, create_validator_messages, insert_events,
-    last_approved,
+    channel_batch, channel_list, channel_validate, create_channel, create_validator_messages,
+    get_channel, insert_events, last_approved,
 };
 use slog::Logger;
 use std::collections::HashMap;
+use thiserror::Error;
+use tiny_keccak::Keccak;
 
 pub mod middleware;
 pub mod routes {
@@ -44,31 +52,134 @@ pub mod db;
 pub mod event_aggregator;
 pub mod event_reducer;
 pub mod payout;
+pub mod router;
+#[cfg(test)]
+pub mod test_util;
+
+/// The routes without any parameters in the path
+#[derive(Debug, Clone, Copy)]
+enum StaticRoute {
+    Config,
+    CreateChannel,
+    ChannelList,
+    ChannelBatch,
+    ChannelValidate,
+    Analytics,
+    AdvancedAnalytics,
+    AdvertiserAnalytics,
+    PublisherAnalytics,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum AnalyticsRoute {
+    Channel,
+    ForAdvertiser,
+    ForPublisher,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ChannelRoute {
+    Get,
+    InsertEvents,
+    LastApproved,
+    Status,
+    ListValidatorMessages,
+    CreateValidatorMessages,
+    EventAggregates,
+}
 
 lazy_static! {
-    static ref CHANNEL_GET_BY_ID: Regex =
-        Regex::new(r"^/channel/0x([a-zA-Z0-9]{64})/?$").expect("The regex should be valid");
-    static ref LAST_APPROVED_BY_CHANNEL_ID: Regex = Regex::new(r"^/channel/0x([a-zA-Z0-9]{64})/last-approved/?$").expect("The regex should be valid");
-    static ref CHANNEL_STATUS_BY_CHANNEL_ID: Regex = Regex::new(r"^/channel/0x([a-zA-Z0-9]{64})/status/?$").expect("The regex should be valid");
-    // Only the initial Regex to be matched.
-    static ref CHANNEL_VALIDATOR_MESSAGES: Regex = Regex::new(r"^/channel/0x([a-zA-Z0-9]{64})/validator-messages(/.*)?$").expect("The regex should be valid");
-    static ref CHANNEL_EVENTS_AGGREGATES: Regex = Regex::new(r"^/channel/0x([a-zA-Z0-9]{64})/events-aggregates/?$").expect("The regex should be valid");
-    static ref ANALYTICS_BY_CHANNEL_ID: Regex = Regex::new(r"^/analytics/0x([a-zA-Z0-9]{64})/?$").expect("The regex should be valid");
-    static ref ADVERTISER_ANALYTICS_BY_CHANNEL_ID: Regex = Regex::new(r"^/analytics/for-advertiser/0x([a-zA-Z0-9]{64})/?$").expect("The regex should be valid");
-    static ref PUBLISHER_ANALYTICS_BY_CHANNEL_ID: Regex = Regex::new(r"^/analytics/for-publisher/0x([a-zA-Z0-9]{64})/?$").expect("The regex should be valid");
-    static ref CREATE_EVENTS_BY_CHANNEL_ID: Regex = Regex::new(r"^/channel/0x([a-zA-Z0-9]{64})/events/?$").expect("The regex should be valid");
+    static ref STATIC_ROUTES: Router<StaticRoute> = Router::new()
+        .route(Method::GET, "/cfg", StaticRoute::Config)
+        .route(Method::POST, "/channel", StaticRoute::CreateChannel)
+        .route(Method::GET, "/channel/list", StaticRoute::ChannelList)
+        .route(Method::POST, "/channel/batch", StaticRoute::ChannelBatch)
+        .route(
+            Method::POST,
+            "/channel/validate",
+            StaticRoute::ChannelValidate
+        )
+        .route(Method::GET, "/analytics", StaticRoute::Analytics)
+        .route(
+            Method::GET,
+            "/analytics/advanced",
+            StaticRoute::AdvancedAnalytics
+        )
+        .route(
+            Method::GET,
+            "/analytics/for-advertiser",
+            StaticRoute::AdvertiserAnalytics
+        )
+        .route(
+            Method::GET,
+            "/analytics/for-publisher",
+            StaticRoute::PublisherAnalytics
+        );
+    static ref ANALYTICS_ROUTES: Router<AnalyticsRoute> = Router::new()
+        .route(Method::GET, "/analytics/0x:id", AnalyticsRoute::Channel)
+        .route(
+            Method::GET,
+            "/analytics/for-advertiser/0x:id",
+            AnalyticsRoute::ForAdvertiser
+        )
+        .route(
+            Method::GET,
+            "/analytics/for-publisher/0x:id",
+            AnalyticsRoute::ForPublisher
+        );
+    static ref CHANNEL_ROUTES: Router<ChannelRoute> = Router::new()
+        .route(Method::GET, "/channel/0x:id", ChannelRoute::Get)
+        .route(
+            Method::POST,
+            "/channel/0x:id/events",
+            ChannelRoute::InsertEvents
+        )
+        .route(
+            Method::GET,
+            "/channel/0x:id/last-approved",
+            ChannelRoute::LastApproved
+        )
+        .route(Method::GET, "/channel/0x:id/status", ChannelRoute::Status)
+        .route(
+            Method::GET,
+            "/channel/0x:id/validator-messages/*rest",
+            ChannelRoute::ListValidatorMessages
+        )
+        .route(
+            Method::POST,
+            "/channel/0x:id/validator-messages",
+            ChannelRoute::CreateValidatorMessages
+        )
+        .route(
+            Method::GET,
+            "/channel/0x:id/events-aggregates",
+            ChannelRoute::EventAggregates
+        );
 }
 
-#[derive(Debug)]
-pub struct RouteParams(Vec<String>);
+/// The parameters extracted from the path of the request by the [`Router`](router::Router),
+/// in the order they appear in the route pattern
+#[derive(Debug, Default)]
+pub struct RouteParams(Vec<(String, String)>);
 
 impl RouteParams {
     pub fn get(&self, index: usize) -> Option<String> {
-        self.0.get(index).map(ToOwned::to_owned)
+        self.0.get(index).map(|(_, value)| value.to_owned())
     }
 
     pub fn index(&self, i: usize) -> String {
-        self.0[i].clone()
+        self.0[i].1.clone()
+    }
+
+    pub fn named(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub(crate) fn push(&mut self, name: &str, value: &str) {
+        self.0.push((name.to_string(), value.to_string()));
     }
 }
 
@@ -80,9 +191,14 @@ pub struct Application<A: Adapter> {
     pub pool: DbPool,
     pub config: Config,
     pub event_aggregator: EventAggregator,
+    pub rate_limiter: RateLimiter,
 }
 
 impl<A: Adapter + 'static> Application<A> {
+    pub fn builder() -> ApplicationBuilder<A> {
+        ApplicationBuilder::default()
+    }
+
     pub fn new(
         adapter: A,
         config: Config,
@@ -97,6 +213,7 @@ impl<A: Adapter + 'static> Application<A> {
             redis,
             pool,
             event_aggregator: Default::default(),
+            rate_limiter: Default::default(),
         }
     }
 
@@ -108,52 +225,39 @@ impl<A: Adapter + 'static> Application<A> {
             None => Default::default(),
         };
 
-        let req = match Authenticate.call(req, &self).await {
+        let req = match Chain::new()
+            .chain(Authenticate)
+            .chain(RequireJson)
+            .apply(req, &self)
+            .await
+        {
             Ok(req) => req,
             Err(error) => return map_response_error(error),
         };
 
-        let mut response = match (req.uri().path(), req.method()) {
-            ("/cfg", &Method::GET) => config(req, &self).await,
-            ("/channel", &Method::POST) => create_channel(req, &self).await,
-            ("/channel/list", &Method::GET) => channel_list(req, &self).await,
-            ("/channel/validate", &Method::POST) => channel_validate(req, &self).await,
-
-            ("/analytics", &Method::GET) => analytics(req, &self).await,
-            ("/analytics/advanced", &Method::GET) => {
-                let req = match AuthRequired.call(req, &self).await {
-                    Ok(req) => req,
-                    Err(error) => {
-                        return map_response_error(error);
-                    }
-                };
-
-                advanced_analytics(req, &self).await
-            }
-            ("/analytics/for-advertiser", &Method::GET) => {
-                let req = match AuthRequired.call(req, &self).await {
-                    Ok(req) => req,
-                    Err(error) => {
-                        return map_response_error(error);
-                    }
-                };
-                advertiser_analytics(req, &self).await
+        // the analytics are expensive to query, so limit the requests of each client
+        let req = if req.uri().path().starts_with("/analytics") {
+            match RateLimit.call(req, &self).await {
+                Ok(req) => req,
+                Err(error) => return map_response_error(error),
             }
-            ("/analytics/for-publisher", &Method::GET) => {
-                let req = match AuthRequired.call(req, &self).await {
-                    Ok(req) => req,
-                    Err(error) => {
-                        return map_response_error(error);
-                    }
-                };
-
-                publisher_analytics(req, &self).await
+        } else {
+            req
+        };
+
+        let mut response = match STATIC_ROUTES.find(req.method(), req.uri().path()) {
+            Ok((route, _)) => static_router(*route, req, &self).await,
+            // the path matches a static route, so it can't be a path with parameters
+            Err(RouteError::MethodNotAllowed(allowed)) => {
+                Err(ResponseError::MethodNotAllowed(allowed))
             }
-            (route, _) if route.starts_with("/analytics") => analytics_router(req, &self).await,
-            // This is important becuase it prevents us from doing
-            // expensive regex matching for routes without /channel
-            (path, _) if path.starts_with("/channel") => channels_router(req, &self).await,
-            _ => Err(ResponseError::NotFound),
+            Err(RouteError::NotFound) => match req.uri().path() {
+                route if route.starts_with("/analytics") => analytics_router(req, &self).await,
+                // This is important becuase it prevents us from doing
+                // path pattern matching for routes without /channel
+                path if path.starts_with("/channel") => channels_router(req, &self).await,
+                _ => Err(ResponseError::NotFound),
+            },
         }
         .unwrap_or_else(map_response_error);
 
@@ -163,61 +267,72 @@ impl<A: Adapter + 'static> Application<A> {
     }
 }
 
+async fn static_router<A: Adapter + 'static>(
+    route: StaticRoute,
+    req: Request<Body>,
+    app: &Application<A>,
+) -> Result<Response<Body>, ResponseError> {
+    match route {
+        StaticRoute::Config => config(req, app).await,
+        StaticRoute::CreateChannel => create_channel(req, app).await,
+        StaticRoute::ChannelList => channel_list(req, app).await,
+        StaticRoute::ChannelBatch => channel_batch(req, app).await,
+        StaticRoute::ChannelValidate => channel_validate(req, app).await,
+        StaticRoute::Analytics => analytics(req, app).await,
+        StaticRoute::AdvancedAnalytics => {
+            let req = AuthRequired.call(req, app).await?;
+            advanced_analytics(req, app).await
+        }
+        StaticRoute::AdvertiserAnalytics => {
+            let req = AuthRequired.call(req, app).await?;
+            advertiser_analytics(req, app).await
+        }
+        StaticRoute::PublisherAnalytics => {
+            let req = AuthRequired.call(req, app).await?;
+            publisher_analytics(req, app).await
+        }
+    }
+}
+
 async fn analytics_router<A: Adapter + 'static>(
     mut req: Request<Body>,
     app: &Application<A>,
 ) -> Result<Response<Body>, ResponseError> {
-    let (route, method) = (req.uri().path(), req.method());
-
-    match *method {
-        Method::GET => {
-            if let Some(caps) = ANALYTICS_BY_CHANNEL_ID.captures(route) {
-                let param = RouteParams(vec![caps
-                    .get(1)
-                    .map_or("".to_string(), |m| m.as_str().to_string())]);
-                req.extensions_mut().insert(param);
-
-                // apply middlewares
-                req = Chain::new()
-                    .chain(ChannelLoad)
-                    .chain(GetChannelId)
-                    .apply(req, app)
-                    .await?;
-
-                analytics(req, app).await
-            } else if let Some(caps) = ADVERTISER_ANALYTICS_BY_CHANNEL_ID.captures(route) {
-                let param = RouteParams(vec![caps
-                    .get(1)
-                    .map_or("".to_string(), |m| m.as_str().to_string())]);
-                req.extensions_mut().insert(param);
-
-                // apply middlewares
-                req = Chain::new()
-                    .chain(AuthRequired)
-                    .chain(GetChannelId)
-                    .apply(req, app)
-                    .await?;
-
-                advertiser_analytics(req, app).await
-            } else if let Some(caps) = PUBLISHER_ANALYTICS_BY_CHANNEL_ID.captures(route) {
-                let param = RouteParams(vec![caps
-                    .get(1)
-                    .map_or("".to_string(), |m| m.as_str().to_string())]);
-                req.extensions_mut().insert(param);
-
-                // apply middlewares
-                req = Chain::new()
-                    .chain(AuthRequired)
-                    .chain(GetChannelId)
-                    .apply(req, app)
-                    .await?;
-
-                publisher_analytics(req, app).await
-            } else {
-                Err(ResponseError::NotFound)
-            }
+    let (route, params) = ANALYTICS_ROUTES.find(req.method(), req.uri().path())?;
+    let route = *route;
+    req.extensions_mut().insert(params);
+
+    match route {
+        AnalyticsRoute::Channel => {
+            // apply middlewares
+            req = Chain::new()
+                .chain(ChannelLoad)
+                .chain(GetChannelId)
+                .apply(req, app)
+                .await?;
+
+            analytics(req, app).await
+        }
+        AnalyticsRoute::ForAdvertiser => {
+            // apply middlewares
+            req = Chain::new()
+                .chain(AuthRequired)
+                .chain(GetChannelId)
+                .apply(req, app)
+                .await?;
+
+            advertiser_analytics(req, app).await
+        }
+        AnalyticsRoute::ForPublisher => {
+            // apply middlewares
+            req = Chain::new()
+                .chain(AuthRequired)
+                .chain(GetChannelId)
+                .apply(req, app)
+                .await?;
+
+            publisher_analytics(req, app).await
         }
-        _ => Err(ResponseError::NotFound),
     }
 }
 
@@ -225,97 +340,177 @@ async fn channels_router<A: Adapter + 'static>(
     mut req: Request<Body>,
     app: &Application<A>,
 ) -> Result<Response<Body>, ResponseError> {
-    let (path, method) = (req.uri().path().to_owned(), req.method());
-
-    // regex matching for routes with params
-    if let (Some(caps), &Method::POST) = (CREATE_EVENTS_BY_CHANNEL_ID.captures(&path), method) {
-        let param = RouteParams(vec![caps
-            .get(1)
-            .map_or("".to_string(), |m| m.as_str().to_string())]);
-
-        req.extensions_mut().insert(param);
-
-        insert_events(req, app).await
-    } else if let (Some(caps), &Method::GET) = (LAST_APPROVED_BY_CHANNEL_ID.captures(&path), method)
-    {
-        let param = RouteParams(vec![caps
-            .get(1)
-            .map_or("".to_string(), |m| m.as_str().to_string())]);
-        req.extensions_mut().insert(param);
-
-        last_approved(req, app).await
-    } else if let (Some(caps), &Method::GET) =
-        (CHANNEL_STATUS_BY_CHANNEL_ID.captures(&path), method)
-    {
-        let param = RouteParams(vec![caps
-            .get(1)
-            .map_or("".to_string(), |m| m.as_str().to_string())]);
-        req.extensions_mut().insert(param);
-
-        req = ChannelLoad.call(req, app).await?;
-        channel_status(req, app).await
-    } else if let (Some(caps), &Method::GET) = (CHANNEL_VALIDATOR_MESSAGES.captures(&path), method)
-    {
-        let param = RouteParams(vec![caps
-            .get(1)
-            .map_or("".to_string(), |m| m.as_str().to_string())]);
-
-        req.extensions_mut().insert(param);
-
-        req = ChannelLoad.call(req, app).await?;
-
-        // @TODO: Move this to a middleware?!
-        let extract_params = match extract_params(caps.get(2).map_or("", |m| m.as_str())) {
-            Ok(params) => params,
-            Err(error) => {
-                return Err(error.into());
-            }
-        };
+    let (route, params) = CHANNEL_ROUTES.find(req.method(), req.uri().path())?;
+    let route = *route;
+    // the validator and message types of the validator messages
+    let rest = params.named("rest").unwrap_or_default().to_string();
+    req.extensions_mut().insert(params);
+
+    match route {
+        ChannelRoute::Get => {
+            req = ChannelLoad.call(req, app).await?;
+            get_channel(req, app).await
+        }
+        ChannelRoute::InsertEvents => {
+            req = RateLimit.call(req, app).await?;
+            insert_events(req, app).await
+        }
+        ChannelRoute::LastApproved => last_approved(req, app).await,
+        ChannelRoute::Status => {
+            req = ChannelLoad.call(req, app).await?;
+            channel_status(req, app).await
+        }
+        ChannelRoute::ListValidatorMessages => {
+            req = ChannelLoad.call(req, app).await?;
+
+            // @TODO: Move this to a middleware?!
+            let extract_params = match extract_params(&rest) {
+                Ok(params) => params,
+                Err(error) => {
+                    return Err(error.into());
+                }
+            };
+
+            list_validator_messages(req, &app, &extract_params.0, &extract_params.1).await
+        }
+        ChannelRoute::CreateValidatorMessages => {
+            let req = Chain::new()
+                .chain(AuthRequired)
+                .chain(ChannelLoad)
+                .apply(req, app)
+                .await?;
+
+            create_validator_messages(req, &app).await
+        }
+        ChannelRoute::EventAggregates => {
+            req = AuthRequired.call(req, app).await?;
+            req = ChannelLoad.call(req, app).await?;
 
-        list_validator_messages(req, &app, &extract_params.0, &extract_params.1).await
-    } else if let (Some(caps), &Method::POST) = (CHANNEL_VALIDATOR_MESSAGES.captures(&path), method)
-    {
-        let param = RouteParams(vec![caps
-            .get(1)
-            .map_or("".to_string(), |m| m.as_str().to_string())]);
+            list_channel_event_aggregates(req, app).await
+        }
+    }
+}
 
-        req.extensions_mut().insert(param);
+/// Assembles an [`Application`] with a custom `Adapter`, `Config`, Redis connection and `DbPool`,
+/// e.g. when embedding the Sentry.
+///
+/// The `logger` is optional and discards all the records when not set.
+pub struct ApplicationBuilder<A: Adapter> {
+    adapter: Option<A>,
+    config: Option<Config>,
+    logger: Option<Logger>,
+    redis: Option<MultiplexedConnection>,
+    pool: Option<DbPool>,
+}
 
-        let req = Chain::new()
-            .chain(AuthRequired)
-            .chain(ChannelLoad)
-            .apply(req, app)
-            .await?;
+impl<A: Adapter> Default for ApplicationBuilder<A> {
+    fn default() -> Self {
+        Self {
+            adapter: None,
+            config: None,
+            logger: None,
+            redis: None,
+            pool: None,
+        }
+    }
+}
 
-        create_validator_messages(req, &app).await
-    } else if let (Some(caps), &Method::GET) = (CHANNEL_EVENTS_AGGREGATES.captures(&path), method) {
-        req = AuthRequired.call(req, app).await?;
+impl<A: Adapter + 'static> ApplicationBuilder<A> {
+    pub fn adapter(mut self, adapter: A) -> Self {
+        self.adapter = Some(adapter);
+        self
+    }
 
-        let param = RouteParams(vec![
-            caps.get(1)
-                .map_or("".to_string(), |m| m.as_str().to_string()),
-            caps.get(2)
-                .map_or("".to_string(), |m| m.as_str().trim_matches('/').to_string()),
-        ]);
-        req.extensions_mut().insert(param);
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
 
-        req = ChannelLoad.call(req, app).await?;
+    pub fn logger(mut self, logger: Logger) -> Self {
+        self.logger = Some(logger);
+        self
+    }
 
-        list_channel_event_aggregates(req, app).await
-    } else {
-        Err(ResponseError::NotFound)
+    pub fn redis(mut self, redis: MultiplexedConnection) -> Self {
+        self.redis = Some(redis);
+        self
+    }
+
+    pub fn pool(mut self, pool: DbPool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    pub fn build(self) -> Result<Application<A>, BuildError> {
+        let adapter = self.adapter.ok_or(BuildError::Missing("adapter"))?;
+        let config = self.config.ok_or(BuildError::Missing("config"))?;
+        let redis = self.redis.ok_or(BuildError::Missing("redis"))?;
+        let pool = self.pool.ok_or(BuildError::Missing("pool"))?;
+        let logger = self
+            .logger
+            .unwrap_or_else(|| Logger::root(slog::Discard, slog::o!()));
+
+        if config.max_body_size == 0 {
+            return Err(BuildError::InvalidConfig(
+                "max_body_size should be greater than 0".to_string(),
+            ));
+        }
+
+        Ok(Application::new(adapter, config, logger, redis, pool))
     }
 }
 
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BuildError {
+    #[error("the {0} of the Application is required")]
+    Missing(&'static str),
+    #[error("invalid config: {0}")]
+    InvalidConfig(String),
+}
+
 #[derive(Debug)]
 pub enum ResponseError {
     NotFound,
+    /// The path exists, but only for the contained methods
+    MethodNotAllowed(Vec<Method>),
     BadRequest(String),
     FailedValidation(String),
     Unauthorized,
     Forbidden(String),
     Conflict(String),
     TooManyRequests(String),
+    PayloadTooLarge(String),
+    UnsupportedMediaType(String),
+    InternalServerError(String),
+    ServiceUnavailable(String),
+}
+
+impl ResponseError {
+    /// Maps the errors of the `Adapter` to the corresponding response:
+    /// - `InvalidChannel`, `Domain` & the client errors of the `Adapter` - `400 Bad Request`
+    /// - `Authentication` - `401 Unauthorized`
+    /// - `Authorization` - `403 Forbidden`
+    /// - all other errors - `500 Internal Server Error`
+    ///
+    /// It's not a `From` implementation, since it would conflict with the one for all `std::error::Error`s,
+    /// use it with `map_err(ResponseError::from_adapter)?` instead.
+    pub fn from_adapter<AE: AdapterErrorKind>(error: AdapterError<AE>) -> Self {
+        match error {
+            AdapterError::InvalidChannel(_) | AdapterError::Domain(_) => {
+                ResponseError::BadRequest(error.to_string())
+            }
+            AdapterError::Authentication(_) => ResponseError::Unauthorized,
+            AdapterError::Authorization(message) => ResponseError::Forbidden(message),
+            AdapterError::Adapter(ref error) if error.is_client_error() => {
+                ResponseError::BadRequest(error.to_string())
+            }
+            AdapterError::Adapter(_) | AdapterError::LockedWallet => {
+                ResponseError::InternalServerError(
+                    "internal server error, please try again later".to_string(),
+                )
+            }
+        }
+    }
 }
 
 impl<T> From<T> for ResponseError
@@ -323,12 +518,26 @@ where
     T: std::error::Error + 'static,
 {
     fn from(error: T) -> Self {
+        // an exhausted pool is a temporary condition of the Sentry, not an error of the request
+        if let Some(PoolError::Exhausted) = (&error as &dyn std::any::Any).downcast_ref() {
+            return ResponseError::ServiceUnavailable(error.to_string());
+        }
+
         // @TODO use a error proper logger?
         println!("{:#?}", error);
         ResponseError::BadRequest("Bad Request: try again later".into())
     }
 }
 
+impl From<RouteError> for ResponseError {
+    fn from(error: RouteError) -> Self {
+        match error {
+            RouteError::NotFound => ResponseError::NotFound,
+            RouteError::MethodNotAllowed(allowed) => ResponseError::MethodNotAllowed(allowed),
+        }
+    }
+}
+
 impl Into<Response<Body>> for ResponseError {
     fn into(self) -> Response<Body> {
         map_response_error(self)
@@ -338,6 +547,7 @@ impl Into<Response<Body>> for ResponseError {
 pub fn map_response_error(error: ResponseError) -> Response<Body> {
     match error {
         ResponseError::NotFound => not_found(),
+        ResponseError::MethodNotAllowed(allowed) => method_not_allowed(&allowed),
         ResponseError::BadRequest(e) => bad_response(e, StatusCode::BAD_REQUEST),
         ResponseError::Unauthorized => bad_response(
             "invalid authorization".to_string(),
@@ -346,7 +556,13 @@ pub fn map_response_error(error: ResponseError) -> Response<Body> {
         ResponseError::Forbidden(e) => bad_response(e, StatusCode::FORBIDDEN),
         ResponseError::Conflict(e) => bad_response(e, StatusCode::CONFLICT),
         ResponseError::TooManyRequests(e) => bad_response(e, StatusCode::TOO_MANY_REQUESTS),
+        ResponseError::PayloadTooLarge(e) => bad_response(e, StatusCode::PAYLOAD_TOO_LARGE),
+        ResponseError::UnsupportedMediaType(e) => {
+            bad_response(e, StatusCode::UNSUPPORTED_MEDIA_TYPE)
+        }
         ResponseError::FailedValidation(e) => bad_validation_response(e),
+        ResponseError::InternalServerError(e) => bad_response(e, StatusCode::INTERNAL_SERVER_ERROR),
+        ResponseError::ServiceUnavailable(e) => bad_response(e, StatusCode::SERVICE_UNAVAILABLE),
     }
 }
 
@@ -357,6 +573,25 @@ pub fn not_found() -> Response<Body> {
     response
 }
 
+pub fn method_not_allowed(allowed: &[Method]) -> Response<Body> {
+    let mut response = bad_response(
+        "method not allowed".to_string(),
+        StatusCode::METHOD_NOT_ALLOWED,
+    );
+
+    let allow = allowed
+        .iter()
+        .map(Method::as_str)
+        .collect::<Vec<_>>()
+        .join(", ");
+    response.headers_mut().insert(
+        ALLOW,
+        allow.parse().expect("Methods are valid header values"),
+    );
+
+    response
+}
+
 pub fn bad_response(response_body: String, status_code: StatusCode) -> Response<Body> {
     let mut error_response = HashMap::new();
     error_response.insert("message", response_body);
@@ -406,6 +641,78 @@ pub fn success_response(response_body: String) -> Response<Body> {
     response
 }
 
+/// Same as [`success_response`], but with an `ETag` header (the keccak256 hash of the `response_body`).
+/// When the `If-None-Match` header of the request matches the `ETag`,
+/// a `304 Not Modified` response without a body is returned instead.
+pub fn etag_response(req: &Request<Body>, response_body: String) -> Response<Body> {
+    let etag = etag(response_body.as_bytes());
+
+    let not_modified = req
+        .headers()
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag);
+
+    let mut response = if not_modified {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = StatusCode::NOT_MODIFIED;
+        response
+    } else {
+        success_response(response_body)
+    };
+
+    response.headers_mut().insert(
+        ETAG,
+        etag.parse()
+            .expect("The ETag should be a valid header value"),
+    );
+
+    response
+}
+
+fn etag(body: &[u8]) -> String {
+    let mut keccak = Keccak::new_keccak256();
+    keccak.update(body);
+
+    let mut hash = [0u8; 32];
+    keccak.finalize(&mut hash);
+
+    format!("\"{}\"", hex::encode(hash))
+}
+
+/// Reads the whole request `body`, but fails with [`ResponseError::PayloadTooLarge`]
+/// as soon as it exceeds `max_size` bytes, without reading the rest of it.
+pub async fn read_body(mut body: Body, max_size: u32) -> Result<Vec<u8>, ResponseError> {
+    let max_size = max_size as usize;
+    let payload_too_large = || {
+        ResponseError::PayloadTooLarge(format!(
+            "request body is too large, the limit is {} bytes",
+            max_size
+        ))
+    };
+
+    // the `Content-Length` of the request, if there is one
+    if body.size_hint().lower() > max_size as u64 {
+        return Err(payload_too_large());
+    }
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+
+        if bytes.len() + chunk.len() > max_size {
+            return Err(payload_too_large());
+        }
+
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(bytes)
+}
+
 pub fn epoch() -> f64 {
     Utc::now().timestamp() as f64 / 2_628_000_000.0
 }
@@ -424,3 +731,250 @@ pub struct Auth {
     pub era: i64,
     pub uid: ValidatorId,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::redis_connection;
+    use crate::db::tests_postgres::with_test_database;
+    use crate::test_util::setup_dummy_app;
+    use adapter::DummyAdapter;
+    use futures::stream;
+    use primitives::adapter::DummyAdapterOptions;
+    use primitives::config::{configuration, AdapterConfig};
+    use primitives::util::tests::prep_db::{AUTH, DUMMY_CHANNEL, IDS};
+
+    #[tokio::test]
+    async fn read_body_respects_the_max_size() {
+        let body = read_body(Body::from("0123456789"), 10)
+            .await
+            .expect("Should read a body of the max size");
+        assert_eq!(b"0123456789".to_vec(), body);
+
+        let error = read_body(Body::from("0123456789"), 9)
+            .await
+            .expect_err("Should reject a body with a known size");
+        assert!(matches!(error, ResponseError::PayloadTooLarge(_)));
+
+        // a body without a `Content-Length`
+        let chunks = vec!["01234", "56789"]
+            .into_iter()
+            .map(Ok::<_, std::io::Error>);
+        let error = read_body(Body::wrap_stream(stream::iter(chunks)), 9)
+            .await
+            .expect_err("Should reject a chunked body");
+        assert!(matches!(error, ResponseError::PayloadTooLarge(_)));
+    }
+
+    #[tokio::test]
+    async fn wrong_method_for_a_known_path_is_not_allowed() {
+        with_test_database(|pool| async move {
+            let app = setup_dummy_app(pool).await;
+
+            let request = Request::get(format!("/channel/{}/events", DUMMY_CHANNEL.id))
+                .body(Body::empty())
+                .expect("Should build Request");
+            let response = app.handle_routing(request).await;
+
+            assert_eq!(StatusCode::METHOD_NOT_ALLOWED, response.status());
+            assert_eq!(
+                "POST",
+                response.headers()[ALLOW]
+                    .to_str()
+                    .expect("Should be a valid string")
+            );
+
+            let request = Request::get(format!("/channel/{}/unknown", DUMMY_CHANNEL.id))
+                .body(Body::empty())
+                .expect("Should build Request");
+            let response = app.handle_routing(request).await;
+
+            assert_eq!(StatusCode::NOT_FOUND, response.status());
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn wrong_method_for_a_static_path_is_not_allowed() {
+        use hyper::header::CONTENT_TYPE;
+
+        with_test_database(|pool| async move {
+            let app = setup_dummy_app(pool).await;
+
+            let wrong_methods = vec![
+                (Method::GET, "/channel", "POST"),
+                (Method::POST, "/channel/list", "GET"),
+                (Method::POST, "/cfg", "GET"),
+                (Method::POST, "/analytics", "GET"),
+            ];
+
+            for (method, path, allowed) in wrong_methods {
+                let request = Request::builder()
+                    .method(method)
+                    .uri(path)
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(Body::empty())
+                    .expect("Should build Request");
+                let response = app.handle_routing(request).await;
+
+                assert_eq!(
+                    StatusCode::METHOD_NOT_ALLOWED,
+                    response.status(),
+                    "{}",
+                    path
+                );
+                assert_eq!(
+                    allowed,
+                    response.headers()[ALLOW]
+                        .to_str()
+                        .expect("Should be a valid string")
+                );
+            }
+        })
+        .await
+    }
+
+    #[test]
+    fn etag_response_is_not_modified_for_a_matching_etag() {
+        let body = r#"{"channels":[]}"#.to_string();
+
+        let response = etag_response(&Request::new(Body::empty()), body.clone());
+        assert_eq!(StatusCode::OK, response.status());
+        let etag = response.headers()[ETAG].clone();
+
+        let request = Request::get("/channel/list")
+            .header(IF_NONE_MATCH, etag.clone())
+            .body(Body::empty())
+            .expect("Should build Request");
+        let response = etag_response(&request, body.clone());
+        assert_eq!(StatusCode::NOT_MODIFIED, response.status());
+        assert_eq!(etag, response.headers()[ETAG]);
+
+        let request = Request::get("/channel/list")
+            .header(IF_NONE_MATCH, "\"outdated\"")
+            .body(Body::empty())
+            .expect("Should build Request");
+        let response = etag_response(&request, body);
+        assert_eq!(StatusCode::OK, response.status());
+    }
+
+    #[test]
+    fn exhausted_pool_is_mapped_to_service_unavailable() {
+        let response = map_response_error(ResponseError::from(PoolError::Exhausted));
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, response.status());
+    }
+
+    #[test]
+    fn adapter_errors_are_mapped_to_responses() {
+        use adapter::dummy::Error as DummyError;
+        use primitives::{channel::ChannelError, DomainError};
+
+        let status = |error: AdapterError<DummyError>| {
+            map_response_error(ResponseError::from_adapter(error)).status()
+        };
+
+        assert_eq!(
+            StatusCode::BAD_REQUEST,
+            status(AdapterError::InvalidChannel(ChannelError::UnlistedCreator))
+        );
+        assert_eq!(
+            StatusCode::BAD_REQUEST,
+            status(AdapterError::Domain(DomainError::InvalidArgument(
+                "invalid".to_string()
+            )))
+        );
+        assert_eq!(
+            StatusCode::UNAUTHORIZED,
+            status(AdapterError::Authentication("no session".to_string()))
+        );
+        assert_eq!(
+            StatusCode::FORBIDDEN,
+            status(AdapterError::Authorization("not allowed".to_string()))
+        );
+        assert_eq!(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            status(AdapterError::Adapter(Box::new(DummyError {})))
+        );
+        assert_eq!(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            status(AdapterError::LockedWallet)
+        );
+    }
+
+    #[test]
+    fn application_builder_requires_all_the_parts() {
+        let config = configuration("development", None).expect("Dev config should be available");
+        let adapter = DummyAdapter::init(
+            DummyAdapterOptions {
+                dummy_identity: IDS["leader"],
+                dummy_auth: IDS.clone(),
+                dummy_auth_tokens: AUTH.clone(),
+            },
+            &config,
+        );
+
+        let result = Application::<DummyAdapter>::builder()
+            .config(config)
+            .build();
+        assert_eq!(Some(BuildError::Missing("adapter")), result.err());
+
+        let result = Application::builder().adapter(adapter).build();
+        assert_eq!(Some(BuildError::Missing("config")), result.err());
+    }
+
+    #[tokio::test]
+    async fn application_built_with_the_builder_handles_requests() {
+        with_test_database(|pool| async move {
+            let mut config =
+                configuration("development", None).expect("Dev config should be available");
+            config.channels_find_limit = 42;
+            config.sentry_read_replicas = vec!["http://replica.localhost:8005".to_string()];
+            config.adapter = Some(AdapterConfig::Dummy {
+                dummy_identity: IDS["follower"],
+            });
+            let adapter = DummyAdapter::init(
+                DummyAdapterOptions {
+                    dummy_identity: IDS["follower"],
+                    dummy_auth: IDS.clone(),
+                    dummy_auth_tokens: AUTH.clone(),
+                },
+                &config,
+            );
+            let redis = redis_connection().await.expect("Couldn't connect to Redis");
+
+            let app = Application::builder()
+                .adapter(adapter.clone())
+                .config(config.clone())
+                .redis(redis.clone())
+                .pool(pool.clone())
+                .build()
+                .expect("Should build the Application");
+
+            let request = Request::get("/cfg")
+                .body(Body::empty())
+                .expect("Should build Request");
+            let response = app.handle_routing(request).await;
+            assert_eq!(StatusCode::OK, response.status());
+
+            let body = hyper::body::to_bytes(response.into_body())
+                .await
+                .expect("Should read the body");
+            let cfg: serde_json::Value =
+                serde_json::from_slice(&body).expect("Should deserialize the config");
+            assert_eq!(42, cfg["CHANNELS_FIND_LIMIT"]);
+            // the validator worker's settings are not exposed
+            assert!(cfg.get("SENTRY_READ_REPLICAS").is_none());
+            assert!(cfg.get("ADAPTER").is_none());
+
+            config.max_body_size = 0;
+            let result = Application::builder()
+                .adapter(adapter)
+                .config(config)
+                .redis(redis)
+                .pool(pool)
+                .build();
+            assert!(matches!(result, Err(BuildError::InvalidConfig(_))));
+        })
+        .await
+    }
+}
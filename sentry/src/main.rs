@@ -4,13 +4,14 @@
 use clap::{crate_version, App, Arg};
 
 use adapter::{AdapterTypes, DummyAdapter, EthereumAdapter};
+use hyper::server::conn::AddrStream;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{Error, Server};
 use primitives::adapter::{Adapter, DummyAdapterOptions, KeystoreOptions};
 use primitives::config::configuration;
 use primitives::util::tests::prep_db::{AUTH, IDS};
 use primitives::ValidatorId;
-use sentry::db::{postgres_connection, redis_connection, setup_migrations};
+use sentry::db::{postgres_connection, redis_connection, run_migrations};
 use sentry::Application;
 use slog::{error, info, Logger};
 use std::{convert::TryFrom, net::{IpAddr, Ipv4Addr, SocketAddr}};
@@ -66,6 +67,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let config_file = cli.value_of("config");
     let config = configuration(&environment, config_file).unwrap();
+    let logger = logger();
 
     let adapter = match cli.value_of("adapter").unwrap() {
         "ethereum" => {
@@ -78,7 +80,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 keystore_file: keystore_file.to_string(),
                 keystore_pwd,
             };
-            let ethereum_adapter = EthereumAdapter::init(options, &config)
+            let ethereum_adapter = EthereumAdapter::init(options, &config, logger.clone())
                 .expect("Should initialize ethereum adapter");
 
             AdapterTypes::EthereumAdapter(Box::new(ethereum_adapter))
@@ -101,12 +103,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         _ => panic!("You can only use `ethereum` & `dummy` adapters!"),
     };
 
-    let logger = logger();
     let redis = redis_connection().await?;
-    info!(&logger, "Checking connection and applying migrations...");
-    // Check connection and setup migrations before setting up Postgres
-    setup_migrations(&environment).await;
     let postgres = postgres_connection().await?;
+    if config.run_migrations {
+        info!(&logger, "Applying the database migrations...");
+        run_migrations(&postgres).await?;
+    }
 
     match adapter {
         AdapterTypes::EthereumAdapter(adapter) => {
@@ -133,11 +135,14 @@ async fn run<A: Adapter + 'static>(app: Application<A>, socket_addr: SocketAddr)
     let logger = app.logger.clone();
     info!(&logger, "Listening on socket address: {}!", socket_addr);
 
-    let make_service = make_service_fn(|_| {
+    let make_service = make_service_fn(|conn: &AddrStream| {
         let server = app.clone();
+        let remote_addr = conn.remote_addr();
         async move {
-            Ok::<_, Error>(service_fn(move |req| {
+            Ok::<_, Error>(service_fn(move |mut req| {
                 let server = server.clone();
+                // used for identifying the client, see `RateLimit`
+                req.extensions_mut().insert(remote_addr);
                 async move { Ok::<_, Error>(server.handle_routing(req).await) }
             }))
         }
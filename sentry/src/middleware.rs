@@ -8,7 +8,9 @@ use async_trait::async_trait;
 
 pub mod auth;
 pub mod channel;
+pub mod content_type;
 pub mod cors;
+pub mod rate_limit;
 
 #[async_trait]
 pub trait Middleware<A: Adapter + 'static>: Send + Sync + Debug {
@@ -48,3 +50,82 @@ impl<A: Adapter + 'static> Chain<A> {
         Ok(request)
     }
 }
+
+#[async_trait]
+impl<A: Adapter + 'static> Middleware<A> for Chain<A> {
+    /// Allows nesting a `Chain` inside another one
+    async fn call<'a>(
+        &self,
+        request: Request<Body>,
+        application: &'a Application<A>,
+    ) -> Result<Request<Body>, ResponseError> {
+        self.apply(request, application).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::tests_postgres::with_test_database;
+    use crate::test_util::setup_dummy_app;
+    use adapter::DummyAdapter;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug)]
+    struct Record {
+        name: &'static str,
+        calls: Arc<Mutex<Vec<&'static str>>>,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl Middleware<DummyAdapter> for Record {
+        async fn call<'a>(
+            &self,
+            request: Request<Body>,
+            _: &'a Application<DummyAdapter>,
+        ) -> Result<Request<Body>, ResponseError> {
+            self.calls.lock().expect("Should lock").push(self.name);
+
+            if self.fail {
+                Err(ResponseError::Forbidden(self.name.to_string()))
+            } else {
+                Ok(request)
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn chain_applies_the_middlewares_in_order_and_short_circuits() {
+        with_test_database(|pool| async move {
+            let app = setup_dummy_app(pool).await;
+            let calls = Arc::new(Mutex::new(vec![]));
+            let record = |name, fail| Record {
+                name,
+                calls: calls.clone(),
+                fail,
+            };
+
+            let chain = Chain::new()
+                .chain(record("auth", false))
+                .chain(record("load", false));
+            chain
+                .apply(Request::new(Body::empty()), &app)
+                .await
+                .expect("Should apply all the middlewares");
+            assert_eq!(vec!["auth", "load"], *calls.lock().expect("Should lock"));
+
+            calls.lock().expect("Should lock").clear();
+            let chain = Chain::new()
+                .chain(record("auth", true))
+                .chain(Chain::new().chain(record("load", false)));
+            let error = chain
+                .apply(Request::new(Body::empty()), &app)
+                .await
+                .expect_err("Should stop at the first error");
+            assert!(matches!(error, ResponseError::Forbidden(name) if name == "auth"));
+            assert_eq!(vec!["auth"], *calls.lock().expect("Should lock"));
+        })
+        .await
+    }
+}
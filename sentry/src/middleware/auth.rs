@@ -116,7 +116,7 @@ async fn for_request(
     Ok(req)
 }
 
-fn get_request_ip(req: &Request<Body>) -> Option<String> {
+pub(crate) fn get_request_ip(req: &Request<Body>) -> Option<String> {
     req.headers()
         .get("true-client-ip")
         .or_else(|| req.headers().get("x-forwarded-for"))
@@ -0,0 +1,90 @@
+use async_trait::async_trait;
+use hyper::header::CONTENT_TYPE;
+use hyper::{Body, Method, Request};
+
+use primitives::adapter::Adapter;
+
+use crate::{middleware::Middleware, Application, ResponseError};
+
+/// Rejects `POST` & `PUT` requests which don't have a `Content-Type: application/json` header,
+/// since their bodies are always deserialized as JSON.
+#[derive(Debug)]
+pub struct RequireJson;
+
+#[async_trait]
+impl<A: Adapter + 'static> Middleware<A> for RequireJson {
+    async fn call<'a>(
+        &self,
+        request: Request<Body>,
+        _application: &'a Application<A>,
+    ) -> Result<Request<Body>, ResponseError> {
+        require_json(&request)?;
+
+        Ok(request)
+    }
+}
+
+fn require_json(request: &Request<Body>) -> Result<(), ResponseError> {
+    if !matches!(*request.method(), Method::POST | Method::PUT) {
+        return Ok(());
+    }
+
+    // ignore any parameters, e.g. `application/json; charset=utf-8`
+    let is_json = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .and_then(|content_type| content_type.split(';').next())
+        .map_or(false, |mime| {
+            mime.trim().eq_ignore_ascii_case("application/json")
+        });
+
+    if is_json {
+        Ok(())
+    } else {
+        Err(ResponseError::UnsupportedMediaType(
+            "Content-Type should be application/json".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn request(method: Method, content_type: Option<&str>) -> Request<Body> {
+        let mut builder = Request::builder().method(method).uri("/channel");
+        if let Some(content_type) = content_type {
+            builder = builder.header(CONTENT_TYPE, content_type);
+        }
+
+        builder.body(Body::empty()).expect("Should build Request")
+    }
+
+    #[test]
+    fn json_content_type_is_accepted() {
+        assert!(require_json(&request(Method::POST, Some("application/json"))).is_ok());
+        assert!(require_json(&request(
+            Method::PUT,
+            Some("Application/JSON; charset=utf-8")
+        ))
+        .is_ok());
+        // only the requests with a body are checked
+        assert!(require_json(&request(Method::GET, None)).is_ok());
+    }
+
+    #[test]
+    fn other_content_types_are_rejected() {
+        let form = require_json(&request(
+            Method::POST,
+            Some("application/x-www-form-urlencoded"),
+        ));
+        assert!(matches!(form, Err(ResponseError::UnsupportedMediaType(_))));
+
+        let missing = require_json(&request(Method::PUT, None));
+        assert!(matches!(
+            missing,
+            Err(ResponseError::UnsupportedMediaType(_))
+        ));
+    }
+}
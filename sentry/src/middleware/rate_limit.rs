@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
+
+use async_trait::async_trait;
+use hyper::{Body, Request};
+
+use primitives::{adapter::Adapter, config::RequestsRateLimit};
+
+use crate::{middleware::Middleware, Application, ResponseError};
+
+/// When tracking more client IPs than this, the full buckets are dropped,
+/// since they are the same as a newly created bucket.
+const MAX_TRACKED_IPS: usize = 10_000;
+/// The minimum number of buckets evicted at once when reaching [`MAX_TRACKED_IPS`],
+/// so the eviction doesn't happen again for each of the following new client IPs.
+const EVICTION_BATCH: usize = MAX_TRACKED_IPS / 10;
+
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(limit: &RequestsRateLimit, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(limit.burst),
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, limit: &RequestsRateLimit, now: Instant) {
+        self.tokens = self.tokens_at(limit, now);
+        self.last_refill = now;
+    }
+
+    /// The tokens of the bucket at the time of `now`, without refilling it
+    fn tokens_at(&self, limit: &RequestsRateLimit, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        let refilled = elapsed.as_secs_f64() * f64::from(limit.per_second.get());
+
+        (self.tokens + refilled).min(f64::from(limit.burst))
+    }
+
+    fn is_full_at(&self, limit: &RequestsRateLimit, now: Instant) -> bool {
+        self.tokens_at(limit, now) >= f64::from(limit.burst)
+    }
+
+    /// Refills the bucket and takes a single token out of it, if there is one
+    fn take(&mut self, limit: &RequestsRateLimit, now: Instant) -> bool {
+        self.refill(limit, now);
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// The token buckets of the client IPs, shared between all the requests to the [`Application`]
+#[derive(Debug, Default, Clone)]
+pub struct RateLimiter(Arc<Mutex<HashMap<String, TokenBucket>>>);
+
+impl RateLimiter {
+    /// Returns whether a request of the client `ip` is allowed by the `limit` at the time of `now`
+    pub fn allow(&self, ip: &str, limit: &RequestsRateLimit, now: Instant) -> bool {
+        let mut buckets = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if buckets.len() >= MAX_TRACKED_IPS && !buckets.contains_key(ip) {
+            evict(&mut buckets, limit, now);
+        }
+
+        buckets
+            .entry(ip.to_string())
+            .or_insert_with(|| TokenBucket::new(limit, now))
+            .take(limit, now)
+    }
+}
+
+/// Drops the full buckets and, if there are not at least [`EVICTION_BATCH`] of them,
+/// the buckets of the client IPs with the oldest requests as well.
+/// The client IPs of the latter get a new (full) bucket on their next request.
+fn evict(buckets: &mut HashMap<String, TokenBucket>, limit: &RequestsRateLimit, now: Instant) {
+    buckets.retain(|_, bucket| !bucket.is_full_at(limit, now));
+
+    let excess = (buckets.len() + EVICTION_BATCH).saturating_sub(MAX_TRACKED_IPS);
+    if excess > 0 {
+        // buckets are only refilled when taking a token, i.e. on a request of the client IP
+        let mut by_last_request = buckets
+            .iter()
+            .map(|(ip, bucket)| (bucket.last_refill, ip.clone()))
+            .collect::<Vec<_>>();
+        by_last_request.sort_unstable();
+
+        for (_, ip) in by_last_request.into_iter().take(excess) {
+            buckets.remove(&ip);
+        }
+    }
+}
+
+/// Rejects the request with `429 Too Many Requests` when the token bucket of the client IP is empty.
+///
+/// The client IP is the IP of the connection, unless it's one of the `trusted_proxies` of the config,
+/// see [`client_ip`]. Requests without a `SocketAddr` of the connection are not limited.
+#[derive(Debug)]
+pub struct RateLimit;
+
+#[async_trait]
+impl<A: Adapter + 'static> Middleware<A> for RateLimit {
+    async fn call<'a>(
+        &self,
+        request: Request<Body>,
+        application: &'a Application<A>,
+    ) -> Result<Request<Body>, ResponseError> {
+        let ip = match client_ip(&request, &application.config.trusted_proxies) {
+            Some(ip) => ip,
+            None => return Ok(request),
+        };
+
+        let limit = &application.config.requests_rate_limit;
+        if application
+            .rate_limiter
+            .allow(&ip.to_string(), limit, Instant::now())
+        {
+            Ok(request)
+        } else {
+            Err(ResponseError::TooManyRequests(
+                "Too many requests, please try again later".to_string(),
+            ))
+        }
+    }
+}
+
+/// When the connection comes from one of the `trusted_proxies`, the client IP is the right-most hop
+/// of the `X-Forwarded-For` header which is not a trusted proxy, since the hops to the left of it
+/// are supplied by the client and can be spoofed.
+/// A malformed hop can't be trusted either, so then the IP of the connection is used.
+fn client_ip(request: &Request<Body>, trusted_proxies: &[IpAddr]) -> Option<IpAddr> {
+    let connection_ip = request.extensions().get::<SocketAddr>()?.ip();
+    if !trusted_proxies.contains(&connection_ip) {
+        return Some(connection_ip);
+    }
+
+    let hops = request
+        .headers()
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|hop| hop.trim().parse::<IpAddr>().ok())
+        .collect::<Vec<_>>();
+
+    for hop in hops.into_iter().rev() {
+        match hop {
+            Some(ip) if trusted_proxies.contains(&ip) => continue,
+            Some(ip) => return Some(ip),
+            None => break,
+        }
+    }
+
+    Some(connection_ip)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::tests_postgres::with_test_database;
+    use crate::test_util::setup_dummy_app;
+    use hyper::StatusCode;
+    use serde_json::json;
+    use std::num::NonZeroU32;
+    use std::time::Duration;
+
+    #[test]
+    fn token_bucket_refills_over_time() {
+        let limit = RequestsRateLimit {
+            burst: 2,
+            per_second: NonZeroU32::new(1).expect("Should be non-zero"),
+        };
+        let rate_limiter = RateLimiter::default();
+        let now = Instant::now();
+
+        assert!(rate_limiter.allow("127.0.0.1", &limit, now));
+        assert!(rate_limiter.allow("127.0.0.1", &limit, now));
+        assert!(
+            !rate_limiter.allow("127.0.0.1", &limit, now),
+            "The burst should be exhausted"
+        );
+        assert!(
+            rate_limiter.allow("10.0.0.1", &limit, now),
+            "Other IPs should have their own bucket"
+        );
+
+        let half_second = now + Duration::from_millis(500);
+        assert!(!rate_limiter.allow("127.0.0.1", &limit, half_second));
+
+        let second = now + Duration::from_secs(1);
+        assert!(rate_limiter.allow("127.0.0.1", &limit, second));
+        assert!(!rate_limiter.allow("127.0.0.1", &limit, second));
+
+        // the bucket never holds more than the burst
+        let minute = now + Duration::from_secs(60);
+        assert!(rate_limiter.allow("127.0.0.1", &limit, minute));
+        assert!(rate_limiter.allow("127.0.0.1", &limit, minute));
+        assert!(!rate_limiter.allow("127.0.0.1", &limit, minute));
+    }
+
+    #[test]
+    fn a_batch_of_the_oldest_buckets_is_evicted_when_tracking_too_many_ips() {
+        let limit = RequestsRateLimit {
+            burst: 100,
+            per_second: NonZeroU32::new(1).expect("Should be non-zero"),
+        };
+        let rate_limiter = RateLimiter::default();
+        let now = Instant::now();
+
+        // none of the buckets gets full again during the test
+        for index in 0..MAX_TRACKED_IPS {
+            let ip = index.to_string();
+            let at = now + Duration::from_micros(index as u64);
+            assert!(rate_limiter.allow(&ip, &limit, at));
+        }
+
+        let later = now + Duration::from_micros(MAX_TRACKED_IPS as u64);
+        assert!(rate_limiter.allow("new", &limit, later));
+
+        let buckets = rate_limiter.0.lock().expect("Should lock the buckets");
+        assert_eq!(MAX_TRACKED_IPS - EVICTION_BATCH + 1, buckets.len());
+        assert!(!buckets.contains_key("0"));
+        assert!(!buckets.contains_key(&(EVICTION_BATCH - 1).to_string()));
+        assert!(buckets.contains_key(&EVICTION_BATCH.to_string()));
+        assert!(buckets.contains_key("new"));
+    }
+
+    #[test]
+    fn zero_requests_per_second_is_rejected() {
+        let zero = serde_json::from_value::<RequestsRateLimit>(json!({
+            "burst": 1,
+            "per_second": 0,
+        }));
+        assert!(zero.is_err(), "The bucket would never be refilled");
+    }
+
+    #[tokio::test]
+    async fn over_the_limit_requests_get_too_many_requests() {
+        with_test_database(|pool| async move {
+            let mut app = setup_dummy_app(pool).await;
+            // a single token is refilled every second, which is longer than the test
+            app.config.requests_rate_limit = RequestsRateLimit {
+                burst: 1,
+                per_second: NonZeroU32::new(1).expect("Should be non-zero"),
+            };
+
+            let request = |socket_ip: [u8; 4], forwarded_for: Option<&str>| {
+                let mut request = Request::get(
+                    "/analytics?eventType=IMPRESSION&metric=eventCounts&timeframe=day",
+                );
+                if let Some(forwarded_for) = forwarded_for {
+                    request = request.header("x-forwarded-for", forwarded_for);
+                }
+                let mut request = request.body(Body::empty()).expect("Should build Request");
+                request
+                    .extensions_mut()
+                    .insert(SocketAddr::from((socket_ip, 8005)));
+
+                request
+            };
+
+            let response = app.handle_routing(request([1, 1, 1, 1], None)).await;
+            assert_eq!(StatusCode::OK, response.status());
+
+            let response = app.handle_routing(request([1, 1, 1, 1], None)).await;
+            assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+
+            let response = app.handle_routing(request([2, 2, 2, 2], None)).await;
+            assert_eq!(StatusCode::OK, response.status());
+
+            // requests without a connection address are not limited
+            let without_socket_addr = Request::new(Body::empty());
+            RateLimit
+                .call(without_socket_addr, &app)
+                .await
+                .expect("Should not limit the request");
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn spoofed_forwarded_for_headers_do_not_reset_the_bucket() {
+        with_test_database(|pool| async move {
+            let mut app = setup_dummy_app(pool).await;
+            app.config.requests_rate_limit = RequestsRateLimit {
+                burst: 1,
+                per_second: NonZeroU32::new(1).expect("Should be non-zero"),
+            };
+            app.config.trusted_proxies =
+                vec![IpAddr::from([10, 0, 0, 1]), IpAddr::from([10, 0, 0, 2])];
+
+            let call = |socket_ip: [u8; 4], forwarded_for: &str| {
+                let mut request = Request::builder()
+                    .header("x-forwarded-for", forwarded_for)
+                    .header("true-client-ip", forwarded_for)
+                    .body(Body::empty())
+                    .expect("Should build Request");
+                request
+                    .extensions_mut()
+                    .insert(SocketAddr::from((socket_ip, 8005)));
+
+                RateLimit.call(request, &app)
+            };
+
+            // the headers of clients which are not trusted proxies are ignored
+            call([1, 1, 1, 1], "5.5.5.5")
+                .await
+                .expect("Should allow the first request");
+            let error = call([1, 1, 1, 1], "6.6.6.6")
+                .await
+                .expect_err("Should limit the request with a different header");
+            assert!(matches!(error, ResponseError::TooManyRequests(_)));
+
+            // behind a trusted proxy, the right-most hop which is not a trusted proxy is used
+            call([10, 0, 0, 1], "5.5.5.5, 2.2.2.2, 10.0.0.2")
+                .await
+                .expect("Should allow the first request of 2.2.2.2");
+            let error = call([10, 0, 0, 1], "6.6.6.6, 2.2.2.2")
+                .await
+                .expect_err("Should limit 2.2.2.2 regardless of the spoofed hops");
+            assert!(matches!(error, ResponseError::TooManyRequests(_)));
+            call([10, 0, 0, 1], "3.3.3.3")
+                .await
+                .expect("Should allow the request of another client of the proxy");
+        })
+        .await
+    }
+}
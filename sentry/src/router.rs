@@ -0,0 +1,272 @@
+//! A small router which matches the path of a request against patterns like `/channel/0x:id/events`.
+//!
+//! A pattern is split into segments by `/` and every segment is either:
+//! - static, e.g. `channel`, which should be equal to the segment of the path
+//! - a named parameter, e.g. `:id`, which matches any non-empty segment of the path.
+//!   The parameter can have a static prefix, e.g. `0x:id`, which is not part of the extracted value.
+//! - a wildcard, e.g. `*rest`, which can only be the last segment and matches all the remaining
+//!   segments (including none of them). The extracted value is the segments joined by `/`.
+//!
+//! Trailing slashes of the path are ignored.
+use hyper::Method;
+
+use crate::RouteParams;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Static(String),
+    Param { prefix: String, name: String },
+    Wildcard(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern(Vec<Segment>);
+
+impl Pattern {
+    /// # Panics
+    /// When a wildcard is not the last segment of the pattern or a parameter has no name.
+    pub fn new(pattern: &str) -> Self {
+        let segments: Vec<Segment> = split_path(pattern)
+            .map(|segment| {
+                if let Some(name) = segment.strip_prefix('*') {
+                    Segment::Wildcard(name.to_string())
+                } else if let Some(position) = segment.find(':') {
+                    let (prefix, name) = (&segment[..position], &segment[position + 1..]);
+                    assert!(!name.is_empty(), "Parameter without a name in {}", pattern);
+
+                    Segment::Param {
+                        prefix: prefix.to_string(),
+                        name: name.to_string(),
+                    }
+                } else {
+                    Segment::Static(segment.to_string())
+                }
+            })
+            .collect();
+
+        let wildcard_position = segments
+            .iter()
+            .position(|segment| matches!(segment, Segment::Wildcard(_)));
+        if let Some(position) = wildcard_position {
+            assert_eq!(
+                segments.len() - 1,
+                position,
+                "The wildcard should be the last segment of {}",
+                pattern
+            );
+        }
+
+        Self(segments)
+    }
+
+    /// Returns the extracted parameters, in the order they appear in the pattern,
+    /// if the `path` matches the pattern.
+    pub fn matches(&self, path: &str) -> Option<RouteParams> {
+        let mut path_segments = split_path(path);
+        let mut params = RouteParams::default();
+
+        for segment in self.0.iter() {
+            match segment {
+                Segment::Static(expected) => {
+                    if path_segments.next()? != expected.as_str() {
+                        return None;
+                    }
+                }
+                Segment::Param { prefix, name } => {
+                    let value = path_segments
+                        .next()?
+                        .strip_prefix(prefix.as_str())
+                        .filter(|value| !value.is_empty())?;
+
+                    params.push(name, value);
+                }
+                Segment::Wildcard(name) => {
+                    let rest = path_segments.by_ref().collect::<Vec<_>>().join("/");
+
+                    params.push(name, &rest);
+                }
+            }
+        }
+
+        // all segments of the path should be matched
+        match path_segments.next() {
+            Some(_) => None,
+            None => Some(params),
+        }
+    }
+}
+
+fn split_path(path: &str) -> impl Iterator<Item = &str> {
+    path.trim_matches('/')
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+}
+
+#[derive(Debug)]
+struct Route<T> {
+    method: Method,
+    pattern: Pattern,
+    handler: T,
+}
+
+/// Dispatches the requests to the `handler` of the first route
+/// that matches both the method and the path of the request.
+#[derive(Debug)]
+pub struct Router<T>(Vec<Route<T>>);
+
+impl<T> Default for Router<T> {
+    fn default() -> Self {
+        Self(vec![])
+    }
+}
+
+impl<T> Router<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn route(mut self, method: Method, pattern: &str, handler: T) -> Self {
+        self.0.push(Route {
+            method,
+            pattern: Pattern::new(pattern),
+            handler,
+        });
+
+        self
+    }
+
+    /// Returns the handler of the matched route together with the parameters extracted from the `path`
+    ///
+    /// When the `path` matches some of the routes, but none of them is for the `method`,
+    /// [`RouteError::MethodNotAllowed`] is returned with the methods allowed for this `path`.
+    pub fn find(&self, method: &Method, path: &str) -> Result<(&T, RouteParams), RouteError> {
+        let mut allowed = vec![];
+
+        for route in self.0.iter() {
+            if let Some(params) = route.pattern.matches(path) {
+                if &route.method == method {
+                    return Ok((&route.handler, params));
+                }
+
+                if !allowed.contains(&route.method) {
+                    allowed.push(route.method.clone());
+                }
+            }
+        }
+
+        if allowed.is_empty() {
+            Err(RouteError::NotFound)
+        } else {
+            Err(RouteError::MethodNotAllowed(allowed))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteError {
+    NotFound,
+    /// The path has routes only for the contained methods
+    MethodNotAllowed(Vec<Method>),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CHANNEL_ID: &str = "061d5e2a67d0a9a10f1c732bca12a676d83f79663a396f7d87b3e30b9b411088";
+
+    fn values(params: &RouteParams) -> Vec<String> {
+        (0..)
+            .map(|index| params.get(index))
+            .take_while(Option::is_some)
+            .flatten()
+            .collect()
+    }
+
+    #[test]
+    fn patterns_extract_the_params() {
+        let status = Pattern::new("/channel/0x:id/status");
+        let params = status
+            .matches(&format!("/channel/0x{}/status/", CHANNEL_ID))
+            .expect("Should match with a trailing slash");
+        assert_eq!(Some(CHANNEL_ID), params.named("id"));
+        assert_eq!(vec![CHANNEL_ID.to_string()], values(&params));
+
+        // the prefix is required and the parameter can't be empty
+        assert!(status
+            .matches(&format!("/channel/{}/status", CHANNEL_ID))
+            .is_none());
+        assert!(status.matches("/channel/0x/status").is_none());
+        assert!(status.matches("/channel/0x123/status/extra").is_none());
+        assert!(status.matches("/channel/0x123").is_none());
+
+        let accounting = Pattern::new("/channel/:id/accounting/:validator");
+        let params = accounting
+            .matches("/channel/0x123/accounting/leader")
+            .expect("Should match");
+        assert_eq!(Some("0x123"), params.named("id"));
+        assert_eq!(Some("leader"), params.named("validator"));
+        assert_eq!(None, params.named("other"));
+        assert_eq!(
+            vec!["0x123".to_string(), "leader".to_string()],
+            values(&params)
+        );
+    }
+
+    #[test]
+    fn wildcard_matches_the_rest_of_the_path() {
+        let messages = Pattern::new("/channel/0x:id/validator-messages/*rest");
+
+        let params = messages
+            .matches("/channel/0x123/validator-messages")
+            .expect("Should match without any rest");
+        assert_eq!(Some(""), params.named("rest"));
+
+        let params = messages
+            .matches("/channel/0x123/validator-messages/0xabc/NewState+ApproveState")
+            .expect("Should match the rest");
+        assert_eq!(Some("123"), params.named("id"));
+        assert_eq!(Some("0xabc/NewState+ApproveState"), params.named("rest"));
+    }
+
+    #[test]
+    #[should_panic(expected = "The wildcard should be the last segment")]
+    fn wildcard_should_be_the_last_segment() {
+        Pattern::new("/channel/*rest/status");
+    }
+
+    #[test]
+    fn router_dispatches_by_method_and_path() {
+        let router = Router::new()
+            .route(Method::GET, "/channel/0x:id/status", "status")
+            .route(Method::POST, "/channel/0x:id/events", "insert_events")
+            .route(Method::GET, "/channel/0x:id/events", "list_events");
+
+        let (handler, params) = router
+            .find(&Method::POST, "/channel/0x123/events")
+            .expect("Should find the route");
+        assert_eq!(&"insert_events", handler);
+        assert_eq!(Some("123"), params.named("id"));
+
+        let (handler, _) = router
+            .find(&Method::GET, "/channel/0x123/events")
+            .expect("Should find the route");
+        assert_eq!(&"list_events", handler);
+
+        assert_eq!(
+            Some(RouteError::NotFound),
+            router.find(&Method::GET, "/channel/0x123/unknown").err()
+        );
+        assert_eq!(
+            Some(RouteError::MethodNotAllowed(vec![Method::GET])),
+            router.find(&Method::POST, "/channel/0x123/status").err()
+        );
+        assert_eq!(
+            Some(RouteError::MethodNotAllowed(vec![
+                Method::POST,
+                Method::GET
+            ])),
+            router.find(&Method::DELETE, "/channel/0x123/events").err()
+        );
+    }
+}
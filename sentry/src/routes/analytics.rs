@@ -1,16 +1,25 @@
 use crate::{
-    db::analytics::{advertiser_channel_ids, get_advanced_reports, get_analytics, AnalyticsType},
+    db::analytics::{
+        advertiser_channel_ids, fetch_analytics, get_advanced_reports, get_analytics, AnalyticsType,
+    },
     success_response, Application, Auth, ResponseError, RouteParams,
 };
 use hyper::{Body, Request, Response};
 use primitives::{
     adapter::Adapter,
-    analytics::{AnalyticsQuery, AnalyticsResponse},
+    analytics::{AnalyticsQuery, AnalyticsResponse, Metric},
     ChannelId,
 };
 use redis::aio::MultiplexedConnection;
 use slog::{error, Logger};
 
+/// The invalid `eventType`, `metric` and `timeframe` values are rejected
+/// when deserializing the query, before any of them can reach the database.
+fn analytics_query(req: &Request<Body>) -> Result<AnalyticsQuery, ResponseError> {
+    serde_urlencoded::from_str::<AnalyticsQuery>(req.uri().query().unwrap_or(""))
+        .map_err(|e| ResponseError::BadRequest(e.to_string()))
+}
+
 pub async fn publisher_analytics<A: Adapter>(
     req: Request<Body>,
     app: &Application<A>,
@@ -28,10 +37,27 @@ pub async fn publisher_analytics<A: Adapter>(
         .map(success_response)
 }
 
+/// Only the event counts are public.
+/// The event payouts require authentication and are scoped to the authenticated publisher.
 pub async fn analytics<A: Adapter>(
     req: Request<Body>,
     app: &Application<A>,
 ) -> Result<Response<Body>, ResponseError> {
+    let query = analytics_query(&req)?;
+
+    if query.metric == Metric::EventPayouts {
+        let auth = req
+            .extensions()
+            .get::<Auth>()
+            .ok_or(ResponseError::Unauthorized)?
+            .clone();
+
+        // don't cache it, since the response is specific to the authenticated publisher
+        return process_analytics(req, app, AnalyticsType::Publisher { auth })
+            .await
+            .map(success_response);
+    }
+
     let request_uri = req.uri().to_string();
     let redis = app.redis.clone();
 
@@ -80,7 +106,7 @@ pub async fn process_analytics<A: Adapter>(
     app: &Application<A>,
     analytics_type: AnalyticsType,
 ) -> Result<String, ResponseError> {
-    let query = serde_urlencoded::from_str::<AnalyticsQuery>(&req.uri().query().unwrap_or(""))?;
+    let query = analytics_query(&req)?;
     query
         .is_valid()
         .map_err(|e| ResponseError::BadRequest(e.to_string()))?;
@@ -91,14 +117,21 @@ pub async fn process_analytics<A: Adapter>(
 
     let limit = query.limit;
 
-    let aggr = get_analytics(
-        query,
-        &app.pool,
-        analytics_type,
-        segment_channel,
-        channel_id,
-    )
-    .await?;
+    let aggr = match analytics_type {
+        AnalyticsType::Global if channel_id.is_none() && !segment_channel => {
+            fetch_analytics(&app.pool, &query).await?
+        }
+        analytics_type => {
+            get_analytics(
+                query,
+                &app.pool,
+                analytics_type,
+                segment_channel,
+                channel_id,
+            )
+            .await?
+        }
+    };
 
     let response = AnalyticsResponse { limit, aggr };
 
@@ -110,14 +143,21 @@ pub async fn advanced_analytics<A: Adapter>(
     req: Request<Body>,
     app: &Application<A>,
 ) -> Result<Response<Body>, ResponseError> {
-    let auth = req.extensions().get::<Auth>().expect("auth is required");
-    let advertiser_channels = advertiser_channel_ids(&app.pool, &auth.uid).await?;
+    let auth = req
+        .extensions()
+        .get::<Auth>()
+        .ok_or(ResponseError::Unauthorized)?;
+
+    let query = analytics_query(&req)?;
+    query
+        .is_valid()
+        .map_err(|e| ResponseError::BadRequest(e.to_string()))?;
 
-    let query = serde_urlencoded::from_str::<AnalyticsQuery>(&req.uri().query().unwrap_or(""))?;
+    let advertiser_channels = advertiser_channel_ids(&app.pool, &auth.uid).await?;
 
     let response = get_advanced_reports(
         &app.redis,
-        &query.event_type,
+        query.event_type.as_str(),
         &auth.uid,
         &advertiser_channels,
     )
@@ -144,3 +184,287 @@ async fn cache(
         error!(&logger, "Server error: {}", err; "module" => "analytics-cache");
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::{insert_channel, insert_event_aggregate, tests_postgres::with_test_database};
+    use crate::test_util::setup_dummy_app;
+    use chrono::Utc;
+    use hyper::{header::AUTHORIZATION, StatusCode};
+    use primitives::sentry::{
+        AdvancedAnalyticsResponse, AggregateEvents, ChannelReport, EventAggregate,
+    };
+    use primitives::util::tests::prep_db::{AUTH, DUMMY_CHANNEL, IDS};
+    use primitives::Channel;
+
+    #[tokio::test]
+    async fn advanced_analytics_returns_the_reports_of_the_advertiser_channels() {
+        with_test_database(|pool| async move {
+            let app = setup_dummy_app(pool).await;
+
+            let channel = Channel {
+                id: ChannelId::from([2_u8; 32]),
+                creator: IDS["leader"],
+                ..DUMMY_CHANNEL.clone()
+            };
+            insert_channel(&app.pool, &channel)
+                .await
+                .expect("Should insert the channel");
+
+            let report_key = format!(
+                "{}:{}:{}",
+                ChannelReport::Hostname,
+                "IMPRESSION",
+                channel.id
+            );
+            redis::cmd("ZADD")
+                .arg(&report_key)
+                .arg(4)
+                .arg("advanced-analytics.adex.network")
+                .query_async::<_, ()>(&mut app.redis.clone())
+                .await
+                .expect("Should add the report stat");
+
+            let unauthorized = Request::builder()
+                .uri("/analytics/advanced?eventType=IMPRESSION")
+                .body(Body::empty())
+                .expect("Should build Request");
+            let response = app.handle_routing(unauthorized).await;
+            assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+            let request = Request::builder()
+                .uri("/analytics/advanced?eventType=IMPRESSION")
+                .header(AUTHORIZATION, format!("Bearer {}", AUTH["leader"]))
+                .body(Body::empty())
+                .expect("Should build Request");
+
+            let response = app.handle_routing(request).await;
+            assert_eq!(StatusCode::OK, response.status());
+
+            let body = hyper::body::to_bytes(response.into_body())
+                .await
+                .expect("Should read the body");
+            let advanced: AdvancedAnalyticsResponse =
+                serde_json::from_slice(&body).expect("Should deserialize the response");
+
+            let channel_stats = advanced
+                .by_channel_stats
+                .get(&channel.id)
+                .expect("Should have stats for the advertiser channel");
+            assert_eq!(
+                3,
+                channel_stats.len(),
+                "All channel reports should be present"
+            );
+            assert_eq!(
+                Some(&4.0),
+                channel_stats[&ChannelReport::Hostname].get("advanced-analytics.adex.network")
+            );
+            assert_eq!(5, advanced.publisher_stats.len());
+
+            let _ = redis::cmd("DEL")
+                .arg(&report_key)
+                .query_async::<_, ()>(&mut app.redis.clone())
+                .await;
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn analytics_returns_the_global_analytics_for_a_valid_query() {
+        with_test_database(|pool| async move {
+            let app = setup_dummy_app(pool).await;
+
+            let channel = DUMMY_CHANNEL.clone();
+            insert_channel(&app.pool, &channel)
+                .await
+                .expect("Should insert the channel");
+
+            let aggregate_events = AggregateEvents {
+                event_counts: Some(vec![(IDS["publisher"], 2.into())].into_iter().collect()),
+                event_payouts: vec![(IDS["publisher"], 20.into())].into_iter().collect(),
+            };
+            let event_aggregate = EventAggregate {
+                channel_id: channel.id,
+                created: Utc::now(),
+                events: vec![("IMPRESSION".to_string(), aggregate_events)]
+                    .into_iter()
+                    .collect(),
+            };
+            insert_event_aggregate(&app.pool, &channel.id, &event_aggregate)
+                .await
+                .expect("Should insert the event aggregate");
+
+            let uri = "/analytics?eventType=IMPRESSION&metric=eventCounts&timeframe=day&limit=10";
+            // clear any response cached by a previous run
+            let _ = redis::cmd("DEL")
+                .arg(uri)
+                .query_async::<_, ()>(&mut app.redis.clone())
+                .await;
+
+            let request = Request::builder()
+                .uri(uri)
+                .body(Body::empty())
+                .expect("Should build Request");
+
+            let response = app.handle_routing(request).await;
+            assert_eq!(StatusCode::OK, response.status());
+
+            let body = hyper::body::to_bytes(response.into_body())
+                .await
+                .expect("Should read the body");
+            let analytics: AnalyticsResponse =
+                serde_json::from_slice(&body).expect("Should deserialize the response");
+
+            assert_eq!(10, analytics.limit);
+            assert_eq!(1, analytics.aggr.len());
+            assert_eq!("2", analytics.aggr[0].value);
+
+            let _ = redis::cmd("DEL")
+                .arg(uri)
+                .query_async::<_, ()>(&mut app.redis.clone())
+                .await;
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn analytics_returns_bad_request_for_an_invalid_timeframe() {
+        with_test_database(|pool| async move {
+            let app = setup_dummy_app(pool).await;
+
+            let request = Request::builder()
+                .uri("/analytics?timeframe=decade")
+                .body(Body::empty())
+                .expect("Should build Request");
+
+            let response = app.handle_routing(request).await;
+            assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+            let body = hyper::body::to_bytes(response.into_body())
+                .await
+                .expect("Should read the body");
+            let error: serde_json::Value =
+                serde_json::from_slice(&body).expect("Should deserialize the error");
+
+            assert!(error["message"]
+                .as_str()
+                .expect("Should have a message")
+                .contains("invalid timeframe"));
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn analytics_rejects_a_malicious_timeframe_before_querying() {
+        with_test_database(|pool| async move {
+            let app = setup_dummy_app(pool).await;
+
+            // `hour', created) AS time FROM channels; DROP TABLE event_aggregates; --`
+            let request = Request::builder()
+                .uri("/analytics/for-publisher?timeframe=hour%27%2C%20created%29%20AS%20time%20FROM%20channels%3B%20DROP%20TABLE%20event_aggregates%3B%20--")
+                .header(AUTHORIZATION, format!("Bearer {}", AUTH["publisher"]))
+                .body(Body::empty())
+                .expect("Should build Request");
+
+            let response = app.handle_routing(request).await;
+            assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+            let body = hyper::body::to_bytes(response.into_body())
+                .await
+                .expect("Should read the body");
+            let error: serde_json::Value =
+                serde_json::from_slice(&body).expect("Should deserialize the error");
+
+            assert!(error["message"]
+                .as_str()
+                .expect("Should have a message")
+                .contains("invalid timeframe"));
+
+            let event_aggregates = app
+                .pool
+                .run(|connection| async move {
+                    match connection
+                        .query("SELECT * FROM event_aggregates", &[])
+                        .await
+                    {
+                        Ok(rows) => Ok((rows.len(), connection)),
+                        Err(e) => Err((e, connection)),
+                    }
+                })
+                .await
+                .expect("The event_aggregates table should still exist");
+            assert_eq!(0, event_aggregates);
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn analytics_event_payouts_require_auth_and_are_scoped_to_the_publisher() {
+        with_test_database(|pool| async move {
+            let app = setup_dummy_app(pool).await;
+
+            let channel = DUMMY_CHANNEL.clone();
+            insert_channel(&app.pool, &channel)
+                .await
+                .expect("Should insert the channel");
+
+            let aggregate_events = AggregateEvents {
+                event_counts: Some(
+                    vec![(IDS["publisher"], 2.into()), (IDS["publisher2"], 3.into())]
+                        .into_iter()
+                        .collect(),
+                ),
+                event_payouts: vec![
+                    (IDS["publisher"], 20.into()),
+                    (IDS["publisher2"], 30.into()),
+                ]
+                .into_iter()
+                .collect(),
+            };
+            let event_aggregate = EventAggregate {
+                channel_id: channel.id,
+                created: Utc::now(),
+                events: vec![("IMPRESSION".to_string(), aggregate_events)]
+                    .into_iter()
+                    .collect(),
+            };
+            insert_event_aggregate(&app.pool, &channel.id, &event_aggregate)
+                .await
+                .expect("Should insert the event aggregate");
+
+            let uri = "/analytics?eventType=IMPRESSION&metric=eventPayouts&timeframe=day";
+
+            // public request
+            let unauthenticated = Request::builder()
+                .uri(uri)
+                .body(Body::empty())
+                .expect("Should build Request");
+            let response = app.handle_routing(unauthenticated).await;
+            assert_eq!(StatusCode::UNAUTHORIZED, response.status());
+
+            // authenticated request
+            let authenticated = Request::builder()
+                .uri(uri)
+                .header(AUTHORIZATION, format!("Bearer {}", AUTH["publisher"]))
+                .body(Body::empty())
+                .expect("Should build Request");
+            let response = app.handle_routing(authenticated).await;
+            assert_eq!(StatusCode::OK, response.status());
+
+            let body = hyper::body::to_bytes(response.into_body())
+                .await
+                .expect("Should read the body");
+            let analytics: AnalyticsResponse =
+                serde_json::from_slice(&body).expect("Should deserialize the response");
+
+            assert_eq!(1, analytics.aggr.len());
+            assert_eq!(
+                "20", analytics.aggr[0].value,
+                "Only the payouts of the authenticated publisher should be returned"
+            );
+        })
+        .await
+    }
+}
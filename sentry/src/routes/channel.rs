@@ -1,11 +1,15 @@
 use crate::db::event_aggregate::{latest_approve_state, latest_heartbeats, latest_new_state};
 use crate::db::{
-    get_channel_by_id, insert_channel, insert_validator_messages, list_channels,
-    update_exhausted_channel,
+    fetch_channel_timestamps, fetch_channels, get_channel_by_id, get_channel_timestamps,
+    insert_channel, insert_validator_messages, list_channels, update_exhausted_channel,
+    ChannelTimestamps, PoolError,
+};
+use crate::{
+    etag_response, read_body, success_response, Application, Auth, ResponseError, RouteParams,
+    Session,
 };
-use crate::{success_response, Application, Auth, ResponseError, RouteParams, Session};
-use bb8::RunError;
 use bb8_postgres::tokio_postgres::error;
+use chrono::{DateTime, Utc};
 use futures::future::try_join_all;
 use hex::FromHex;
 use hyper::{Body, Request, Response};
@@ -13,22 +17,45 @@ use primitives::{
     adapter::Adapter,
     sentry::{
         channel_list::{ChannelListQuery, LastApprovedQuery},
-        Event, LastApproved, LastApprovedResponse, SuccessResponse,
+        ChannelBatchRequest, ChannelBatchResponse, Event, LastApproved, LastApprovedResponse,
+        SuccessResponse,
     },
     validator::MessageTypes,
-    Channel, ChannelId,
+    Channel, ChannelId, Config,
 };
+use serde::Serialize;
 use slog::error;
 use std::collections::HashMap;
 
+/// A `Channel` together with the timestamps of its database row
+#[derive(Serialize)]
+struct ChannelWithTimestamps<'a> {
+    #[serde(flatten)]
+    channel: &'a Channel,
+    #[serde(flatten)]
+    timestamps: ChannelTimestamps,
+}
+
+/// The same as [`ChannelListResponse`](primitives::sentry::ChannelListResponse),
+/// but with the timestamps of the Channels
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChannelListWithTimestamps<'a> {
+    channels: Vec<ChannelWithTimestamps<'a>>,
+    total_pages: u64,
+    total: u64,
+    page: u64,
+}
+
 pub async fn channel_status<A: Adapter>(
     req: Request<Body>,
-    _: &Application<A>,
+    app: &Application<A>,
 ) -> Result<Response<Body>, ResponseError> {
-    use serde::Serialize;
     #[derive(Serialize)]
     struct ChannelStatusResponse<'a> {
         channel: &'a Channel,
+        created: DateTime<Utc>,
+        updated: DateTime<Utc>,
     }
 
     let channel = req
@@ -36,23 +63,53 @@ pub async fn channel_status<A: Adapter>(
         .get::<Channel>()
         .expect("Request should have Channel");
 
-    let response = ChannelStatusResponse { channel };
+    let timestamps = get_channel_timestamps(&app.pool, &channel.id)
+        .await?
+        .ok_or(ResponseError::NotFound)?;
+
+    let response = ChannelStatusResponse {
+        channel,
+        created: timestamps.created,
+        updated: timestamps.updated,
+    };
+
+    Ok(etag_response(&req, serde_json::to_string(&response)?))
+}
+
+pub async fn get_channel<A: Adapter>(
+    req: Request<Body>,
+    app: &Application<A>,
+) -> Result<Response<Body>, ResponseError> {
+    let channel = req
+        .extensions()
+        .get::<Channel>()
+        .expect("Request should have Channel");
+
+    let timestamps = get_channel_timestamps(&app.pool, &channel.id)
+        .await?
+        .ok_or(ResponseError::NotFound)?;
 
-    Ok(success_response(serde_json::to_string(&response)?))
+    let response = ChannelWithTimestamps {
+        channel,
+        timestamps,
+    };
+
+    Ok(etag_response(&req, serde_json::to_string(&response)?))
 }
 
 pub async fn create_channel<A: Adapter>(
     req: Request<Body>,
     app: &Application<A>,
 ) -> Result<Response<Body>, ResponseError> {
-    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let body = read_body(req.into_body(), app.config.max_body_size).await?;
 
     let channel = serde_json::from_slice::<Channel>(&body)
         .map_err(|e| ResponseError::FailedValidation(e.to_string()))?;
 
-    if let Err(e) = app.adapter.validate_channel(&channel).await {
-        return Err(ResponseError::BadRequest(e.to_string()));
-    }
+    app.adapter
+        .validate_channel(&channel)
+        .await
+        .map_err(ResponseError::from_adapter)?;
 
     let error_response = ResponseError::BadRequest("err occurred; please try again later".into());
 
@@ -60,13 +117,19 @@ pub async fn create_channel<A: Adapter>(
         Err(error) => {
             error!(&app.logger, "{}", &error; "module" => "create_channel");
             match error {
-                RunError::User(e) if e.code() == Some(&error::SqlState::UNIQUE_VIOLATION) => Err(
-                    ResponseError::Conflict("channel already exists".to_string()),
-                ),
+                PoolError::Postgres(e) if e.code() == Some(&error::SqlState::UNIQUE_VIOLATION) => {
+                    Err(ResponseError::Conflict(
+                        "channel already exists".to_string(),
+                    ))
+                }
+                error @ PoolError::Exhausted => Err(ResponseError::from(error)),
                 _ => Err(error_response),
             }
         }
-        Ok(false) => Err(error_response),
+        // `insert_channel` does nothing on conflict, so the channel already exists
+        Ok(false) => Err(ResponseError::Conflict(
+            "channel already exists".to_string(),
+        )),
         _ => Ok(()),
     }?;
 
@@ -95,14 +158,64 @@ pub async fn channel_list<A: Adapter>(
     )
     .await?;
 
-    Ok(success_response(serde_json::to_string(&list_response)?))
+    let ids = list_response
+        .channels
+        .iter()
+        .map(|channel| channel.id)
+        .collect::<Vec<_>>();
+    let timestamps = fetch_channel_timestamps(&app.pool, &ids).await?;
+
+    // a Channel without timestamps has been removed since it was listed
+    let channels = list_response
+        .channels
+        .iter()
+        .filter_map(|channel| {
+            let timestamps = *timestamps.get(&channel.id)?;
+
+            Some(ChannelWithTimestamps {
+                channel,
+                timestamps,
+            })
+        })
+        .collect();
+
+    let response = ChannelListWithTimestamps {
+        channels,
+        total_pages: list_response.total_pages,
+        total: list_response.total,
+        page: list_response.page,
+    };
+
+    Ok(etag_response(&req, serde_json::to_string(&response)?))
+}
+
+pub async fn channel_batch<A: Adapter>(
+    req: Request<Body>,
+    app: &Application<A>,
+) -> Result<Response<Body>, ResponseError> {
+    let body = read_body(req.into_body(), app.config.max_body_size).await?;
+    let batch = serde_json::from_slice::<ChannelBatchRequest>(&body)
+        .map_err(|e| ResponseError::FailedValidation(e.to_string()))?;
+
+    if batch.ids.len() > app.config.channels_find_limit as usize {
+        return Err(ResponseError::BadRequest(format!(
+            "too many channel ids, the limit is {}",
+            app.config.channels_find_limit
+        )));
+    }
+
+    let channels = fetch_channels(&app.pool, &batch.ids).await?;
+
+    Ok(success_response(serde_json::to_string(
+        &ChannelBatchResponse { channels },
+    )?))
 }
 
 pub async fn channel_validate<A: Adapter>(
     req: Request<Body>,
-    _: &Application<A>,
+    app: &Application<A>,
 ) -> Result<Response<Body>, ResponseError> {
-    let body = hyper::body::to_bytes(req.into_body()).await?;
+    let body = read_body(req.into_body(), app.config.max_body_size).await?;
     let _channel = serde_json::from_slice::<Channel>(&body)
         .map_err(|e| ResponseError::FailedValidation(e.to_string()))?;
     let create_response = SuccessResponse { success: true };
@@ -200,7 +313,7 @@ pub async fn insert_events<A: Adapter + 'static>(
 
     let channel_id = ChannelId::from_hex(route_params.index(0))?;
 
-    let body_bytes = hyper::body::to_bytes(req_body).await?;
+    let body_bytes = read_body(req_body, app.config.max_body_size).await?;
     let request_body = serde_json::from_slice::<HashMap<String, Vec<Event>>>(&body_bytes)?;
 
     let events = request_body
@@ -217,6 +330,40 @@ pub async fn insert_events<A: Adapter + 'static>(
         .unwrap())
 }
 
+/// Rejects the propagated messages if there are more than `msgs_propagate_limit` of them
+/// or if any of their balances has more than `msgs_balances_limit` entries.
+fn check_messages_size(config: &Config, messages: &[MessageTypes]) -> Result<(), ResponseError> {
+    if messages.len() > config.msgs_propagate_limit as usize {
+        return Err(ResponseError::PayloadTooLarge(format!(
+            "too many messages, the limit is {}",
+            config.msgs_propagate_limit
+        )));
+    }
+
+    let balances_limit = config.msgs_balances_limit as usize;
+    let exceeds_balances_limit = messages.iter().any(|message| match message {
+        MessageTypes::NewState(new_state) => new_state.balances.len() > balances_limit,
+        MessageTypes::RejectState(reject_state) => reject_state
+            .balances
+            .as_ref()
+            .map_or(false, |balances| balances.len() > balances_limit),
+        MessageTypes::Accounting(accounting) => {
+            accounting.balances.len() > balances_limit
+                || accounting.balances_before_fees.len() > balances_limit
+        }
+        MessageTypes::ApproveState(_) | MessageTypes::Heartbeat(_) => false,
+    });
+
+    if exceeds_balances_limit {
+        Err(ResponseError::PayloadTooLarge(format!(
+            "too many balances in a message, the limit is {}",
+            balances_limit
+        )))
+    } else {
+        Ok(())
+    }
+}
+
 pub async fn create_validator_messages<A: Adapter + 'static>(
     req: Request<Body>,
     app: &Application<A>,
@@ -233,14 +380,15 @@ pub async fn create_validator_messages<A: Adapter + 'static>(
         .expect("Request should have Channel")
         .to_owned();
 
-    let into_body = req.into_body();
-    let body = hyper::body::to_bytes(into_body).await?;
+    let body = read_body(req.into_body(), app.config.max_body_size).await?;
 
     let request_body = serde_json::from_slice::<HashMap<String, Vec<MessageTypes>>>(&body)?;
     let messages = request_body
         .get("messages")
         .ok_or_else(|| ResponseError::BadRequest("missing messages body".to_string()))?;
 
+    check_messages_size(&app.config, messages)?;
+
     let channel_is_exhausted = messages.iter().any(|message| match message {
         MessageTypes::ApproveState(approve) => approve.exhausted,
         MessageTypes::NewState(new_state) => new_state.exhausted,
@@ -267,3 +415,269 @@ pub async fn create_validator_messages<A: Adapter + 'static>(
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::tests_postgres::with_test_database;
+    use crate::map_response_error;
+    use crate::test_util::setup_dummy_app;
+    use chrono::{Duration, Utc};
+    use hyper::header::{ETAG, IF_NONE_MATCH};
+    use hyper::StatusCode;
+    use primitives::util::tests::prep_db::{DUMMY_CHANNEL, IDS};
+    use primitives::validator::NewState;
+    use primitives::{BalancesMap, ValidatorId};
+
+    #[tokio::test]
+    async fn create_channel_returns_conflict_for_a_duplicate_channel() {
+        with_test_database(|pool| async move {
+            let app = setup_dummy_app(pool).await;
+
+            let mut channel = DUMMY_CHANNEL.clone();
+            channel.valid_until = Utc::now() + Duration::days(30);
+            channel.spec.withdraw_period_start = Utc::now() + Duration::days(20);
+            let body = serde_json::to_string(&channel).expect("Should serialize the channel");
+
+            let build_request = || {
+                Request::post("/channel")
+                    .body(Body::from(body.clone()))
+                    .expect("Should build Request")
+            };
+
+            let response = create_channel(build_request(), &app)
+                .await
+                .expect("Should create the channel");
+            assert_eq!(StatusCode::OK, response.status());
+
+            let error = create_channel(build_request(), &app)
+                .await
+                .expect_err("Should not create the same channel twice");
+
+            assert!(matches!(error, ResponseError::Conflict(_)));
+            assert_eq!(StatusCode::CONFLICT, map_response_error(error).status());
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn create_channel_rejects_an_oversized_body() {
+        with_test_database(|pool| async move {
+            let mut app = setup_dummy_app(pool).await;
+            app.config.max_body_size = 1024;
+
+            let body = serde_json::json!({ "padding": "0".repeat(1024) });
+            let request = Request::post("/channel")
+                .body(Body::from(body.to_string()))
+                .expect("Should build Request");
+
+            let error = create_channel(request, &app)
+                .await
+                .expect_err("Should reject the oversized body");
+
+            assert!(matches!(error, ResponseError::PayloadTooLarge(_)));
+            assert_eq!(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                map_response_error(error).status()
+            );
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn create_channel_returns_bad_request_for_an_invalid_or_inactive_channel() {
+        use adapter::{EthereumAdapter, EthereumChannel};
+        use primitives::util::tests::{discard_logger, prep_db::DUMMY_VALIDATOR_LEADER};
+        use std::convert::TryFrom;
+        use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
+
+        with_test_database(|pool| async move {
+            let server = MockServer::start().await;
+            // an ABI encoded `uint256` equal to `0`, i.e. an Unknown (not Active) channel
+            let inactive_state = format!("0x{:0>64}", "0");
+            Mock::given(method("POST"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "result": inactive_state,
+                })))
+                .mount(&server)
+                .await;
+
+            let dummy_app = setup_dummy_app(pool).await;
+            let mut config = dummy_app.config.clone();
+            config.ethereum_network = server.uri();
+            let adapter = EthereumAdapter::new_read_only(
+                DUMMY_VALIDATOR_LEADER.id,
+                &config,
+                discard_logger(),
+            )
+            .expect("Should create the adapter");
+            let app = Application::new(
+                adapter,
+                config,
+                discard_logger(),
+                dummy_app.redis.clone(),
+                dummy_app.pool.clone(),
+            );
+
+            let mut channel = DUMMY_CHANNEL.clone();
+            channel.valid_until = Utc::now() + Duration::days(30);
+            channel.spec.withdraw_period_start = Utc::now() + Duration::days(20);
+
+            let request = |channel: &Channel| {
+                let body = serde_json::to_string(channel).expect("Should serialize the channel");
+                Request::post("/channel")
+                    .body(Body::from(body))
+                    .expect("Should build Request")
+            };
+
+            // the `DUMMY_CHANNEL.id` is not the hash of the Ethereum channel
+            let error = create_channel(request(&channel), &app)
+                .await
+                .expect_err("Should reject the invalid channel id");
+            assert!(matches!(error, ResponseError::BadRequest(_)));
+            assert_eq!(StatusCode::BAD_REQUEST, map_response_error(error).status());
+
+            let eth_channel =
+                EthereumChannel::try_from(&channel).expect("Should create the Ethereum channel");
+            channel.id = ChannelId::from(eth_channel.hash(&app.config.ethereum_core_address));
+
+            let error = create_channel(request(&channel), &app)
+                .await
+                .expect_err("Should reject the inactive channel");
+            assert!(matches!(error, ResponseError::BadRequest(_)));
+            assert_eq!(StatusCode::BAD_REQUEST, map_response_error(error).status());
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn create_validator_messages_rejects_an_oversized_balances_map() {
+        with_test_database(|pool| async move {
+            let app = setup_dummy_app(pool).await;
+
+            let balances_limit = app.config.msgs_balances_limit as usize;
+            let balances = (0..=balances_limit)
+                .map(|index| {
+                    let mut address = [0_u8; 20];
+                    address[16..].copy_from_slice(&(index as u32).to_be_bytes());
+
+                    (ValidatorId::from(&address), 1.into())
+                })
+                .collect::<BalancesMap>();
+            let new_state = MessageTypes::NewState(NewState {
+                state_root: "0".repeat(64),
+                signature: "signature".to_string(),
+                balances,
+                exhausted: false,
+            });
+            let body = serde_json::json!({ "messages": [new_state] });
+
+            let mut request =
+                Request::post(format!("/channel/{}/validator-messages", DUMMY_CHANNEL.id))
+                    .body(Body::from(body.to_string()))
+                    .expect("Should build Request");
+            request.extensions_mut().insert(Auth {
+                era: 0,
+                uid: IDS["leader"],
+            });
+            request.extensions_mut().insert(DUMMY_CHANNEL.clone());
+
+            let error = create_validator_messages(request, &app)
+                .await
+                .expect_err("Should reject the oversized balances");
+
+            assert!(matches!(error, ResponseError::PayloadTooLarge(_)));
+            assert_eq!(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                map_response_error(error).status()
+            );
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn channel_list_is_not_modified_for_a_matching_etag() {
+        with_test_database(|pool| async move {
+            let app = setup_dummy_app(pool).await;
+            insert_channel(&app.pool, &DUMMY_CHANNEL)
+                .await
+                .expect("Should insert the channel");
+
+            let request = |if_none_match: Option<&str>| {
+                let builder = Request::get("/channel/list");
+                match if_none_match {
+                    Some(etag) => builder.header(IF_NONE_MATCH, etag),
+                    None => builder,
+                }
+                .body(Body::empty())
+                .expect("Should build Request")
+            };
+
+            let response = app.handle_routing(request(None)).await;
+            assert_eq!(StatusCode::OK, response.status());
+            let etag = response.headers()[ETAG]
+                .to_str()
+                .expect("Should be a valid string")
+                .to_string();
+
+            let response = app.handle_routing(request(Some(&etag))).await;
+            assert_eq!(StatusCode::NOT_MODIFIED, response.status());
+
+            let response = app.handle_routing(request(Some("\"outdated\""))).await;
+            assert_eq!(StatusCode::OK, response.status());
+            assert_eq!(etag, response.headers()[ETAG]);
+        })
+        .await
+    }
+    #[tokio::test]
+    async fn get_channel_and_channel_list_include_the_timestamps() {
+        with_test_database(|pool| async move {
+            let app = setup_dummy_app(pool).await;
+            insert_channel(&app.pool, &DUMMY_CHANNEL)
+                .await
+                .expect("Should insert the channel");
+            let timestamps = get_channel_timestamps(&app.pool, &DUMMY_CHANNEL.id)
+                .await
+                .expect("Should get the timestamps")
+                .expect("Should have timestamps");
+
+            let body = |uri: String| {
+                let app = &app;
+                async move {
+                    let request = Request::get(uri)
+                        .body(Body::empty())
+                        .expect("Should build Request");
+                    let response = app.handle_routing(request).await;
+                    assert_eq!(StatusCode::OK, response.status());
+
+                    let body = hyper::body::to_bytes(response.into_body())
+                        .await
+                        .expect("Should read the body");
+                    serde_json::from_slice::<serde_json::Value>(&body)
+                        .expect("Should deserialize the body")
+                }
+            };
+            let expected_created =
+                serde_json::to_value(timestamps.created).expect("Should serialize");
+            let expected_updated =
+                serde_json::to_value(timestamps.updated).expect("Should serialize");
+
+            let channel = body(format!("/channel/{}", DUMMY_CHANNEL.id)).await;
+            assert_eq!(expected_created, channel["created"]);
+            assert_eq!(expected_updated, channel["updated"]);
+            let deserialized: Channel =
+                serde_json::from_value(channel).expect("Should still be a Channel");
+            assert_eq!(DUMMY_CHANNEL.id, deserialized.id);
+
+            let list = body("/channel/list".to_string()).await;
+            let channels = list["channels"].as_array().expect("Should be an array");
+            assert_eq!(1, channels.len());
+            assert_eq!(expected_created, channels[0]["created"]);
+            assert_eq!(expected_updated, channels[0]["updated"]);
+            assert_eq!(serde_json::json!(1), list["totalPages"]);
+        })
+        .await
+    }
+}
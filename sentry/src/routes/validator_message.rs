@@ -1,5 +1,6 @@
-use crate::db::get_validator_messages;
+use crate::db::{get_validator_messages, latest_messages_since};
 use crate::{success_response, Application, ResponseError};
+use chrono::{DateTime, Utc};
 use hyper::{Body, Request, Response};
 use primitives::adapter::Adapter;
 use primitives::sentry::ValidatorMessageResponse;
@@ -10,6 +11,9 @@ use std::convert::TryFrom;
 #[derive(Deserialize)]
 pub struct ValidatorMessagesListQuery {
     limit: Option<u64>,
+    /// Only the latest messages received after it, it can't be combined with the filters
+    /// by validator and message types
+    since: Option<DateTime<Utc>>,
 }
 
 pub fn extract_params(from_path: &str) -> Result<(Option<ValidatorId>, Vec<String>), DomainError> {
@@ -61,10 +65,92 @@ pub async fn list_validator_messages<A: Adapter>(
         .unwrap_or(config_limit)
         .min(config_limit);
 
-    let validator_messages =
-        get_validator_messages(&app.pool, &channel.id, validator_id, message_types, limit).await?;
+    let validator_messages = match query.since {
+        Some(since) => {
+            if validator_id.is_some() || !message_types.is_empty() {
+                return Err(ResponseError::BadRequest(
+                    "since can't be combined with a validator or message types".to_string(),
+                ));
+            }
+
+            let mut messages = latest_messages_since(&app.pool, &channel.id, since).await?;
+            messages.truncate(limit as usize);
+            messages
+        }
+        None => {
+            get_validator_messages(&app.pool, &channel.id, validator_id, message_types, limit)
+                .await?
+        }
+    };
 
     let response = ValidatorMessageResponse { validator_messages };
 
     Ok(success_response(serde_json::to_string(&response)?))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::tests_postgres::with_test_database;
+    use crate::test_util::setup_dummy_app_with;
+    use primitives::util::tests::prep_db::{DUMMY_CHANNEL, IDS};
+    use primitives::validator::{Heartbeat, MessageTypes};
+
+    #[tokio::test]
+    async fn lists_the_messages_received_since() {
+        with_test_database(|pool| async move {
+            let channel = DUMMY_CHANNEL.clone();
+            let heartbeat = MessageTypes::Heartbeat(Heartbeat {
+                signature: "signature".to_string(),
+                state_root: "0".repeat(64),
+                timestamp: Utc::now(),
+            });
+            let app = setup_dummy_app_with(
+                pool,
+                &[channel.clone()],
+                &[(&channel, IDS["leader"], heartbeat)],
+                &[],
+            )
+            .await;
+            let app = &app;
+
+            let list = |query: &str, validator_id: Option<ValidatorId>| {
+                let mut request = Request::get(format!(
+                    "/channel/{}/validator-messages?{}",
+                    channel.id, query
+                ))
+                .body(Body::empty())
+                .expect("Should build Request");
+                request.extensions_mut().insert(channel.clone());
+
+                async move { list_validator_messages(request, app, &validator_id, &[]).await }
+            };
+
+            let response = list("since=2000-01-01T00:00:00Z", None)
+                .await
+                .expect("Should list the messages");
+            let body = hyper::body::to_bytes(response.into_body())
+                .await
+                .expect("Should read the body");
+            let listed = serde_json::from_slice::<ValidatorMessageResponse>(&body)
+                .expect("Should deserialize the response");
+            assert_eq!(1, listed.validator_messages.len());
+
+            let response = list("since=2100-01-01T00:00:00Z", None)
+                .await
+                .expect("Should list the messages");
+            let body = hyper::body::to_bytes(response.into_body())
+                .await
+                .expect("Should read the body");
+            let listed = serde_json::from_slice::<ValidatorMessageResponse>(&body)
+                .expect("Should deserialize the response");
+            assert!(listed.validator_messages.is_empty());
+
+            let error = list("since=2000-01-01T00:00:00Z", Some(IDS["leader"]))
+                .await
+                .expect_err("Should not combine since with the validator filter");
+            assert!(matches!(error, ResponseError::BadRequest(_)));
+        })
+        .await
+    }
+}
@@ -0,0 +1,140 @@
+use adapter::DummyAdapter;
+use primitives::{
+    adapter::DummyAdapterOptions,
+    config::configuration,
+    sentry::EventAggregate,
+    util::tests::{
+        discard_logger,
+        prep_db::{AUTH, IDS},
+    },
+    validator::MessageTypes,
+    Channel, ValidatorId,
+};
+
+use crate::{
+    db::{
+        insert_channel, insert_event_aggregate, insert_validator_messages, redis_connection, DbPool,
+    },
+    Application,
+};
+
+/// Creates an `Application` with a `DummyAdapter` for the `leader`,
+/// the `development` config and a discarding `Logger`.
+///
+/// Use it together with [`with_test_database`](crate::db::tests_postgres::with_test_database)
+/// to get a `DbPool` with an isolated schema.
+pub async fn setup_dummy_app(pool: DbPool) -> Application<DummyAdapter> {
+    let config = configuration("development", None).expect("Dev config should be available");
+    let adapter_options = DummyAdapterOptions {
+        dummy_identity: IDS["leader"],
+        dummy_auth: IDS.clone(),
+        dummy_auth_tokens: AUTH.clone(),
+    };
+    let adapter = DummyAdapter::init(adapter_options, &config);
+    let redis = redis_connection().await.expect("Couldn't connect to Redis");
+
+    Application::new(adapter, config, discard_logger(), redis, pool)
+}
+
+/// The same as [`setup_dummy_app`], but also seeds the database with the given
+/// `channels`, the validator messages (`from` a validator for a channel) and the `event_aggregates`.
+///
+/// The channels are inserted first, so the messages and aggregates can reference them.
+///
+/// # Panics
+/// When inserting any of the data fails.
+pub async fn setup_dummy_app_with(
+    pool: DbPool,
+    channels: &[Channel],
+    validator_messages: &[(&Channel, ValidatorId, MessageTypes)],
+    event_aggregates: &[EventAggregate],
+) -> Application<DummyAdapter> {
+    for channel in channels {
+        insert_channel(&pool, channel)
+            .await
+            .expect("Should insert the channel");
+    }
+
+    for (channel, from, message) in validator_messages {
+        insert_validator_messages(&pool, channel, from, message)
+            .await
+            .expect("Should insert the validator message");
+    }
+
+    for event_aggregate in event_aggregates {
+        insert_event_aggregate(&pool, &event_aggregate.channel_id, event_aggregate)
+            .await
+            .expect("Should insert the event aggregate");
+    }
+
+    setup_dummy_app(pool).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::tests_postgres::with_test_database;
+    use crate::db::{get_channel_by_id, get_validator_messages, list_event_aggregates};
+    use chrono::Utc;
+    use primitives::{
+        sentry::AggregateEvents, util::tests::prep_db::DUMMY_CHANNEL, validator::Heartbeat,
+    };
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn setup_dummy_app_with_seeds_the_database() {
+        with_test_database(|pool| async move {
+            let channel = DUMMY_CHANNEL.clone();
+            let heartbeat = MessageTypes::Heartbeat(Heartbeat {
+                signature: "signature".to_string(),
+                state_root: "state_root".to_string(),
+                timestamp: Utc::now(),
+            });
+            let event_aggregate = EventAggregate {
+                channel_id: channel.id,
+                created: Utc::now(),
+                events: vec![(
+                    "IMPRESSION".to_string(),
+                    AggregateEvents {
+                        event_counts: Some(
+                            vec![(IDS["publisher"], 2.into())].into_iter().collect(),
+                        ),
+                        event_payouts: vec![(IDS["publisher"], 20.into())].into_iter().collect(),
+                    },
+                )]
+                .into_iter()
+                .collect::<HashMap<_, _>>(),
+            };
+
+            let app = setup_dummy_app_with(
+                pool,
+                &[channel.clone()],
+                &[(&channel, IDS["leader"], heartbeat)],
+                &[event_aggregate],
+            )
+            .await;
+
+            let seeded_channel = get_channel_by_id(&app.pool, &channel.id)
+                .await
+                .expect("Should query the channel");
+            assert_eq!(Some(channel.id), seeded_channel.map(|channel| channel.id));
+
+            let messages = get_validator_messages(
+                &app.pool,
+                &channel.id,
+                &Some(IDS["leader"]),
+                &["Heartbeat".to_string()],
+                10,
+            )
+            .await
+            .expect("Should query the validator messages");
+            assert_eq!(1, messages.len());
+
+            let event_aggregates = list_event_aggregates(&app.pool, &channel.id, 10, &None, &None)
+                .await
+                .expect("Should query the event aggregates");
+            assert_eq!(1, event_aggregates.len());
+        })
+        .await
+    }
+}
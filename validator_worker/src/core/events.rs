@@ -194,6 +194,72 @@ mod test {
         );
     }
 
+    #[test]
+    fn should_conserve_the_total_value_including_fees() {
+        // fees that result in fractional balances and fractional validator fees
+        let leader = ValidatorDesc {
+            fee: 33.into(),
+            fee_addr: Some(IDS["tester"]),
+            ..DUMMY_VALIDATOR_LEADER.clone()
+        };
+        let follower = ValidatorDesc {
+            fee: 67.into(),
+            ..DUMMY_VALIDATOR_FOLLOWER.clone()
+        };
+
+        let mut channel = Channel {
+            deposit_amount: 9_999.into(),
+            ..DUMMY_CHANNEL.clone()
+        };
+        channel.spec.validators = (leader, follower).into();
+
+        let mut accounting = Accounting {
+            last_event_aggregate: Utc::now(),
+            balances_before_fees: BalancesMap::default(),
+            balances: BalancesMap::default(),
+        };
+
+        let recipients = [IDS["publisher"], IDS["publisher2"], IDS["user"]];
+        for (index, count) in [1_u64, 7, 13, 3, 29, 101, 17, 1_000].iter().enumerate() {
+            let recipient = &recipients[index % recipients.len()];
+            let new_accounting =
+                merge_aggrs(&accounting, &[gen_ev_aggr(*count, recipient)], &channel)
+                    .expect("Should merge the event aggregates");
+
+            let total_before_fees = new_accounting.balances_before_fees.values().sum::<BigNum>();
+            assert_eq!(
+                total_before_fees,
+                new_accounting.balances.values().sum::<BigNum>(),
+                "sum(balances) == sum(balancesBeforeFees)"
+            );
+            assert!(
+                total_before_fees <= channel.deposit_amount,
+                "sum(balancesBeforeFees) <= depositAmount"
+            );
+            assert!(
+                new_accounting.balances.get(&IDS["leader"]).is_none(),
+                "the leader fee is credited to its fee address"
+            );
+
+            accounting = new_accounting;
+        }
+
+        assert_eq!(
+            &accounting.balances.values().sum::<BigNum>(),
+            &channel.deposit_amount,
+            "the deposit is fully distributed"
+        );
+        // the validators receive their fee and the rounding errors of the earners' balances
+        assert!(
+            accounting.balances[&IDS["tester"]] >= 33.into(),
+            "the fee of the leader is fully paid out"
+        );
+        assert!(
+            accounting.balances[&IDS["follower"]] >= 67.into(),
+            "the fee of the follower is fully paid out"
+        );
+    }
+
     fn gen_ev_aggr(count: u64, recipient: &ValidatorId) -> EventAggregate {
         let aggregate_events = AggregateEvents {
             event_counts: Some(
@@ -29,8 +29,7 @@ pub fn get_balances_after_fees_tree(
 
     let deposit_to_distribute = &deposit_amount - &total_validators_fee;
 
-    let ratio = Ratio::new(deposit_to_distribute.clone(), deposit_amount.clone());
-    let fee_ratio = Ratio::new(total_distributed.clone(), deposit_amount.clone());
+    let ratio = Ratio::new(deposit_to_distribute, deposit_amount);
 
     let mut balances_after_fees = BalancesMap::default();
     let mut total = BigNum::from(0);
@@ -42,44 +41,97 @@ pub fn get_balances_after_fees_tree(
         balances_after_fees.insert(*key, adjusted_balance);
     }
 
-    let rounding_error = if deposit_amount == total_distributed {
-        deposit_to_distribute.checked_sub(&total).ok_or_else(|| {
-            DomainError::RuleViolation("rounding_err should never be negative".to_owned())
-        })?
-    } else {
-        BigNum::from(0)
-    };
+    // everything that is distributed, but not paid out to the earners, goes to the validators,
+    // this way rounding the balances never creates or destroys value
+    let total_fee = total_distributed.checked_sub(&total).ok_or_else(|| {
+        DomainError::RuleViolation("total fee should never be negative".to_owned())
+    })?;
 
     let balances_after_fees = distribute_fee(
         balances_after_fees,
-        rounding_error,
-        fee_ratio,
+        total_fee,
         channel.spec.validators.iter(),
     );
 
     Ok(balances_after_fees)
 }
 
+/// Splits the `amount` proportionally to the `weights`,
+/// so that the sum of the parts is always exactly the `amount`.
+/// It's used for splitting the fee between the validators.
+///
+/// Every part is rounded down and the leftover units are given, one per part,
+/// to the parts with the largest rounding remainders.
+/// For equal remainders the part that comes first gets the unit.
+/// If all of the `weights` are zero, the `amount` is split equally.
+pub fn split_proportionally(amount: &BigNum, weights: &[BigNum]) -> Vec<BigNum> {
+    if weights.is_empty() {
+        return vec![];
+    }
+
+    let total_weight = weights.iter().sum::<BigNum>();
+    if total_weight == 0.into() {
+        return split_proportionally(amount, &vec![BigNum::from(1); weights.len()]);
+    }
+
+    let (mut parts, remainders): (Vec<BigNum>, Vec<BigNum>) = weights
+        .iter()
+        .map(|weight| {
+            let numerator = amount * weight;
+            let part = numerator.div_floor(&total_weight);
+            let remainder = &numerator - &(&part * &total_weight);
+
+            (part, remainder)
+        })
+        .unzip();
+
+    // the leftover is always less than the number of parts
+    let leftover = amount - &parts.iter().sum::<BigNum>();
+    let leftover = leftover.to_u64().unwrap_or_default() as usize;
+
+    let mut by_remainder = (0..parts.len()).collect::<Vec<_>>();
+    // the sort is stable, so parts with equal remainders keep their order
+    by_remainder.sort_by(|a, b| remainders[*b].cmp(&remainders[*a]));
+
+    for index in by_remainder.into_iter().take(leftover) {
+        parts[index] += &BigNum::from(1);
+    }
+
+    parts
+}
+
+/// Splits the `total_fee` between the validators proportionally to their `fee`,
+/// see [`split_proportionally`].
+///
+/// The `total_fee` includes the rounding remainders of the earners' balances,
+/// so `sum(balances) == sum(balancesBeforeFees)` after every aggregation.
+/// The JS validator rounds the fractional fees down and gives the remainder to the leader
+/// only once the deposit is fully distributed, so until then its balances (and state roots) differ.
+/// Since the follower recomputes the leader's state root, both validators of a channel
+/// have to use the same rounding, i.e. a channel can't be validated by a JS and a Rust validator.
 fn distribute_fee<'a>(
     mut balances: BalancesMap,
-    rounding_error: BigNum,
-    fee_ratio: Ratio<BigNum>,
+    total_fee: BigNum,
     validators: impl Iterator<Item = &'a ValidatorDesc>,
 ) -> BalancesMap {
-    for (index, validator) in validators.enumerate() {
-        let fee = &validator.fee * &fee_ratio;
+    if total_fee == 0.into() {
+        return balances;
+    }
 
-        let fee_rounded = if index == 0 {
-            &fee + &rounding_error
-        } else {
-            fee
-        };
+    let validators = validators.collect::<Vec<_>>();
+    let weights = validators
+        .iter()
+        .map(|validator| validator.fee.clone())
+        .collect::<Vec<_>>();
+    let fees = split_proportionally(&total_fee, &weights);
 
-        if fee_rounded > 0.into() {
-            let addr = validator.fee_addr.as_ref().unwrap_or(&validator.id);
-            let entry = balances.entry(addr.to_owned()).or_insert_with(|| 0.into());
+    for (validator, fee) in validators.into_iter().zip(fees) {
+        if fee > 0.into() {
+            let entry = balances
+                .entry(validator.fee_recipient())
+                .or_insert_with(|| 0.into());
 
-            *entry += &fee_rounded;
+            *entry += &fee;
         }
     }
 
@@ -92,6 +144,76 @@ mod test {
     use primitives::util::tests::prep_db::{
         DUMMY_CHANNEL, DUMMY_VALIDATOR_FOLLOWER, DUMMY_VALIDATOR_LEADER, IDS,
     };
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    mod splitting_proportionally {
+        use super::*;
+
+        fn to_big_nums(values: &[u64]) -> Vec<BigNum> {
+            values.iter().map(|value| BigNum::from(*value)).collect()
+        }
+
+        #[test]
+        fn leftover_goes_to_the_largest_remainders_first() {
+            assert_eq!(
+                to_big_nums(&[4, 3, 3]),
+                split_proportionally(&10.into(), &to_big_nums(&[1, 1, 1]))
+            );
+            assert_eq!(
+                to_big_nums(&[3, 7]),
+                split_proportionally(&10.into(), &to_big_nums(&[33, 67]))
+            );
+            assert_eq!(
+                to_big_nums(&[1, 2, 2]),
+                split_proportionally(&5.into(), &to_big_nums(&[1, 2, 2]))
+            );
+        }
+
+        #[test]
+        fn zero_weights_split_equally() {
+            assert_eq!(
+                to_big_nums(&[2, 2, 1]),
+                split_proportionally(&5.into(), &to_big_nums(&[0, 0, 0]))
+            );
+            assert!(split_proportionally(&5.into(), &[]).is_empty());
+        }
+
+        #[test]
+        fn random_splits_conserve_the_amount() {
+            // seeded, so a failing split can be reproduced
+            let mut rng = StdRng::seed_from_u64(42);
+
+            for _ in 0..1_000 {
+                let amount = BigNum::from(rng.gen_range(0, 1_000_000_000_u64));
+                let weights = (0..rng.gen_range(1, 6))
+                    .map(|_| BigNum::from(rng.gen_range(0, 1_000_u64)))
+                    .collect::<Vec<_>>();
+                let total_weight = weights.iter().sum::<BigNum>();
+
+                let parts = split_proportionally(&amount, &weights);
+
+                assert_eq!(weights.len(), parts.len());
+                assert_eq!(
+                    amount,
+                    parts.iter().sum::<BigNum>(),
+                    "split of {:?} by {:?} should conserve the amount",
+                    amount,
+                    weights
+                );
+
+                if total_weight > 0.into() {
+                    // every part differs from its exact share by less than a unit
+                    for (part, weight) in parts.iter().zip(weights.iter()) {
+                        let exact = &amount * weight;
+                        let lower = part * &total_weight;
+                        let upper = &lower + &total_weight;
+
+                        assert!(lower < &exact + &total_weight && exact < upper);
+                    }
+                }
+            }
+        }
+    }
 
     mod applying_fee_returns_the_same_tree_with_zero_fees {
         use super::*;
@@ -240,7 +362,8 @@ mod test {
             let expected_balances: BalancesMap = vec![
                 (IDS["publisher"].clone(), 99.into()),
                 (IDS["publisher2"].clone(), 1_980.into()),
-                (IDS["leader"].clone(), 209.into()),
+                // 198 + 12, the leader's fee includes the rounding error of the earners' balances
+                (IDS["leader"].clone(), 210.into()),
                 (IDS["follower"].clone(), 11.into()),
             ]
             .into_iter()
@@ -298,6 +421,88 @@ mod test {
         }
     }
 
+    #[test]
+    fn fees_are_credited_to_the_fee_addr_when_set() {
+        let balances_map = vec![
+            (IDS["publisher"].clone(), 1_000.into()),
+            (IDS["publisher2"].clone(), 1_200.into()),
+        ]
+        .into_iter()
+        .collect();
+
+        let leader = ValidatorDesc {
+            fee: 50.into(),
+            fee_addr: Some(IDS["tester"]),
+            ..DUMMY_VALIDATOR_LEADER.clone()
+        };
+        let follower = ValidatorDesc {
+            fee: 50.into(),
+            ..DUMMY_VALIDATOR_FOLLOWER.clone()
+        };
+
+        let mut spec = DUMMY_CHANNEL.spec.clone();
+        spec.validators = (leader, follower).into();
+
+        let channel = Channel {
+            deposit_amount: 10_000.into(),
+            spec,
+            ..DUMMY_CHANNEL.clone()
+        };
+
+        let expected_balances: BalancesMap = vec![
+            (IDS["publisher"].clone(), 990.into()),
+            (IDS["publisher2"].clone(), 1_188.into()),
+            (IDS["tester"].clone(), 11.into()),
+            (IDS["follower"].clone(), 11.into()),
+        ]
+        .into_iter()
+        .collect();
+
+        let balances_after_fee = get_balances_after_fees_tree(&balances_map, &channel)
+            .expect("Calculation of fees failed");
+
+        assert_eq!(expected_balances, balances_after_fee);
+    }
+
+    #[test]
+    fn the_leftover_fee_goes_to_the_validator_with_the_largest_remainder() {
+        let balances_map = vec![(IDS["publisher"].clone(), 1.into())]
+            .into_iter()
+            .collect();
+
+        let leader = ValidatorDesc {
+            fee: 1.into(),
+            ..DUMMY_VALIDATOR_LEADER.clone()
+        };
+        let follower = ValidatorDesc {
+            fee: 2.into(),
+            ..DUMMY_VALIDATOR_FOLLOWER.clone()
+        };
+
+        let mut spec = DUMMY_CHANNEL.spec.clone();
+        spec.validators = (leader, follower).into();
+
+        let channel = Channel {
+            deposit_amount: 1_000.into(),
+            spec,
+            ..DUMMY_CHANNEL.clone()
+        };
+
+        // 1 * 997 / 1000 is rounded down to 0 for the publisher and the fee of 1 is split 1:2,
+        // so the follower gets it for the larger remainder (2/3 > 1/3)
+        let expected_balances: BalancesMap = vec![
+            (IDS["publisher"].clone(), 0.into()),
+            (IDS["follower"].clone(), 1.into()),
+        ]
+        .into_iter()
+        .collect();
+
+        let balances_after_fee = get_balances_after_fees_tree(&balances_map, &channel)
+            .expect("Calculation of fees failed");
+
+        assert_eq!(expected_balances, balances_after_fee);
+    }
+
     #[test]
     fn errors_when_fees_larger_that_deposit() {
         let balances_map = vec![
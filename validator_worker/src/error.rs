@@ -1,39 +1,28 @@
 use primitives::adapter::AdapterErrorKind;
 use primitives::ChannelId;
-use std::fmt;
+use thiserror::Error;
 
-#[derive(Debug)]
-pub enum TickError {
-    TimedOut(tokio::time::Elapsed),
-    Tick(Box<dyn std::error::Error>),
-}
-
-impl fmt::Display for TickError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            TickError::TimedOut(err) => write!(f, "Tick TimedOut: ({})", err),
-            TickError::Tick(err) => write!(f, "Tick: {}", err),
-        }
-    }
-}
-
-#[derive(Debug)]
-pub enum Error<AE: AdapterErrorKind> {
-    SentryApi(crate::sentry_interface::Error<AE>),
-    LeaderTick(ChannelId, TickError),
-    FollowerTick(ChannelId, TickError),
-}
-
-impl<AE: AdapterErrorKind> std::error::Error for Error<AE> {}
+use crate::sentry_interface;
 
-impl<AE: AdapterErrorKind> fmt::Display for Error<AE> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        use Error::*;
+pub use primitives::Role;
 
-        match self {
-            SentryApi(err) => write!(f, "SentryApi: {}", err),
-            LeaderTick(channel_id, err) => write!(f, "LeaderTick {:#?}: {}", channel_id, err),
-            FollowerTick(channel_id, err) => write!(f, "FollowerTick {:#?}: {}", channel_id, err),
-        }
-    }
+#[derive(Debug, Error)]
+pub enum Error<AE: AdapterErrorKind + 'static> {
+    #[error("Initializing the SentryApi: {0}")]
+    SentryInit(#[from] sentry_interface::Error<AE>),
+    #[error("Leader tick of channel {channel}: {source}")]
+    LeaderTick {
+        channel: ChannelId,
+        #[source]
+        source: Box<dyn std::error::Error>,
+    },
+    #[error("Follower tick of channel {channel}: {source}")]
+    FollowerTick {
+        channel: ChannelId,
+        #[source]
+        source: Box<dyn std::error::Error>,
+    },
+    /// The tick didn't finish within the `validator_tick_timeout`
+    #[error("{role} tick of channel {channel} timed out")]
+    Timeout { channel: ChannelId, role: Role },
 }
@@ -8,7 +8,7 @@ use primitives::{BalancesMap, BigNum};
 use crate::core::follower_rules::{get_health, is_valid_transition};
 use crate::heartbeat::{heartbeat, HeartbeatStatus};
 use crate::sentry_interface::{PropagationResult, SentryApi};
-use crate::{get_state_root_hash, producer};
+use crate::{is_state_root_valid, producer, state_roots_match};
 use chrono::Utc;
 
 #[derive(Debug)]
@@ -66,14 +66,8 @@ pub async fn tick<A: Adapter + 'static>(
         .get_our_latest_msg(&["ApproveState", "RejectState"])
         .await?;
 
-    let our_latest_msg_state_root = match our_latest_msg_response {
-        Some(MessageTypes::ApproveState(approve_state)) => Some(approve_state.state_root),
-        Some(MessageTypes::RejectState(reject_state)) => Some(reject_state.state_root),
-        _ => None,
-    };
-
-    let latest_is_responded_to = match (&new_msg, &our_latest_msg_state_root) {
-        (Some(new_msg), Some(state_root)) => &new_msg.state_root == state_root,
+    let latest_is_responded_to = match (&new_msg, &our_latest_msg_response) {
+        (Some(new_msg), Some(response)) => state_roots_match(new_msg, response),
         _ => false,
     };
 
@@ -104,7 +98,7 @@ async fn on_new_state<'a, A: Adapter + 'static>(
 ) -> Result<ApproveStateResult<A::AdapterError>, Box<dyn Error>> {
     let proposed_balances = new_state.balances.clone();
     let proposed_state_root = new_state.state_root.clone();
-    if proposed_state_root != hex::encode(get_state_root_hash(&iface, &proposed_balances)?) {
+    if !is_state_root_valid(&iface, &proposed_balances, &proposed_state_root)? {
         return Ok(on_error(&iface, &new_state, InvalidNewState::RootHash).await);
     }
 
@@ -3,12 +3,12 @@ use std::error::Error;
 use primitives::adapter::{Adapter, AdapterErrorKind};
 use primitives::{
     validator::{Accounting, MessageTypes, NewState},
-    BalancesMap, BigNum,
+    BalancesMap, BigNum, ValidatorId,
 };
 
 use crate::heartbeat::{heartbeat, HeartbeatStatus};
 use crate::sentry_interface::{PropagationResult, SentryApi};
-use crate::{get_state_root_hash, producer};
+use crate::{get_state_root_hash, producer, state_roots_match};
 
 #[derive(Debug)]
 pub struct TickStatus<AE: AdapterErrorKind> {
@@ -26,7 +26,7 @@ pub async fn tick<A: Adapter + 'static>(
     let (balances, new_state) = match &producer_tick {
         producer::TickStatus::Sent { new_accounting, .. } => {
             let new_state = on_new_accounting(&iface, new_accounting).await?;
-            (&new_accounting.balances, Some(new_state))
+            (&new_accounting.balances, new_state)
         }
         producer::TickStatus::NoNewEventAggr(balances) => (balances, None),
         producer::TickStatus::EmptyBalances => (&empty_balances, None),
@@ -39,13 +39,36 @@ pub async fn tick<A: Adapter + 'static>(
     })
 }
 
+/// Signs and propagates a `NewState` for the `new_accounting`.
+///
+/// If our latest `NewState` already has the same state root, it's propagated again
+/// only to the follower and only if it hasn't responded to it (e.g. it missed the `NewState`).
+/// Returns `None` if the follower has already responded to it.
 async fn on_new_accounting<A: Adapter + 'static>(
     iface: &SentryApi<A>,
     new_accounting: &Accounting,
-) -> Result<Vec<PropagationResult<A::AdapterError>>, Box<dyn Error>> {
+) -> Result<Option<Vec<PropagationResult<A::AdapterError>>>, Box<dyn Error>> {
     let state_root_raw = get_state_root_hash(&iface, &new_accounting.balances)?;
     let state_root = hex::encode(state_root_raw);
 
+    if let Some(MessageTypes::NewState(latest_new_state)) =
+        iface.get_our_latest_msg(&["NewState"]).await?
+    {
+        if latest_new_state.state_root == state_root {
+            let follower = iface.channel.spec.validators.follower().id;
+
+            if is_responded_by(&iface, &follower, &latest_new_state).await? {
+                return Ok(None);
+            }
+
+            let propagation_results = iface
+                .propagate_to_validators(&[follower], &[&MessageTypes::NewState(latest_new_state)])
+                .await;
+
+            return Ok(Some(propagation_results));
+        }
+    }
+
     let signature = iface.adapter.sign(&state_root)?;
 
     let exhausted =
@@ -60,5 +83,112 @@ async fn on_new_accounting<A: Adapter + 'static>(
         })])
         .await;
 
-    Ok(propagation_results)
+    Ok(Some(propagation_results))
+}
+
+/// Whether the `validator` has responded with an `ApproveState` or a `RejectState` to the `new_state`
+async fn is_responded_by<A: Adapter + 'static>(
+    iface: &SentryApi<A>,
+    validator: &ValidatorId,
+    new_state: &NewState,
+) -> Result<bool, Box<dyn Error>> {
+    let response = iface
+        .get_latest_msg(validator, &["ApproveState", "RejectState"])
+        .await?;
+
+    Ok(response.map_or(false, |response| state_roots_match(new_state, &response)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_harness::{impressions_aggregate, Harness};
+    use chrono::{TimeZone, Utc};
+    use primitives::util::tests::prep_db::IDS;
+    use primitives::validator::ApproveState;
+
+    #[tokio::test]
+    async fn new_state_is_produced_only_when_the_state_root_changes() {
+        let harness = Harness::setup(vec![impressions_aggregate(Utc::now())]).await;
+        let leader_id = IDS["leader"];
+        let follower_id = IDS["follower"];
+
+        // resets the Accounting, which makes the producer merge the same aggregates again
+        let reset_accounting = || {
+            harness.leader_sentry.insert(
+                leader_id,
+                MessageTypes::Accounting(Accounting {
+                    last_event_aggregate: Utc.timestamp(0, 0),
+                    balances_before_fees: Default::default(),
+                    balances: Default::default(),
+                }),
+            )
+        };
+
+        let first_tick = tick(&harness.leader)
+            .await
+            .expect("Leader tick should succeed");
+        assert!(
+            first_tick.new_state.is_some(),
+            "A NewState should be produced for the new Accounting"
+        );
+        let state_root = match harness.leader_sentry.received(&leader_id, "NewState").pop() {
+            Some(MessageTypes::NewState(new_state)) => new_state.state_root,
+            _ => panic!("The leader should have propagated a NewState"),
+        };
+
+        reset_accounting();
+        let second_tick = tick(&harness.leader)
+            .await
+            .expect("Leader tick should succeed");
+        assert!(matches!(
+            second_tick.producer_tick,
+            producer::TickStatus::Sent { .. }
+        ));
+        // the follower hasn't responded to the NewState, so it's propagated again only to it
+        let propagated_to = second_tick
+            .new_state
+            .expect("The same NewState should be propagated again")
+            .into_iter()
+            .map(|result| result.expect("Should propagate the NewState"))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![follower_id], propagated_to);
+        assert_eq!(
+            1,
+            harness.leader_sentry.received(&leader_id, "NewState").len()
+        );
+        assert_eq!(
+            2,
+            harness
+                .follower_sentry
+                .received(&leader_id, "NewState")
+                .len()
+        );
+
+        harness.leader_sentry.insert(
+            follower_id,
+            MessageTypes::ApproveState(ApproveState {
+                state_root,
+                signature: "signature".to_string(),
+                is_healthy: true,
+                exhausted: false,
+            }),
+        );
+
+        reset_accounting();
+        let third_tick = tick(&harness.leader)
+            .await
+            .expect("Leader tick should succeed");
+        assert!(
+            third_tick.new_state.is_none(),
+            "A NewState with the same state root should not be produced once the follower has responded"
+        );
+        assert_eq!(
+            2,
+            harness
+                .follower_sentry
+                .received(&leader_id, "NewState")
+                .len()
+        );
+    }
 }
@@ -2,13 +2,19 @@
 #![deny(clippy::all)]
 
 use std::error::Error;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
 
-use adapter::{get_balance_leaf, get_signable_state_root};
 use primitives::adapter::Adapter;
-use primitives::merkle_tree::MerkleTree;
-use primitives::BalancesMap;
+use primitives::merkle_tree::state_root;
+use primitives::validator::{MessageTypes, NewState};
+use primitives::{BalancesMap, Channel, ChannelId, Config};
+use slog::{info, Logger};
+use tokio::time::timeout;
 
-pub use self::sentry_interface::{all_channels, SentryApi};
+use self::error::{Error as ValidatorWorkerError, Role};
+pub use self::sentry_interface::{all_channels, FailedPropagations, SentryApi};
 
 pub mod error;
 pub mod follower;
@@ -16,6 +22,8 @@ pub mod heartbeat;
 pub mod leader;
 pub mod producer;
 pub mod sentry_interface;
+#[cfg(test)]
+mod test_harness;
 
 pub mod core {
     pub mod events;
@@ -23,19 +31,88 @@ pub mod core {
     pub mod follower_rules;
 }
 
+/// Runs the leader or the follower tick of the `channel`, depending on our role in it,
+/// within the `validator_tick_timeout` of the `config`.
+/// The messages which have failed to propagate on the previous ticks are propagated first.
+pub async fn validator_tick<A: Adapter + 'static>(
+    adapter: Arc<A>,
+    channel: Channel,
+    config: &Config,
+    failed_propagations: &FailedPropagations,
+    logger: &Logger,
+) -> Result<(ChannelId, Box<dyn Debug>), ValidatorWorkerError<A::AdapterError>> {
+    let whoami = *adapter.whoami();
+
+    // Cloning the `Logger` is cheap, see documentation for more info
+    let sentry = SentryApi::init(adapter, channel.clone(), &config, logger.clone())?
+        .with_failed_propagations(failed_propagations.clone());
+    // the messages which fail again are kept for the next tick
+    sentry.propagate_failed().await;
+
+    let duration = Duration::from_millis(config.validator_tick_timeout as u64);
+
+    match channel.spec.validators.find_with_role(&whoami) {
+        Some((Role::Leader, _)) => match timeout(duration, leader::tick(&sentry)).await {
+            Err(_) => Err(ValidatorWorkerError::Timeout {
+                channel: channel.id,
+                role: Role::Leader,
+            }),
+            Ok(Err(source)) => Err(ValidatorWorkerError::LeaderTick {
+                channel: channel.id,
+                source,
+            }),
+            Ok(Ok(tick_status)) => {
+                info!(&logger, "Leader tick"; "status" => ?tick_status);
+                Ok((channel.id, Box::new(tick_status)))
+            }
+        },
+        Some((Role::Follower, _)) => match timeout(duration, follower::tick(&sentry)).await {
+            Err(_) => Err(ValidatorWorkerError::Timeout {
+                channel: channel.id,
+                role: Role::Follower,
+            }),
+            Ok(Err(source)) => Err(ValidatorWorkerError::FollowerTick {
+                channel: channel.id,
+                source,
+            }),
+            Ok(Ok(tick_status)) => {
+                info!(&logger, "Follower tick"; "status" => ?tick_status);
+                Ok((channel.id, Box::new(tick_status)))
+            }
+        },
+        // @TODO: Can we make this so that we don't have this check at all? maybe something with the SentryApi struct?
+        None => unreachable!("SentryApi makes a check if validator is in Channel spec on `init()`"),
+    }
+}
+
 pub(crate) fn get_state_root_hash<A: Adapter + 'static>(
     iface: &SentryApi<A>,
     balances: &BalancesMap,
 ) -> Result<[u8; 32], Box<dyn Error>> {
-    // Note: MerkleTree takes care of deduplicating and sorting
-    let elems: Vec<[u8; 32]> = balances
-        .iter()
-        .map(|(acc, amount)| get_balance_leaf(acc, amount))
-        .collect::<Result<_, _>>()?;
-
-    let tree = MerkleTree::new(&elems)?;
-    // keccak256(channelId, balanceRoot
-    get_signable_state_root(iface.channel.id.as_ref(), &tree.root())
+    Ok(state_root(&iface.channel.id, balances)?)
+}
+
+/// Checks whether the `response`, an `ApproveState` or a `RejectState`,
+/// is for the state root of the given `NewState`
+pub fn state_roots_match(new_state: &NewState, response: &MessageTypes) -> bool {
+    match response {
+        MessageTypes::ApproveState(approve_state) => {
+            new_state.state_root == approve_state.state_root
+        }
+        MessageTypes::RejectState(reject_state) => new_state.state_root == reject_state.state_root,
+        _ => false,
+    }
+}
+
+/// Recomputes the state root of the `balances` and compares it to the hex encoded `state_root`
+pub(crate) fn is_state_root_valid<A: Adapter + 'static>(
+    iface: &SentryApi<A>,
+    balances: &BalancesMap,
+    state_root: &str,
+) -> Result<bool, Box<dyn Error>> {
+    let computed = get_state_root_hash(iface, balances)?;
+
+    Ok(hex::encode(computed) == state_root)
 }
 
 #[cfg(test)]
@@ -45,9 +122,14 @@ mod test {
     use adapter::DummyAdapter;
     use primitives::adapter::DummyAdapterOptions;
     use primitives::config::configuration;
-    use primitives::util::tests::prep_db::{AUTH, DUMMY_CHANNEL, IDS};
-    use primitives::{BalancesMap, Channel};
-    use slog::{o, Discard, Logger};
+    use primitives::util::tests::prep_db::{
+        AUTH, DUMMY_CHANNEL, DUMMY_VALIDATOR_FOLLOWER, DUMMY_VALIDATOR_LEADER, IDS,
+    };
+    use primitives::validator::{ApproveState, RejectState};
+    use primitives::{BalancesMap, Channel, SpecValidators, ValidatorId};
+    use slog::{o, Discard};
+    use std::net::TcpListener;
+    use wiremock::{matchers::method, Mock, MockServer, ResponseTemplate};
 
     fn setup_iface(channel: &Channel) -> SentryApi<DummyAdapter> {
         let adapter_options = DummyAdapterOptions {
@@ -59,7 +141,8 @@ mod test {
         let dummy_adapter = DummyAdapter::init(adapter_options, &config);
         let logger = Logger::root(Discard, o!());
 
-        SentryApi::init(dummy_adapter, channel.clone(), &config, logger).expect("should succeed")
+        SentryApi::init(Arc::new(dummy_adapter), channel.clone(), &config, logger)
+            .expect("should succeed")
     }
 
     #[test]
@@ -84,6 +167,101 @@ mod test {
         );
     }
 
+    #[test]
+    fn state_roots_match_compares_new_state_and_the_response() {
+        let new_state = NewState {
+            state_root: "d6c784be61c4d2c47a52cc72af6c133d24b163ad053ac7f0a65091001f43dda1"
+                .to_string(),
+            signature: "signature".to_string(),
+            balances: BalancesMap::default(),
+            exhausted: false,
+        };
+        let approve_state = ApproveState {
+            state_root: new_state.state_root.clone(),
+            signature: "signature".to_string(),
+            is_healthy: true,
+            exhausted: false,
+        };
+
+        let reject_state = RejectState {
+            reason: "InvalidRootHash".to_string(),
+            state_root: new_state.state_root.clone(),
+            signature: "signature".to_string(),
+            balances: None,
+            timestamp: None,
+        };
+
+        assert!(state_roots_match(
+            &new_state,
+            &MessageTypes::ApproveState(approve_state.clone())
+        ));
+        assert!(state_roots_match(
+            &new_state,
+            &MessageTypes::RejectState(reject_state.clone())
+        ));
+
+        let mismatched_root =
+            "4fad5375c3ef5f8a9d23a8276fed0151164dea72a5891cec8b43e1d190ed430e".to_string();
+        let mismatched = ApproveState {
+            state_root: mismatched_root.clone(),
+            ..approve_state
+        };
+        assert!(!state_roots_match(
+            &new_state,
+            &MessageTypes::ApproveState(mismatched)
+        ));
+        let mismatched = RejectState {
+            state_root: mismatched_root,
+            ..reject_state
+        };
+        assert!(!state_roots_match(
+            &new_state,
+            &MessageTypes::RejectState(mismatched)
+        ));
+        // only the responses to a NewState are matched
+        assert!(!state_roots_match(
+            &new_state,
+            &MessageTypes::NewState(new_state.clone())
+        ));
+    }
+
+    #[test]
+    fn is_state_root_valid_recomputes_the_state_root_of_balances() {
+        let channel = DUMMY_CHANNEL.clone();
+
+        let iface = setup_iface(&channel);
+
+        let balances: BalancesMap = vec![
+            (IDS["publisher"].clone(), 1.into()),
+            (IDS["tester"].clone(), 2.into()),
+        ]
+        .into_iter()
+        .collect();
+
+        let is_valid = is_state_root_valid(
+            &iface,
+            &balances,
+            "d6c784be61c4d2c47a52cc72af6c133d24b163ad053ac7f0a65091001f43dda1",
+        )
+        .expect("should compute state root");
+        assert!(is_valid);
+
+        let tampered_balances: BalancesMap = vec![
+            (IDS["publisher"].clone(), 1.into()),
+            (IDS["tester"].clone(), 3.into()),
+        ]
+        .into_iter()
+        .collect();
+
+        let is_valid = is_state_root_valid(
+            &iface,
+            &tampered_balances,
+            "d6c784be61c4d2c47a52cc72af6c133d24b163ad053ac7f0a65091001f43dda1",
+        )
+        .expect("should compute state root");
+        assert!(!is_valid);
+    }
+
     #[test]
     fn get_state_root_hash_returns_correct_hash_for_fake_channel_aligning_with_js_impl() {
         let channel = DUMMY_CHANNEL.clone();
@@ -102,4 +280,123 @@ mod test {
             hex::encode(actual_hash)
         );
     }
+
+    fn dummy_adapter(identity: ValidatorId) -> Arc<DummyAdapter> {
+        let adapter_options = DummyAdapterOptions {
+            dummy_identity: identity,
+            dummy_auth: IDS.clone(),
+            dummy_auth_tokens: AUTH.clone(),
+        };
+        let config = configuration("development", None).expect("Dev config should be available");
+
+        Arc::new(DummyAdapter::init(adapter_options, &config))
+    }
+
+    #[tokio::test]
+    async fn validator_tick_returns_the_error_of_the_failed_step() {
+        let config = configuration("development", None).expect("Dev config should be available");
+        let logger = Logger::root(Discard, o!());
+
+        // bind and drop a listener to get a port that nobody listens on,
+        // so all the requests to the sentries fail
+        let closed_port = {
+            let listener = TcpListener::bind("127.0.0.1:0").expect("Should bind");
+            listener.local_addr().expect("Should have address").port()
+        };
+        let mut leader = DUMMY_VALIDATOR_LEADER.clone();
+        leader.url = format!("http://127.0.0.1:{}", closed_port);
+        let mut follower = DUMMY_VALIDATOR_FOLLOWER.clone();
+        follower.url = format!("http://127.0.0.1:{}", closed_port);
+        let mut channel = DUMMY_CHANNEL.clone();
+        channel.spec.validators = SpecValidators::new(leader, follower);
+
+        let leader_error = validator_tick(
+            dummy_adapter(IDS["leader"]),
+            channel.clone(),
+            &config,
+            &Default::default(),
+            &logger,
+        )
+        .await
+        .expect_err("The leader tick should fail");
+        assert!(
+            matches!(leader_error, ValidatorWorkerError::LeaderTick { channel: id, .. } if id == channel.id)
+        );
+
+        let follower_error = validator_tick(
+            dummy_adapter(IDS["follower"]),
+            channel.clone(),
+            &config,
+            &Default::default(),
+            &logger,
+        )
+        .await
+        .expect_err("The follower tick should fail");
+        assert!(
+            matches!(follower_error, ValidatorWorkerError::FollowerTick { channel: id, .. } if id == channel.id)
+        );
+
+        let init_error = validator_tick(
+            dummy_adapter(IDS["tester"]),
+            channel,
+            &config,
+            &Default::default(),
+            &logger,
+        )
+        .await
+        .expect_err("We are not a validator of the channel");
+        assert!(matches!(
+            init_error,
+            ValidatorWorkerError::SentryInit(
+                sentry_interface::Error::MissingWhoamiInChannelValidators { .. },
+            )
+        ));
+    }
+
+    #[tokio::test]
+    async fn slow_validator_tick_times_out() {
+        let mut config =
+            configuration("development", None).expect("Dev config should be available");
+        config.validator_tick_timeout = 100;
+        let logger = Logger::root(Discard, o!());
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+            .mount(&server)
+            .await;
+
+        let mut leader = DUMMY_VALIDATOR_LEADER.clone();
+        leader.url = server.uri();
+        let mut follower = DUMMY_VALIDATOR_FOLLOWER.clone();
+        follower.url = server.uri();
+        let mut channel = DUMMY_CHANNEL.clone();
+        channel.spec.validators = SpecValidators::new(leader, follower);
+
+        for (identity, expected_role) in &[
+            (IDS["leader"], Role::Leader),
+            (IDS["follower"], Role::Follower),
+        ] {
+            let error = validator_tick(
+                dummy_adapter(*identity),
+                channel.clone(),
+                &config,
+                &Default::default(),
+                &logger,
+            )
+            .await
+            .expect_err("The tick should time out");
+
+            match error {
+                ValidatorWorkerError::Timeout {
+                    channel: channel_id,
+                    role,
+                } => {
+                    assert_eq!(channel.id, channel_id);
+                    assert_eq!(*expected_role, role);
+                }
+                other => panic!("Expected a Timeout, got: {:?}", other),
+            }
+        }
+    }
 }
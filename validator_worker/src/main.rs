@@ -3,32 +3,118 @@
 
 use std::convert::TryFrom;
 use std::error::Error;
+use std::sync::Arc;
 use std::time::Duration;
 
-use clap::{crate_version, App, Arg};
+use chrono::{TimeZone, Utc};
+use clap::{crate_version, App, Arg, ArgMatches, SubCommand};
 use futures::future::{join, join_all};
+use futures::{Stream, StreamExt};
+use hex::FromHex;
 use tokio::runtime::Runtime;
-use tokio::time::{delay_for, timeout};
+use tokio::time::delay_for;
 
-use adapter::{AdapterTypes, DummyAdapter, EthereumAdapter};
+use adapter::ethereum::{ewt_verify, VerifyPayload};
+use adapter::{AdapterTypes, DummyAdapter, EthereumAdapter, EthereumChannel};
 use primitives::adapter::{Adapter, DummyAdapterOptions, KeystoreOptions};
-use primitives::config::{configuration, Config};
+use primitives::config::{configuration, AdapterConfig, Config};
+use primitives::util::logging::LogLevel;
 use primitives::util::tests::prep_db::{AUTH, IDS};
-use primitives::{Channel, ChannelId, SpecValidator, ValidatorId};
-use slog::{error, info, Logger};
-use std::fmt::Debug;
-use validator_worker::error::{Error as ValidatorWorkerError, TickError};
-use validator_worker::{all_channels, follower, leader, SentryApi};
+use primitives::{ChannelId, ValidatorId};
+use slog::{error, info, Level, Logger};
+use validator_worker::{all_channels, validator_tick, FailedPropagations};
 
 #[derive(Debug, Clone)]
 struct Args<A: Adapter> {
     sentry_url: String,
     config: Config,
-    adapter: A,
+    adapter: Arc<A>,
+    /// Shared between the iterations, so the failed propagations are retried on the next tick
+    failed_propagations: FailedPropagations,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let cli = App::new("Validator worker")
+    let cli = app().get_matches();
+
+    let environment = std::env::var("ENV").unwrap_or_else(|_| "development".into());
+    let config_file = cli.value_of("config");
+    let config = configuration(&environment, config_file).expect("failed to parse configuration");
+
+    if let Some(channel_id_matches) = cli.subcommand_matches("channel-id") {
+        println!("{}", channel_id(channel_id_matches, &config)?);
+
+        return Ok(());
+    }
+
+    // verifying a token doesn't need the adapter, so it runs without a keystore
+    if let Some(("verify", Some(verify_matches))) =
+        cli.subcommand_matches("ewt").map(ArgMatches::subcommand)
+    {
+        println!("{}", ewt_verify_output(verify_matches)?);
+
+        return Ok(());
+    }
+
+    let sentry_url = cli.value_of("sentryUrl").expect("sentry url missing");
+    let is_single_tick = cli.is_present("singleTick");
+
+    let log_level = LogLevel::new(Level::Info);
+    let logger = logger(log_format(&cli)?, &log_level);
+
+    let adapter = match adapter_config(&cli, &config)? {
+        AdapterConfig::Ethereum { keystore_file } => {
+            let keystore_pwd = std::env::var("KEYSTORE_PWD").expect("unable to get keystore pwd");
+            let keystore_options = KeystoreOptions {
+                keystore_file,
+                keystore_pwd,
+            };
+            AdapterTypes::EthereumAdapter(Box::new(
+                EthereumAdapter::init(keystore_options, &config, logger.clone())
+                    .expect("failed to init adapter"),
+            ))
+        }
+        AdapterConfig::Dummy { dummy_identity } => {
+            let options = DummyAdapterOptions {
+                dummy_identity,
+                dummy_auth: IDS.clone(),
+                dummy_auth_tokens: AUTH.clone(),
+            };
+            AdapterTypes::DummyAdapter(Box::new(DummyAdapter::init(options, &config)))
+        }
+    };
+
+    if let Some(ewt_matches) = cli.subcommand_matches("ewt") {
+        let output = match adapter {
+            AdapterTypes::EthereumAdapter(ethadapter) => ewt_sign(*ethadapter, ewt_matches)?,
+            AdapterTypes::DummyAdapter(dummyadapter) => ewt_sign(*dummyadapter, ewt_matches)?,
+        };
+        println!("{}", output);
+
+        return Ok(());
+    }
+
+    match adapter {
+        AdapterTypes::EthereumAdapter(ethadapter) => run(
+            is_single_tick,
+            &sentry_url,
+            &config,
+            *ethadapter,
+            &logger,
+            &log_level,
+        ),
+        AdapterTypes::DummyAdapter(dummyadapter) => run(
+            is_single_tick,
+            &sentry_url,
+            &config,
+            *dummyadapter,
+            &logger,
+            &log_level,
+        ),
+    }
+}
+
+fn app() -> App<'static, 'static> {
+    App::new("Validator worker")
         .version(crate_version!())
         .arg(
             Arg::with_name("config")
@@ -39,9 +125,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             Arg::with_name("adapter")
                 .long("adapter")
                 .short("a")
-                .help("the adapter for authentication and signing")
-                .required(true)
-                .default_value("ethereum")
+                .help("the adapter for authentication and signing, overrides the adapter of the config")
                 .possible_values(&["ethereum", "dummy"])
                 .takes_value(true),
         )
@@ -75,52 +159,207 @@ fn main() -> Result<(), Box<dyn Error>> {
                 .takes_value(false)
                 .help("runs the validator in single-tick mode and exit"),
         )
-        .get_matches();
+        .arg(
+            Arg::with_name("logFormat")
+                .long("log-format")
+                .help("the format of the logs, json is meant for log aggregators")
+                .default_value("compact")
+                .possible_values(&["compact", "json"])
+                .takes_value(true),
+        )
+        .subcommand(channel_id_subcommand())
+        .subcommand(ewt_subcommand())
+}
 
-    let environment = std::env::var("ENV").unwrap_or_else(|_| "development".into());
-    let config_file = cli.value_of("config");
-    let config = configuration(&environment, config_file).expect("failed to parse configuration");
-    let sentry_url = cli.value_of("sentryUrl").expect("sentry url missing");
-    let is_single_tick = cli.is_present("singleTick");
+/// Resolves the adapter from the `--adapter` CLI flag and its options, falling back to the `adapter` of the config.
+/// When neither of them specify it, the Ethereum adapter is used.
+fn adapter_config(cli: &ArgMatches<'_>, config: &Config) -> Result<AdapterConfig, Box<dyn Error>> {
+    let adapter = cli
+        .value_of("adapter")
+        .or_else(|| {
+            config.adapter.as_ref().map(|adapter| match adapter {
+                AdapterConfig::Ethereum { .. } => "ethereum",
+                AdapterConfig::Dummy { .. } => "dummy",
+            })
+        })
+        .unwrap_or("ethereum");
 
-    let adapter = match cli.value_of("adapter").unwrap() {
+    match adapter {
         "ethereum" => {
-            let keystore_file = cli
-                .value_of("keystoreFile")
-                .expect("unable to get keystore file");
-            let keystore_pwd = std::env::var("KEYSTORE_PWD").expect("unable to get keystore pwd");
-            let keystore_options = KeystoreOptions {
-                keystore_file: keystore_file.to_string(),
-                keystore_pwd,
+            let keystore_file = match (cli.value_of("keystoreFile"), &config.adapter) {
+                (Some(keystore_file), _) => keystore_file.to_string(),
+                (None, Some(AdapterConfig::Ethereum { keystore_file })) => keystore_file.clone(),
+                _ => return Err("unable to get keystore file".into()),
             };
-            AdapterTypes::EthereumAdapter(Box::new(
-                EthereumAdapter::init(keystore_options, &config).expect("failed to init adapter"),
-            ))
+
+            Ok(AdapterConfig::Ethereum { keystore_file })
         }
         "dummy" => {
-            let dummy_identity = cli
-                .value_of("dummyIdentity")
-                .expect("unable to get dummyIdentity");
-            let options = DummyAdapterOptions {
-                dummy_identity: ValidatorId::try_from(dummy_identity)?,
-                dummy_auth: IDS.clone(),
-                dummy_auth_tokens: AUTH.clone(),
+            let dummy_identity = match (cli.value_of("dummyIdentity"), &config.adapter) {
+                (Some(dummy_identity), _) => ValidatorId::try_from(dummy_identity)?,
+                (None, Some(AdapterConfig::Dummy { dummy_identity })) => *dummy_identity,
+                _ => return Err("unable to get dummyIdentity".into()),
             };
-            AdapterTypes::DummyAdapter(Box::new(DummyAdapter::init(options, &config)))
+
+            Ok(AdapterConfig::Dummy { dummy_identity })
         }
         // @TODO exit gracefully
         _ => panic!("We don't have any other adapters implemented yet!"),
+    }
+}
+
+fn channel_id_subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("channel-id")
+        .about("prints the id of the channel computed from its on-chain parameters")
+        .arg(
+            Arg::with_name("creator")
+                .long("creator")
+                .help("the address of the channel creator")
+                .required(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("tokenAddr")
+                .long("tokenAddr")
+                .help("the address of the deposit token")
+                .required(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("tokenAmount")
+                .long("tokenAmount")
+                .help("the deposit amount")
+                .required(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("validUntil")
+                .long("validUntil")
+                .help("the UNIX timestamp (in seconds) until which the channel is valid")
+                .required(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("validators")
+                .long("validators")
+                .help("the addresses of the leader and the follower")
+                .required(true)
+                .takes_value(true)
+                .number_of_values(2),
+        )
+        .arg(
+            Arg::with_name("spec")
+                .long("spec")
+                .help("the hex encoded 32 bytes hash of the channel spec")
+                .required(true)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("coreAddress")
+                .long("coreAddress")
+                .help("the AdExCore contract address, defaults to the one in the config")
+                .takes_value(true),
+        )
+}
+
+/// Computes the `ChannelId` from the arguments of the `channel-id` subcommand
+fn channel_id(matches: &ArgMatches<'_>, config: &Config) -> Result<ChannelId, Box<dyn Error>> {
+    fn from_hex<T: FromHex>(value: &str) -> Result<T, T::Error> {
+        T::from_hex(value.trim_start_matches("0x"))
+    }
+
+    let value = |name: &str| {
+        matches
+            .value_of(name)
+            .ok_or_else(|| format!("{} is required", name))
     };
 
-    let logger = logger();
+    let creator: [u8; 20] = from_hex(value("creator")?)?;
+    let token_addr: [u8; 20] = from_hex(value("tokenAddr")?)?;
+    let valid_until = Utc.timestamp(value("validUntil")?.parse()?, 0);
+    let validators = matches
+        .values_of("validators")
+        .ok_or("validators are required")?
+        .map(ValidatorId::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+    let spec: [u8; 32] = from_hex(value("spec")?)?;
+    let core_address = match matches.value_of("coreAddress") {
+        Some(core_address) => from_hex(core_address)?,
+        None => config.ethereum_core_address,
+    };
 
-    match adapter {
-        AdapterTypes::EthereumAdapter(ethadapter) => {
-            run(is_single_tick, &sentry_url, &config, *ethadapter, &logger)
+    let eth_channel = EthereumChannel::new(
+        &creator,
+        &token_addr,
+        value("tokenAmount")?,
+        valid_until,
+        &validators.iter().collect::<Vec<_>>(),
+        &spec,
+    )?;
+
+    Ok(ChannelId::from(eth_channel.hash(&core_address)))
+}
+
+fn ewt_subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("ewt")
+        .about("generates or verifies an Ethereum Web Token for debugging authentication")
+        .subcommand(
+            SubCommand::with_name("sign")
+                .about("generates a token with the adapter for the given validator")
+                .arg(
+                    Arg::with_name("validator")
+                        .help("the id of the validator the token is for")
+                        .required(true)
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about("verifies a token and prints the signer and the payload")
+                .arg(
+                    Arg::with_name("token")
+                        .help("the token to verify")
+                        .required(true)
+                        .takes_value(true),
+                ),
+        )
+}
+
+/// Runs the `ewt sign` subcommand and returns the signed token,
+/// `ewt verify` is handled by [`ewt_verify_output`] before the adapter is initialized
+fn ewt_sign<A: Adapter + 'static>(
+    mut adapter: A,
+    matches: &ArgMatches<'_>,
+) -> Result<String, Box<dyn Error>> {
+    match matches.subcommand() {
+        ("sign", Some(sign_matches)) => {
+            let validator = sign_matches
+                .value_of("validator")
+                .ok_or("validator is required")?;
+            let validator = ValidatorId::try_from(validator)?;
+
+            adapter.unlock()?;
+
+            Ok(adapter.get_auth(&validator)?)
         }
-        AdapterTypes::DummyAdapter(dummyadapter) => {
-            run(is_single_tick, &sentry_url, &config, *dummyadapter, &logger)
+        _ => Err("use either `ewt sign` or `ewt verify`".into()),
+    }
+}
+
+/// Runs the `ewt verify` subcommand and returns the signer and the payload as JSON
+fn ewt_verify_output(matches: &ArgMatches<'_>) -> Result<String, Box<dyn Error>> {
+    let token = matches.value_of("token").ok_or("token is required")?;
+
+    Ok(serde_json::to_string_pretty(&ewt_verify_token(token)?)?)
+}
+
+fn ewt_verify_token(token: &str) -> Result<VerifyPayload, Box<dyn Error>> {
+    match token.split('.').collect::<Vec<_>>().as_slice() {
+        [header_encoded, payload_encoded, token_encoded] => {
+            ewt_verify(header_encoded, payload_encoded, token_encoded)
+                .map_err(|err| err.to_string().into())
         }
+        _ => Err("the token should have a header, a payload and a signature".into()),
     }
 }
 
@@ -130,6 +369,7 @@ fn run<A: Adapter + 'static>(
     config: &Config,
     mut adapter: A,
     logger: &Logger,
+    log_level: &LogLevel,
 ) -> Result<(), Box<dyn Error>> {
     // unlock adapter
     adapter.unlock()?;
@@ -137,7 +377,9 @@ fn run<A: Adapter + 'static>(
     let args = Args {
         sentry_url: sentry_url.to_owned(),
         config: config.to_owned(),
-        adapter,
+        // the unlocked adapter is shared between all channel ticks
+        adapter: Arc::new(adapter),
+        failed_propagations: Default::default(),
     };
 
     // Create the runtime
@@ -146,6 +388,19 @@ fn run<A: Adapter + 'static>(
     if is_single_tick {
         rt.block_on(iterate_channels(args, &logger));
     } else {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+
+            let (log_level, logger) = (log_level.clone(), logger.clone());
+            rt.spawn(async move {
+                match signal(SignalKind::hangup()) {
+                    Ok(hangups) => cycle_log_level(hangups, log_level, &logger).await,
+                    Err(err) => error!(&logger, "Unable to listen for SIGHUP"; "error" => ?err),
+                }
+            });
+        }
+
         rt.block_on(infinite(args, &logger));
     }
 
@@ -155,11 +410,16 @@ fn run<A: Adapter + 'static>(
 async fn infinite<A: Adapter + 'static>(args: Args<A>, logger: &Logger) {
     loop {
         let arg = args.clone();
-        let delay_future = delay_for(Duration::from_millis(arg.config.wait_time as u64));
+        let delay_future = delay_for(wait_time(&arg.config));
         let _result = join(iterate_channels(arg, logger), delay_future).await;
     }
 }
 
+/// The minimum time between the starts of two consecutive iterations over the channels
+fn wait_time(config: &Config) -> Duration {
+    Duration::from_millis(config.wait_time.into())
+}
+
 async fn iterate_channels<A: Adapter + 'static>(args: Args<A>, logger: &Logger) {
     let result = all_channels(&args.sentry_url, args.adapter.whoami()).await;
 
@@ -173,11 +433,15 @@ async fn iterate_channels<A: Adapter + 'static>(args: Args<A>, logger: &Logger)
 
     let channels_size = channels.len();
 
-    let tick_results = join_all(
-        channels
-            .into_iter()
-            .map(|channel| validator_tick(args.adapter.clone(), channel, &args.config, logger)),
-    )
+    let tick_results = join_all(channels.into_iter().map(|channel| {
+        validator_tick(
+            Arc::clone(&args.adapter),
+            channel,
+            &args.config,
+            &args.failed_propagations,
+            logger,
+        )
+    }))
     .await;
 
     for channel_err in tick_results.into_iter().filter_map(Result::err) {
@@ -191,62 +455,272 @@ async fn iterate_channels<A: Adapter + 'static>(args: Args<A>, logger: &Logger)
     }
 }
 
-async fn validator_tick<A: Adapter + 'static>(
-    adapter: A,
-    channel: Channel,
-    config: &Config,
-    logger: &Logger,
-) -> Result<(ChannelId, Box<dyn Debug>), ValidatorWorkerError<A::AdapterError>> {
-    let whoami = *adapter.whoami();
-
-    // Cloning the `Logger` is cheap, see documentation for more info
-    let sentry = SentryApi::init(adapter, channel.clone(), &config, logger.clone())
-        .map_err(ValidatorWorkerError::SentryApi)?;
-    let duration = Duration::from_millis(config.validator_tick_timeout as u64);
-
-    match channel.spec.validators.find(&whoami) {
-        Some(SpecValidator::Leader(_)) => match timeout(duration, leader::tick(&sentry)).await {
-            Err(timeout_e) => Err(ValidatorWorkerError::LeaderTick(
-                channel.id,
-                TickError::TimedOut(timeout_e),
-            )),
-            Ok(Err(tick_e)) => Err(ValidatorWorkerError::LeaderTick(
-                channel.id,
-                TickError::Tick(tick_e),
-            )),
-            Ok(Ok(tick_status)) => {
-                info!(&logger, "Leader tick"; "status" => ?tick_status);
-                Ok((channel.id, Box::new(tick_status)))
-            }
-        },
-        Some(SpecValidator::Follower(_)) => {
-            match timeout(duration, follower::tick(&sentry)).await {
-                Err(timeout_e) => Err(ValidatorWorkerError::FollowerTick(
-                    channel.id,
-                    TickError::TimedOut(timeout_e),
-                )),
-                Ok(Err(tick_e)) => Err(ValidatorWorkerError::FollowerTick(
-                    channel.id,
-                    TickError::Tick(tick_e),
-                )),
-                Ok(Ok(tick_status)) => {
-                    info!(&logger, "Follower tick"; "status" => ?tick_status);
-                    Ok((channel.id, Box::new(tick_status)))
-                }
-            }
-        }
-        // @TODO: Can we make this so that we don't have this check at all? maybe something with the SentryApi struct?
-        None => unreachable!("SentryApi makes a check if validator is in Channel spec on `init()`"),
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LogFormat {
+    Compact,
+    Json,
+}
+
+fn log_format(cli: &ArgMatches<'_>) -> Result<LogFormat, Box<dyn Error>> {
+    match cli.value_of("logFormat") {
+        None | Some("compact") => Ok(LogFormat::Compact),
+        Some("json") => Ok(LogFormat::Json),
+        Some(other) => Err(format!("unknown log format {}", other).into()),
+    }
+}
+
+/// Switches to the next more verbose log level on every signal, e.g. `SIGHUP`
+async fn cycle_log_level(signals: impl Stream<Item = ()>, log_level: LogLevel, logger: &Logger) {
+    futures::pin_mut!(signals);
+
+    while signals.next().await.is_some() {
+        let level = log_level.cycle();
+        info!(logger, "Log level changed to {}", level.as_str());
     }
 }
 
-fn logger() -> Logger {
-    use primitives::util::logging::{Async, PrefixedCompactFormat, TermDecorator};
+fn logger(format: LogFormat, log_level: &LogLevel) -> Logger {
+    use primitives::util::logging::{
+        Async, Json, LogLevelFilter, PrefixedCompactFormat, TermDecorator,
+    };
     use slog::{o, Drain};
 
-    let decorator = TermDecorator::new().build();
-    let drain = PrefixedCompactFormat::new("validator_worker", decorator).fuse();
-    let drain = Async::new(drain).build().fuse();
+    match format {
+        LogFormat::Compact => {
+            let decorator = TermDecorator::new().build();
+            let drain = PrefixedCompactFormat::new("validator_worker", decorator).fuse();
+            let drain = LogLevelFilter::new(drain, log_level.clone());
+            let drain = Async::new(drain).build().fuse();
+
+            Logger::root(drain, o!())
+        }
+        LogFormat::Json => {
+            let drain = Json::new(std::io::stdout())
+                .add_default_keys()
+                .add_key_value(o!("prefix" => "validator_worker"))
+                .build()
+                .fuse();
+            let drain = LogLevelFilter::new(drain, log_level.clone());
+            let drain = Async::new(drain).build().fuse();
+
+            Logger::root(drain, o!())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use primitives::util::tests::discard_logger;
+    use primitives::ToETHChecksum;
+
+    #[test]
+    fn channel_id_is_computed_from_the_arguments() {
+        let config = configuration("development", None).expect("Dev config should be available");
+        let matches = channel_id_subcommand().get_matches_from(vec![
+            "channel-id",
+            "--creator",
+            "0x033ed90e0fec3f3ea1c9b005c724d704501e0196",
+            "--tokenAddr",
+            "0x89d24A6b4CcB1B6fAA2625fE562bDD9a23260359",
+            "--tokenAmount",
+            "1000",
+            "--validUntil",
+            "4102444800",
+            "--validators",
+            "0xce07CbB7e054514D590a0262C93070D838bFBA2e",
+            "0xc91763d7f14ac5c5ddfbcd012e0d2a61ab9bded3",
+            "--spec",
+            "0x0101010101010101010101010101010101010101010101010101010101010101",
+        ]);
+
+        let channel_id = channel_id(&matches, &config).expect("Should compute the ChannelId");
+
+        assert_eq!(
+            "0xb65f57b1a4cb0c2ee724b0e4b8a6956c8c99539a697a47eaf9f6f8b3b09cd331",
+            channel_id.to_string()
+        );
+    }
+
+    #[test]
+    fn ewt_round_trips_through_sign_and_verify() {
+        let config = configuration("development", None).expect("Dev config should be available");
+        let keystore_options = KeystoreOptions {
+            keystore_file: "../adapter/test/resources/keystore.json".to_string(),
+            keystore_pwd: "adexvalidator".to_string(),
+        };
+        let adapter = EthereumAdapter::init(keystore_options, &config, discard_logger())
+            .expect("Should init the adapter");
+        let whoami = *adapter.whoami();
+        let validator = IDS["follower"];
+
+        let sign_matches = ewt_subcommand().get_matches_from(vec![
+            "ewt".to_string(),
+            "sign".to_string(),
+            validator.to_checksum(),
+        ]);
+        let token = ewt_sign(adapter, &sign_matches).expect("Should sign a token");
+
+        // verifying doesn't need an adapter nor a keystore
+        let verify_matches =
+            app().get_matches_from(vec!["validator_worker", "ewt", "verify", token.as_str()]);
+        let verify_matches = verify_matches
+            .subcommand_matches("ewt")
+            .and_then(|ewt_matches| ewt_matches.subcommand_matches("verify"))
+            .expect("Should match the verify subcommand");
+        let output = ewt_verify_output(verify_matches).expect("Should verify the token");
+
+        let verified = ewt_verify_token(&token).expect("Should verify the token");
+        assert_eq!(
+            serde_json::json!({
+                "from": whoami,
+                "payload": {
+                    "id": validator.to_checksum(),
+                    "era": verified.payload.era,
+                    "address": whoami.to_checksum(),
+                },
+            }),
+            serde_json::from_str::<serde_json::Value>(&output).expect("Should be JSON")
+        );
+        assert_eq!(whoami, verified.from);
+        assert_eq!(validator.to_checksum(), verified.payload.id);
+        assert_eq!(whoami.to_checksum(), verified.payload.address);
+    }
+
+    #[test]
+    fn adapter_is_selected_from_the_config_unless_overridden_by_the_cli() {
+        let config_file = std::env::temp_dir().join("validator_worker_dummy_adapter.toml");
+        let dummy_config = format!(
+            "{}\nadapter = {{ type = 'dummy', dummy_identity = '{}' }}\n",
+            include_str!("../../docs/config/dev.toml"),
+            IDS["leader"].to_hex_prefix_string()
+        );
+        std::fs::write(&config_file, dummy_config).expect("Should write the config file");
+
+        let config = configuration("development", config_file.to_str())
+            .expect("Should load the config file");
+
+        let matches = app().get_matches_from(vec!["validator_worker"]);
+        assert_eq!(
+            AdapterConfig::Dummy {
+                dummy_identity: IDS["leader"]
+            },
+            adapter_config(&matches, &config).expect("Should select the adapter of the config")
+        );
+
+        let matches = app().get_matches_from(vec![
+            "validator_worker",
+            "--dummyIdentity",
+            IDS["follower"].to_hex_prefix_string().as_str(),
+        ]);
+        assert_eq!(
+            AdapterConfig::Dummy {
+                dummy_identity: IDS["follower"]
+            },
+            adapter_config(&matches, &config).expect("Should override the dummy identity")
+        );
+
+        let matches = app().get_matches_from(vec![
+            "validator_worker",
+            "--adapter",
+            "ethereum",
+            "--keystoreFile",
+            "./keystore.json",
+        ]);
+        assert_eq!(
+            AdapterConfig::Ethereum {
+                keystore_file: "./keystore.json".to_string()
+            },
+            adapter_config(&matches, &config).expect("Should override the adapter")
+        );
 
-    Logger::root(drain, o!())
+        // without an adapter in the config, the Ethereum adapter requires a keystore file
+        let dev_config =
+            configuration("development", None).expect("Dev config should be available");
+        let matches = app().get_matches_from(vec!["validator_worker"]);
+        assert!(adapter_config(&matches, &dev_config).is_err());
+    }
+
+    #[test]
+    fn log_format_flag_selects_the_drain() {
+        let matches = app().get_matches_from(vec!["validator_worker"]);
+        assert_eq!(
+            LogFormat::Compact,
+            log_format(&matches).expect("Should default to compact")
+        );
+
+        let matches = app().get_matches_from(vec!["validator_worker", "--log-format", "json"]);
+        assert_eq!(
+            LogFormat::Json,
+            log_format(&matches).expect("Should select json")
+        );
+
+        let matches = app().get_matches_from_safe(vec!["validator_worker", "--log-format", "xml"]);
+        assert!(matches.is_err(), "Only compact and json are allowed");
+
+        // both drains should be constructed
+        let log_level = LogLevel::new(Level::Info);
+        let compact = logger(LogFormat::Compact, &log_level);
+        let json = logger(LogFormat::Json, &log_level);
+        info!(&compact, "compact logger");
+        info!(&json, "json logger");
+    }
+
+    #[tokio::test]
+    async fn log_level_is_cycled_on_signals() {
+        use primitives::util::logging::LogLevelFilter;
+        use slog::{debug, o, trace, Never, OwnedKVList, Record};
+        use std::sync::Mutex;
+
+        struct Recorder(Arc<Mutex<Vec<String>>>);
+
+        impl slog::Drain for Recorder {
+            type Ok = ();
+            type Err = Never;
+
+            fn log(&self, record: &Record<'_>, _: &OwnedKVList) -> Result<(), Never> {
+                self.0.lock().unwrap().push(record.msg().to_string());
+                Ok(())
+            }
+        }
+
+        let records = Arc::new(Mutex::new(vec![]));
+        let log_level = LogLevel::new(Level::Info);
+        let logger = Logger::root(
+            LogLevelFilter::new(Recorder(records.clone()), log_level.clone()),
+            o!(),
+        );
+
+        debug!(&logger, "debug before the signal");
+        info!(&logger, "info before the signal");
+
+        let (signals, receiver) = futures::channel::mpsc::unbounded();
+        signals.unbounded_send(()).expect("Should send the signal");
+        // the handler returns once all the signals are handled
+        drop(signals);
+        cycle_log_level(receiver, log_level.clone(), &logger).await;
+        assert_eq!(Level::Debug, log_level.get());
+
+        debug!(&logger, "debug after the signal");
+        trace!(&logger, "trace after the signal");
+
+        assert_eq!(
+            vec![
+                "info before the signal".to_string(),
+                "Log level changed to DEBUG".to_string(),
+                "debug after the signal".to_string(),
+            ],
+            *records.lock().unwrap()
+        );
+    }
+
+    #[test]
+    fn loop_delay_is_the_configured_wait_time_in_milliseconds() {
+        let mut config =
+            configuration("development", None).expect("Dev config should be available");
+        assert_eq!(Duration::from_millis(500), wait_time(&config));
+
+        config.wait_time = 40_000;
+        assert_eq!(Duration::from_secs(40), wait_time(&config));
+    }
 }
@@ -1,15 +1,18 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
-use std::time::Duration;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use chrono::{DateTime, Utc};
 use futures::future::{join_all, try_join_all, TryFutureExt};
-use reqwest::{Client, Response};
-use slog::Logger;
+use reqwest::{Client, Response, StatusCode};
+use slog::{warn, Logger};
 
 use primitives::adapter::{Adapter, AdapterErrorKind, Error as AdapterError};
+use primitives::analytics::{AnalyticsQuery, AnalyticsResponse};
 use primitives::sentry::{
-    ChannelListResponse, EventAggregateResponse, LastApprovedResponse, SuccessResponse,
+    ChannelListResponse, Event, EventAggregateResponse, LastApprovedResponse, SuccessResponse,
     ValidatorMessageResponse,
 };
 use primitives::validator::MessageTypes;
@@ -17,15 +20,63 @@ use primitives::{Channel, ChannelId, Config, ToETHChecksum, ValidatorDesc, Valid
 
 pub type PropagationResult<AE> = Result<ValidatorId, (ValidatorId, Error<AE>)>;
 
+/// The messages which have failed to propagate, by Channel and by the validator they have failed for.
+/// It is shared between the ticks, so the messages are propagated again on the next tick of the Channel,
+/// see [`SentryApi::propagate_failed`].
+#[derive(Debug, Clone, Default)]
+pub struct FailedPropagations(
+    Arc<Mutex<HashMap<ChannelId, HashMap<ValidatorId, Vec<MessageTypes>>>>>,
+);
+
+impl FailedPropagations {
+    /// Keeps only the latest `limit` messages for each validator,
+    /// since this is the maximum number of messages the Sentry accepts in a single propagation.
+    fn add(
+        &self,
+        channel: ChannelId,
+        validator: ValidatorId,
+        messages: &[&MessageTypes],
+        limit: usize,
+    ) {
+        let mut failed = self.0.lock().expect("The lock should not be poisoned");
+        let pending = failed
+            .entry(channel)
+            .or_default()
+            .entry(validator)
+            .or_default();
+
+        pending.extend(messages.iter().map(|message| (*message).clone()));
+        if pending.len() > limit {
+            pending.drain(..pending.len() - limit);
+        }
+    }
+
+    fn take(&self, channel: &ChannelId) -> HashMap<ValidatorId, Vec<MessageTypes>> {
+        self.0
+            .lock()
+            .expect("The lock should not be poisoned")
+            .remove(channel)
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SentryApi<T: Adapter> {
-    pub adapter: T,
+    /// Shared between the ticks of all channels, instead of cloning the adapter for each one
+    pub adapter: Arc<T>,
+    /// The URL of our validator's Sentry
+    pub sentry_url: String,
     pub validator_url: String,
     pub client: Client,
     pub logger: Logger,
     pub channel: Channel,
     pub config: Config,
     pub propagate_to: Vec<(ValidatorDesc, String)>,
+    /// Our validator's Sentry followed by its read replicas
+    read_urls: Vec<String>,
+    /// Shared between the clones, so the reads of all clones are distributed
+    next_read: Arc<AtomicUsize>,
+    failed_propagations: FailedPropagations,
 }
 
 #[derive(Debug)]
@@ -33,6 +84,8 @@ pub enum Error<AE: AdapterErrorKind> {
     BuildingClient(reqwest::Error),
     Request(reqwest::Error),
     ValidatorAuthentication(AdapterError<AE>),
+    /// The `whoami` of the adapter is the zero address, which means it wasn't unlocked
+    AdapterNotUnlocked,
     MissingWhoamiInChannelValidators {
         channel: ChannelId,
         validators: Vec<ValidatorId>,
@@ -40,6 +93,56 @@ pub enum Error<AE: AdapterErrorKind> {
     },
 }
 
+impl<AE: AdapterErrorKind> Error<AE> {
+    fn reqwest_error(&self) -> Option<&reqwest::Error> {
+        match self {
+            Error::BuildingClient(err) | Error::Request(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// The request timed out, it's safe to retry it.
+    pub fn is_timeout(&self) -> bool {
+        self.reqwest_error()
+            .map(reqwest::Error::is_timeout)
+            .unwrap_or(false)
+    }
+
+    /// We were unable to connect to the validator, e.g. the connection was refused.
+    pub fn is_connect(&self) -> bool {
+        use std::error::Error as _;
+        use std::io::ErrorKind;
+
+        let mut source = match self.reqwest_error() {
+            Some(err) => err.source(),
+            None => return false,
+        };
+
+        while let Some(err) = source {
+            if let Some(io_err) = err.downcast_ref::<std::io::Error>() {
+                return matches!(
+                    io_err.kind(),
+                    ErrorKind::ConnectionRefused
+                        | ErrorKind::ConnectionReset
+                        | ErrorKind::ConnectionAborted
+                        | ErrorKind::NotConnected
+                        | ErrorKind::AddrNotAvailable
+                );
+            }
+            source = err.source();
+        }
+
+        false
+    }
+
+    /// The response body could not be decoded, retrying will not help.
+    pub fn is_decode(&self) -> bool {
+        self.reqwest_error()
+            .map(reqwest::Error::is_decode)
+            .unwrap_or(false)
+    }
+}
+
 impl<AE: AdapterErrorKind> std::error::Error for Error<AE> {}
 
 impl<AE: AdapterErrorKind> fmt::Display for Error<AE> {
@@ -52,6 +155,10 @@ impl<AE: AdapterErrorKind> fmt::Display for Error<AE> {
             ValidatorAuthentication(err) => {
                 write!(f, "Getting authentication for validator: {}", err)
             }
+            AdapterNotUnlocked => write!(
+                f,
+                "The adapter has a zero address as whoami, it should be unlocked first"
+            ),
             MissingWhoamiInChannelValidators {
                 channel,
                 validators,
@@ -75,8 +182,10 @@ impl<AE: AdapterErrorKind> fmt::Display for Error<AE> {
 }
 
 impl<A: Adapter + 'static> SentryApi<A> {
+    /// The `adapter` should already be unlocked (see [`Adapter::unlock`]),
+    /// since its `whoami` should be one of the validators of the `channel`.
     pub fn init(
-        adapter: A,
+        adapter: Arc<A>,
         channel: Channel,
         config: &Config,
         logger: Logger,
@@ -86,11 +195,16 @@ impl<A: Adapter + 'static> SentryApi<A> {
             .build()
             .map_err(Error::BuildingClient)?;
 
+        if adapter.whoami().is_zero() {
+            return Err(Error::AdapterNotUnlocked);
+        }
+
         // validate that we are to validate the channel
         match channel.spec.validators.find(adapter.whoami()) {
             Some(ref spec_validator) => {
                 let validator = spec_validator.validator();
-                let validator_url = format!("{}/channel/{}", validator.url, channel.id);
+                let sentry_url = validator.url.clone();
+                let validator_url = format!("{}/channel/{}", sentry_url, channel.id);
 
                 let propagate_to = channel
                     .spec
@@ -103,15 +217,24 @@ impl<A: Adapter + 'static> SentryApi<A> {
                             .map_err(Error::ValidatorAuthentication)
                     })
                     .collect::<Result<Vec<_>, _>>()?;
+                let propagate_to = dedup_propagate_to(propagate_to, &logger);
+
+                let read_urls = std::iter::once(sentry_url.clone())
+                    .chain(config.sentry_read_replicas.iter().cloned())
+                    .collect();
 
                 Ok(Self {
                     adapter,
+                    sentry_url,
                     validator_url,
                     client,
                     logger,
                     propagate_to,
                     channel,
                     config: config.to_owned(),
+                    read_urls,
+                    next_read: Default::default(),
+                    failed_propagations: Default::default(),
                 })
             }
             None => Err(Error::MissingWhoamiInChannelValidators {
@@ -122,20 +245,185 @@ impl<A: Adapter + 'static> SentryApi<A> {
         }
     }
 
+    /// Uses the given store of the failed propagations, instead of a new one for this `SentryApi`,
+    /// so the messages which fail to propagate on this tick are propagated again on the next one.
+    pub fn with_failed_propagations(mut self, failed_propagations: FailedPropagations) -> Self {
+        self.failed_propagations = failed_propagations;
+        self
+    }
+
+    /// The URL of the Sentry to read the channels and the analytics from,
+    /// the reads are distributed in a round-robin between our validator's Sentry and its read replicas.
+    ///
+    /// The validator messages and the event aggregates are always read from our validator's Sentry,
+    /// since the replicas may lag behind and the tick would act on a stale state.
+    fn read_url(&self) -> &str {
+        let index = self.next_read.fetch_add(1, Ordering::Relaxed) % self.read_urls.len();
+
+        &self.read_urls[index]
+    }
+
+    /// Propagates the messages to all of the validators and then retries once,
+    /// only for the validators that have failed, see [`SentryApi::retry_failed`].
+    ///
+    /// The validators are propagated to concurrently and the initial propagation
+    /// together with the retry share a single `propagation_timeout` deadline,
+    /// so the propagation time does not grow with the number of validators.
     pub async fn propagate(
         &self,
         messages: &[&MessageTypes],
     ) -> Vec<PropagationResult<A::AdapterError>> {
-        join_all(self.propagate_to.iter().map(|(validator, auth_token)| {
-            propagate_to::<A>(
-                &self.channel.id,
-                &auth_token,
-                &self.client,
-                &validator,
-                messages,
-            )
+        self.propagate_filtered(messages, |_| true).await
+    }
+
+    /// The same as [`SentryApi::propagate`], but only to the given `validators`.
+    pub async fn propagate_to_validators(
+        &self,
+        validators: &[ValidatorId],
+        messages: &[&MessageTypes],
+    ) -> Vec<PropagationResult<A::AdapterError>> {
+        self.propagate_filtered(messages, |validator_id| validators.contains(validator_id))
+            .await
+    }
+
+    async fn propagate_filtered(
+        &self,
+        messages: &[&MessageTypes],
+        filter: impl Fn(&ValidatorId) -> bool,
+    ) -> Vec<PropagationResult<A::AdapterError>> {
+        let deadline =
+            Instant::now() + Duration::from_millis(self.config.propagation_timeout.into());
+
+        let results = join_all(
+            self.propagate_to
+                .iter()
+                .filter(|(validator, _)| filter(&validator.id))
+                .map(|(validator, auth_token)| {
+                    propagate_to::<A>(
+                        &self.channel.id,
+                        &auth_token,
+                        &self.client,
+                        &validator,
+                        messages,
+                        deadline,
+                    )
+                }),
+        )
+        .await;
+
+        let results = self.retry_failed(messages, results, deadline).await;
+
+        if let Err(failed) = all_ok(&results) {
+            for (validator_id, error) in failed {
+                warn!(
+                    self.logger,
+                    "Failed to propagate the messages, they will be retried on the next tick";
+                    "channel" => %self.channel.id,
+                    "validator" => %validator_id,
+                    "error" => %error
+                );
+
+                self.failed_propagations.add(
+                    self.channel.id,
+                    *validator_id,
+                    messages,
+                    self.config.msgs_propagate_limit as usize,
+                );
+            }
+        }
+
+        results
+    }
+
+    /// Propagates the messages which have failed to propagate on the previous ticks,
+    /// only to the validators they have failed for.
+    /// The messages that fail again are kept for the next tick.
+    pub async fn propagate_failed(&self) -> Vec<PropagationResult<A::AdapterError>> {
+        let failed = self.failed_propagations.take(&self.channel.id);
+
+        if !failed.is_empty() {
+            warn!(
+                self.logger,
+                "Propagating the failed messages of the previous ticks to {} validator(s)",
+                failed.len();
+                "channel" => %self.channel.id
+            );
+        }
+
+        join_all(failed.iter().map(|(validator_id, messages)| async move {
+            let messages = messages.iter().collect::<Vec<_>>();
+
+            self.propagate_to_validators(&[*validator_id], &messages)
+                .await
         }))
         .await
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// Propagates the messages again only to the validators which have failed in `results`,
+    /// so the validators that have already accepted the messages don't receive them twice.
+    /// If the `deadline` has already passed, the `results` are returned as they are.
+    pub async fn retry_failed(
+        &self,
+        messages: &[&MessageTypes],
+        results: Vec<PropagationResult<A::AdapterError>>,
+        deadline: Instant,
+    ) -> Vec<PropagationResult<A::AdapterError>> {
+        let failed = all_ok(&results)
+            .err()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(validator_id, _)| *validator_id)
+            .collect::<Vec<_>>();
+
+        if failed.is_empty() || Instant::now() >= deadline {
+            return results;
+        }
+
+        warn!(
+            self.logger,
+            "Retrying the propagation to {} failed validator(s)",
+            failed.len();
+            "channel" => %self.channel.id
+        );
+
+        let mut retried = join_all(
+            self.propagate_to
+                .iter()
+                .filter(|(validator, _)| failed.contains(&validator.id))
+                .map(|(validator, auth_token)| {
+                    propagate_to::<A>(
+                        &self.channel.id,
+                        &auth_token,
+                        &self.client,
+                        &validator,
+                        messages,
+                        deadline,
+                    )
+                }),
+        )
+        .await
+        .into_iter()
+        .map(|result| {
+            let validator_id = match &result {
+                Ok(validator_id) | Err((validator_id, _)) => *validator_id,
+            };
+
+            (validator_id, result)
+        })
+        .collect::<HashMap<_, _>>();
+
+        results
+            .into_iter()
+            .map(|result| match result {
+                Err((validator_id, error)) => retried
+                    .remove(&validator_id)
+                    .unwrap_or(Err((validator_id, error))),
+                ok => ok,
+            })
+            .collect()
     }
 
     pub async fn get_latest_msg(
@@ -215,6 +503,111 @@ impl<A: Adapter + 'static> SentryApi<A> {
             .map_err(Error::Request)
             .await
     }
+
+    /// Fetches a single channel from our validator's Sentry, returns `None` if it doesn't exist
+    pub async fn get_channel(
+        &self,
+        id: ChannelId,
+    ) -> Result<Option<Channel>, Error<A::AdapterError>> {
+        let response = self
+            .client
+            .get(&format!("{}/channel/{}", self.read_url(), id))
+            .send()
+            .map_err(Error::Request)
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        response.json().map_ok(Some).map_err(Error::Request).await
+    }
+
+    /// Fetches the global analytics of our validator's Sentry
+    pub async fn get_analytics(
+        &self,
+        query: &AnalyticsQuery,
+    ) -> Result<AnalyticsResponse, Error<A::AdapterError>> {
+        self.client
+            .get(&format!("{}/analytics", self.read_url()))
+            .query(query)
+            .send()
+            .and_then(|res: Response| res.json::<AnalyticsResponse>())
+            .map_err(Error::Request)
+            .await
+    }
+
+    /// Submits the `events` of the `channel` to our validator's Sentry
+    pub async fn post_events(
+        &self,
+        channel: ChannelId,
+        events: &[Event],
+    ) -> Result<SuccessResponse, Error<A::AdapterError>> {
+        let auth_token = self
+            .adapter
+            .get_auth(self.adapter.whoami())
+            .map_err(Error::ValidatorAuthentication)?;
+
+        let mut body = HashMap::new();
+        body.insert("events", events);
+
+        self.client
+            .post(&format!("{}/channel/{}/events", self.sentry_url, channel))
+            .bearer_auth(&auth_token)
+            .json(&body)
+            .send()
+            .map_err(Error::Request)
+            .await?
+            .json()
+            .map_err(Error::Request)
+            .await
+    }
+}
+
+/// Summarizes the results of `SentryApi::propagate`,
+/// returning all of the failed validators with their errors, if there are any.
+pub fn all_ok<AE: AdapterErrorKind>(
+    results: &[PropagationResult<AE>],
+) -> Result<(), Vec<&(ValidatorId, Error<AE>)>> {
+    let failed = results
+        .iter()
+        .filter_map(|result| result.as_ref().err())
+        .collect::<Vec<_>>();
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(failed)
+    }
+}
+
+/// Collapses the propagation targets with the same URL and auth token,
+/// e.g. when both validators of the channel point to the same Sentry,
+/// in order to not send the same messages twice.
+fn dedup_propagate_to(
+    propagate_to: Vec<(ValidatorDesc, String)>,
+    logger: &Logger,
+) -> Vec<(ValidatorDesc, String)> {
+    let mut deduped: Vec<(ValidatorDesc, String)> = Vec::with_capacity(propagate_to.len());
+
+    for (validator, auth_token) in propagate_to {
+        let duplicate = deduped.iter().find(|(target, target_token)| {
+            target.url == validator.url && target_token == &auth_token
+        });
+
+        match duplicate {
+            Some((target, _)) => warn!(
+                logger,
+                "Validator {} has the same propagation URL and auth token as validator {}, messages will be propagated only once",
+                validator.id,
+                target.id;
+                "url" => &validator.url
+            ),
+            None => deduped.push((validator, auth_token)),
+        }
+    }
+
+    deduped
 }
 
 async fn propagate_to<A: Adapter>(
@@ -223,6 +616,7 @@ async fn propagate_to<A: Adapter>(
     client: &Client,
     validator: &ValidatorDesc,
     messages: &[&MessageTypes],
+    deadline: Instant,
 ) -> PropagationResult<A::AdapterError> {
     let url = format!(
         "{}/channel/{}/validator-messages",
@@ -235,6 +629,7 @@ async fn propagate_to<A: Adapter>(
         .post(&url)
         .bearer_auth(&auth_token)
         .json(&body)
+        .timeout(deadline.saturating_duration_since(Instant::now()))
         .send()
         .await
         .map_err(|e| (validator.id, Error::Request(e)))?
@@ -259,11 +654,12 @@ pub async fn all_channels(
             try_join_all((1..first_page.total_pages).map(|i| fetch_page(url.clone(), i, &whoami)))
                 .await?;
 
-        let result_all: Vec<Channel> = std::iter::once(first_page)
+        // a channel can be listed on two pages when the list changes between the requests
+        let result_all: HashSet<Channel> = std::iter::once(first_page)
             .chain(all.into_iter())
             .flat_map(|ch| ch.channels.into_iter())
             .collect();
-        Ok(result_all)
+        Ok(result_all.into_iter().collect())
     }
 }
 
@@ -286,3 +682,582 @@ async fn fetch_page(
         .and_then(|res: Response| res.json::<ChannelListResponse>())
         .await
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use adapter::DummyAdapter;
+    use primitives::adapter::DummyAdapterOptions;
+    use primitives::config::configuration;
+    use primitives::util::tests::discard_logger;
+    use primitives::util::tests::prep_db::{
+        AUTH, DUMMY_CHANNEL, DUMMY_VALIDATOR_FOLLOWER, DUMMY_VALIDATOR_LEADER, IDS,
+    };
+    use primitives::validator::Heartbeat;
+    use primitives::SpecValidators;
+    use serde_json::json;
+    use std::net::TcpListener;
+    use wiremock::{
+        matchers::{body_json, header, method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    type TestError = Error<adapter::dummy::Error>;
+
+    #[tokio::test]
+    async fn request_errors_are_categorized() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/slow"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/invalid-json"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("not json"))
+            .mount(&server)
+            .await;
+
+        let client = Client::builder()
+            .timeout(Duration::from_millis(100))
+            .build()
+            .expect("Should build client");
+
+        let timeout: TestError = client
+            .get(&format!("{}/slow", server.uri()))
+            .send()
+            .await
+            .map_err(Error::Request)
+            .expect_err("Should time out");
+        assert!(timeout.is_timeout());
+        assert!(!timeout.is_connect());
+        assert!(!timeout.is_decode());
+
+        let decode: TestError = client
+            .get(&format!("{}/invalid-json", server.uri()))
+            .send()
+            .and_then(|res: Response| res.json::<SuccessResponse>())
+            .await
+            .map_err(Error::Request)
+            .expect_err("Should fail to decode");
+        assert!(decode.is_decode());
+        assert!(!decode.is_timeout());
+        assert!(!decode.is_connect());
+
+        // bind and drop a listener to get a port that nobody listens on
+        let closed_port = {
+            let listener = TcpListener::bind("127.0.0.1:0").expect("Should bind");
+            listener.local_addr().expect("Should have address").port()
+        };
+        let connect: TestError = client
+            .get(&format!("http://127.0.0.1:{}", closed_port))
+            .send()
+            .await
+            .map_err(Error::Request)
+            .expect_err("Should fail to connect");
+        assert!(connect.is_connect());
+        assert!(!connect.is_timeout());
+        assert!(!connect.is_decode());
+
+        let other: TestError = Error::MissingWhoamiInChannelValidators {
+            channel: DUMMY_CHANNEL.id,
+            validators: vec![],
+            whoami: IDS["leader"],
+        };
+        assert!(!other.is_timeout() && !other.is_connect() && !other.is_decode());
+    }
+
+    fn setup_sentry_api(channel: Channel) -> SentryApi<DummyAdapter> {
+        let config = configuration("development", None).expect("Dev config should be available");
+
+        try_setup_sentry_api(IDS["leader"], channel, &config).expect("Should init the SentryApi")
+    }
+
+    fn try_setup_sentry_api(
+        identity: ValidatorId,
+        channel: Channel,
+        config: &Config,
+    ) -> Result<SentryApi<DummyAdapter>, TestError> {
+        let adapter = DummyAdapter::init(
+            DummyAdapterOptions {
+                dummy_identity: identity,
+                dummy_auth: IDS.clone(),
+                dummy_auth_tokens: AUTH.clone(),
+            },
+            config,
+        );
+
+        SentryApi::init(Arc::new(adapter), channel, config, discard_logger())
+    }
+
+    #[test]
+    fn all_ok_returns_only_the_failed_validators() {
+        let ok_results: Vec<PropagationResult<adapter::dummy::Error>> =
+            vec![Ok(IDS["leader"]), Ok(IDS["follower"])];
+        assert!(all_ok(&ok_results).is_ok());
+
+        let mixed_results: Vec<PropagationResult<adapter::dummy::Error>> = vec![
+            Ok(IDS["leader"]),
+            Err((
+                IDS["follower"],
+                Error::ValidatorAuthentication(AdapterError::LockedWallet),
+            )),
+        ];
+        let failed = all_ok(&mixed_results).expect_err("Should return the failed validator");
+
+        assert_eq!(1, failed.len());
+        assert_eq!(IDS["follower"], failed[0].0);
+        assert!(matches!(
+            failed[0].1,
+            Error::ValidatorAuthentication(AdapterError::LockedWallet)
+        ));
+    }
+
+    #[tokio::test]
+    async fn propagate_retries_only_the_failed_validators() {
+        let leader_server = MockServer::start().await;
+        let follower_server = MockServer::start().await;
+
+        let mut leader = DUMMY_VALIDATOR_LEADER.clone();
+        leader.url = leader_server.uri();
+        let mut follower = DUMMY_VALIDATOR_FOLLOWER.clone();
+        follower.url = follower_server.uri();
+        let mut channel = DUMMY_CHANNEL.clone();
+        channel.spec.validators = SpecValidators::new(leader, follower);
+
+        Mock::given(method("POST"))
+            .and(path(format!("/channel/{}/validator-messages", channel.id)))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(SuccessResponse { success: true }),
+            )
+            // the leader accepts the messages and should not receive them again
+            .expect(1)
+            .mount(&leader_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(format!("/channel/{}/validator-messages", channel.id)))
+            .respond_with(ResponseTemplate::new(500))
+            // the initial propagation and a single retry
+            .expect(2)
+            .mount(&follower_server)
+            .await;
+
+        let sentry = setup_sentry_api(channel);
+
+        let heartbeat = MessageTypes::Heartbeat(Heartbeat {
+            signature: "signature".to_string(),
+            state_root: "0".repeat(64),
+            timestamp: Utc::now(),
+        });
+        let results = sentry.propagate(&[&heartbeat]).await;
+
+        assert_eq!(2, results.len());
+        assert!(matches!(results[0], Ok(validator_id) if validator_id == IDS["leader"]));
+        assert!(matches!(results[1], Err((validator_id, _)) if validator_id == IDS["follower"]));
+    }
+
+    #[tokio::test]
+    async fn failed_propagations_are_retried_only_to_the_failed_validator_on_the_next_tick() {
+        let leader_server = MockServer::start().await;
+        let follower_server = MockServer::start().await;
+
+        let mut leader = DUMMY_VALIDATOR_LEADER.clone();
+        leader.url = leader_server.uri();
+        let mut follower = DUMMY_VALIDATOR_FOLLOWER.clone();
+        follower.url = follower_server.uri();
+        let mut channel = DUMMY_CHANNEL.clone();
+        channel.spec.validators = SpecValidators::new(leader, follower);
+
+        let heartbeat = MessageTypes::Heartbeat(Heartbeat {
+            signature: "signature".to_string(),
+            state_root: "0".repeat(64),
+            timestamp: Utc::now(),
+        });
+
+        Mock::given(method("POST"))
+            .and(path(format!("/channel/{}/validator-messages", channel.id)))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(SuccessResponse { success: true }),
+            )
+            // only on the first tick
+            .expect(1)
+            .mount(&leader_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(format!("/channel/{}/validator-messages", channel.id)))
+            .respond_with(ResponseTemplate::new(500))
+            .up_to_n_times(2)
+            // the initial propagation and the retry
+            .expect(2)
+            .mount(&follower_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(format!("/channel/{}/validator-messages", channel.id)))
+            .and(body_json(json!({ "messages": [&heartbeat] })))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(SuccessResponse { success: true }),
+            )
+            // the propagation of the failed message on the next tick
+            .expect(1)
+            .mount(&follower_server)
+            .await;
+
+        let failed_propagations = FailedPropagations::default();
+
+        let first_tick =
+            setup_sentry_api(channel.clone()).with_failed_propagations(failed_propagations.clone());
+        let results = first_tick.propagate(&[&heartbeat]).await;
+        assert!(matches!(results[1], Err((validator_id, _)) if validator_id == IDS["follower"]));
+
+        let next_tick =
+            setup_sentry_api(channel.clone()).with_failed_propagations(failed_propagations.clone());
+        let retried = next_tick.propagate_failed().await;
+        assert_eq!(1, retried.len());
+        assert!(matches!(retried[0], Ok(validator_id) if validator_id == IDS["follower"]));
+
+        // the message has been propagated and there is nothing left to retry
+        assert!(next_tick.propagate_failed().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn propagates_once_to_validators_with_the_same_url() {
+        let server = MockServer::start().await;
+
+        let mut leader = DUMMY_VALIDATOR_LEADER.clone();
+        leader.url = server.uri();
+        let mut follower = DUMMY_VALIDATOR_FOLLOWER.clone();
+        follower.url = server.uri();
+        let mut channel = DUMMY_CHANNEL.clone();
+        channel.spec.validators = SpecValidators::new(leader, follower);
+
+        Mock::given(method("POST"))
+            .and(path(format!("/channel/{}/validator-messages", channel.id)))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(SuccessResponse { success: true }),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let sentry = setup_sentry_api(channel);
+
+        assert_eq!(1, sentry.propagate_to.len());
+
+        let heartbeat = MessageTypes::Heartbeat(Heartbeat {
+            signature: "signature".to_string(),
+            state_root: "0".repeat(64),
+            timestamp: Utc::now(),
+        });
+        let results = sentry.propagate(&[&heartbeat]).await;
+
+        assert_eq!(1, results.len());
+        assert!(results[0].is_ok());
+    }
+
+    #[tokio::test]
+    async fn propagation_to_slow_validators_respects_the_tick_budget() {
+        let leader_server = MockServer::start().await;
+        let follower_server = MockServer::start().await;
+
+        let mut leader = DUMMY_VALIDATOR_LEADER.clone();
+        leader.url = leader_server.uri();
+        let mut follower = DUMMY_VALIDATOR_FOLLOWER.clone();
+        follower.url = follower_server.uri();
+        let mut channel = DUMMY_CHANNEL.clone();
+        channel.spec.validators = SpecValidators::new(leader, follower);
+
+        let sentry = setup_sentry_api(channel.clone());
+        let propagation_timeout = Duration::from_millis(sentry.config.propagation_timeout.into());
+        let tick_timeout = Duration::from_millis(sentry.config.validator_tick_timeout.into());
+
+        for server in &[&leader_server, &follower_server] {
+            Mock::given(method("POST"))
+                .and(path(format!("/channel/{}/validator-messages", channel.id)))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(SuccessResponse { success: true })
+                        .set_delay(tick_timeout),
+                )
+                .mount(server)
+                .await;
+        }
+
+        let heartbeat = MessageTypes::Heartbeat(Heartbeat {
+            signature: "signature".to_string(),
+            state_root: "0".repeat(64),
+            timestamp: Utc::now(),
+        });
+
+        let started = Instant::now();
+        let results = sentry.propagate(&[&heartbeat]).await;
+        let elapsed = started.elapsed();
+
+        assert_eq!(2, results.len());
+        assert!(results
+            .iter()
+            .all(|result| matches!(result, Err((_, err)) if err.is_timeout())));
+        // all validators and the retry share the same deadline
+        assert!(elapsed < propagation_timeout * 2);
+        assert!(elapsed < tick_timeout);
+    }
+
+    #[tokio::test]
+    async fn post_events_submits_the_events_with_our_auth_token() {
+        let server = MockServer::start().await;
+
+        let mut leader = DUMMY_VALIDATOR_LEADER.clone();
+        leader.url = server.uri();
+        let mut channel = DUMMY_CHANNEL.clone();
+        channel.spec.validators = SpecValidators::new(leader, DUMMY_VALIDATOR_FOLLOWER.clone());
+
+        let events = vec![
+            Event::Impression {
+                publisher: IDS["publisher"],
+                ad_unit: None,
+                ad_slot: None,
+                referrer: None,
+            },
+            Event::Close,
+        ];
+
+        Mock::given(method("POST"))
+            .and(path(format!("/channel/{}/events", channel.id)))
+            .and(header("authorization", "Bearer AUTH_awesomeLeader"))
+            .and(body_json(json!({
+                "events": [
+                    {
+                        "type": "IMPRESSION",
+                        "publisher": IDS["publisher"],
+                        "adUnit": null,
+                        "adSlot": null,
+                        "referrer": null,
+                    },
+                    { "type": "CLOSE" },
+                ]
+            })))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(SuccessResponse { success: true }),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let sentry = setup_sentry_api(channel.clone());
+
+        let response = sentry
+            .post_events(channel.id, &events)
+            .await
+            .expect("Should post the events");
+        assert!(response.success);
+    }
+
+    #[tokio::test]
+    async fn get_analytics_serializes_the_query() {
+        use chrono::TimeZone;
+        use primitives::analytics::{AnalyticsData, EventType, Metric, Timeframe};
+
+        let server = MockServer::start().await;
+
+        let mut leader = DUMMY_VALIDATOR_LEADER.clone();
+        leader.url = server.uri();
+        let mut channel = DUMMY_CHANNEL.clone();
+        channel.spec.validators = SpecValidators::new(leader, DUMMY_VALIDATOR_FOLLOWER.clone());
+
+        let start = Utc.ymd(2020, 10, 1).and_hms(0, 0, 0);
+        let query = AnalyticsQuery {
+            limit: 50,
+            event_type: EventType::Click,
+            metric: Metric::EventPayouts,
+            timeframe: Timeframe::Week,
+            segment_by_channel: None,
+            start: Some(start),
+            end: None,
+        };
+
+        Mock::given(method("GET"))
+            .and(path("/analytics"))
+            .and(query_param("limit", "50"))
+            .and(query_param("eventType", "CLICK"))
+            .and(query_param("metric", "eventPayouts"))
+            .and(query_param("timeframe", "week"))
+            .and(query_param("start", "2020-10-01T00:00:00Z"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(AnalyticsResponse {
+                aggr: vec![AnalyticsData {
+                    time: 1_601_510_400_000.0,
+                    value: "1000".to_string(),
+                    channel_id: None,
+                }],
+                limit: 50,
+            }))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let sentry = setup_sentry_api(channel);
+
+        let response = sentry
+            .get_analytics(&query)
+            .await
+            .expect("Should get the analytics");
+        assert_eq!(50, response.limit);
+        assert_eq!(1, response.aggr.len());
+        assert_eq!("1000", response.aggr[0].value);
+    }
+
+    #[tokio::test]
+    async fn get_channel_returns_none_for_a_missing_channel() {
+        let server = MockServer::start().await;
+
+        let mut leader = DUMMY_VALIDATOR_LEADER.clone();
+        leader.url = server.uri();
+        let mut channel = DUMMY_CHANNEL.clone();
+        channel.spec.validators = SpecValidators::new(leader, DUMMY_VALIDATOR_FOLLOWER.clone());
+        let missing = ChannelId::from([3_u8; 32]);
+
+        Mock::given(method("GET"))
+            .and(path(format!("/channel/{}", channel.id)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&channel))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/channel/{}", missing)))
+            .respond_with(ResponseTemplate::new(404).set_body_string("Not found"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let sentry = setup_sentry_api(channel.clone());
+
+        let found = sentry
+            .get_channel(channel.id)
+            .await
+            .expect("Should get the channel")
+            .expect("The channel should exist");
+        assert_eq!(channel.id, found.id);
+        assert_eq!(channel.spec.validators, found.spec.validators);
+
+        let not_found = sentry
+            .get_channel(missing)
+            .await
+            .expect("Should not error for a missing channel");
+        assert!(not_found.is_none());
+    }
+
+    #[test]
+    fn init_fails_for_an_adapter_which_is_not_unlocked() {
+        let config = configuration("development", None).expect("Dev config should be available");
+
+        let error = try_setup_sentry_api(ValidatorId::ZERO, DUMMY_CHANNEL.clone(), &config)
+            .expect_err("Should not init with a locked adapter");
+
+        assert!(matches!(error, Error::AdapterNotUnlocked));
+    }
+
+    #[tokio::test]
+    async fn only_the_channel_and_analytics_reads_are_distributed() {
+        let primary = MockServer::start().await;
+        let replica = MockServer::start().await;
+
+        let mut leader = DUMMY_VALIDATOR_LEADER.clone();
+        leader.url = primary.uri();
+        let mut channel = DUMMY_CHANNEL.clone();
+        channel.spec.validators = SpecValidators::new(leader, DUMMY_VALIDATOR_FOLLOWER.clone());
+
+        // the validator messages are read only from the primary
+        Mock::given(method("GET"))
+            .and(path(format!("/channel/{}/last-approved", channel.id)))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(LastApprovedResponse {
+                    last_approved: None,
+                    heartbeats: None,
+                }),
+            )
+            .expect(4)
+            .mount(&primary)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!(
+                "/channel/{}/validator-messages/{}/NewState",
+                channel.id,
+                IDS["leader"].to_checksum()
+            )))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(ValidatorMessageResponse {
+                    validator_messages: vec![],
+                }),
+            )
+            .expect(2)
+            .mount(&primary)
+            .await;
+        for server in &[&primary, &replica] {
+            Mock::given(method("GET"))
+                .and(path(format!("/channel/{}", channel.id)))
+                .respond_with(ResponseTemplate::new(200).set_body_json(&channel))
+                .expect(1)
+                .mount(server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/analytics"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(AnalyticsResponse {
+                    aggr: vec![],
+                    limit: 100,
+                }))
+                .expect(1)
+                .mount(server)
+                .await;
+        }
+        Mock::given(method("POST"))
+            .and(path(format!("/channel/{}/events", channel.id)))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(SuccessResponse { success: true }),
+            )
+            .expect(1)
+            .mount(&primary)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&replica)
+            .await;
+
+        let mut config =
+            configuration("development", None).expect("Dev config should be available");
+        config.sentry_read_replicas = vec![replica.uri()];
+        let sentry = try_setup_sentry_api(IDS["leader"], channel.clone(), &config)
+            .expect("Should init the SentryApi");
+
+        for _ in 0..4 {
+            sentry
+                .get_last_approved()
+                .await
+                .expect("Should get the last approved");
+        }
+        for _ in 0..2 {
+            sentry
+                .get_our_latest_msg(&["NewState"])
+                .await
+                .expect("Should get our latest NewState");
+        }
+        for _ in 0..2 {
+            sentry
+                .get_channel(channel.id)
+                .await
+                .expect("Should get the channel")
+                .expect("The channel should exist");
+        }
+        let query: AnalyticsQuery =
+            serde_json::from_value(json!({})).expect("Should use the default query");
+        for _ in 0..2 {
+            sentry
+                .get_analytics(&query)
+                .await
+                .expect("Should get the analytics");
+        }
+
+        sentry
+            .post_events(channel.id, &[Event::Close])
+            .await
+            .expect("Should post the events");
+    }
+}
@@ -0,0 +1,450 @@
+//! A leader and a follower `SentryApi`, each one with its own in-memory mock sentry,
+//! which allows running `leader::tick` and `follower::tick` end to end with dummy adapters.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use adapter::DummyAdapter;
+use chrono::{DateTime, TimeZone, Utc};
+use primitives::adapter::DummyAdapterOptions;
+use primitives::config::configuration;
+use primitives::sentry::{
+    AggregateEvents, ApproveStateValidatorMessage, EventAggregate, EventAggregateResponse,
+    LastApproved, LastApprovedResponse, NewStateValidatorMessage, SuccessResponse,
+    ValidatorMessage, ValidatorMessageResponse,
+};
+use primitives::util::tests::discard_logger;
+use primitives::util::tests::prep_db::{
+    AUTH, DUMMY_CHANNEL, DUMMY_VALIDATOR_FOLLOWER, DUMMY_VALIDATOR_LEADER, IDS,
+};
+use primitives::validator::MessageTypes;
+use primitives::{Channel, SpecValidators, ToETHChecksum, ValidatorId};
+use wiremock::{
+    matchers::{header, method},
+    Mock, MockServer, Request, Respond, ResponseTemplate,
+};
+
+use crate::SentryApi;
+
+type Messages = Arc<Mutex<Vec<(ValidatorId, DateTime<Utc>, MessageTypes)>>>;
+
+fn message_type(message: &MessageTypes) -> &'static str {
+    match message {
+        MessageTypes::ApproveState(_) => "ApproveState",
+        MessageTypes::NewState(_) => "NewState",
+        MessageTypes::RejectState(_) => "RejectState",
+        MessageTypes::Heartbeat(_) => "Heartbeat",
+        MessageTypes::Accounting(_) => "Accounting",
+    }
+}
+
+/// Stores the propagated validator messages of a single validator (the `from`)
+struct PropagateResponder {
+    from: ValidatorId,
+    messages: Messages,
+}
+
+impl Respond for PropagateResponder {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let mut body: HashMap<String, Vec<MessageTypes>> =
+            match serde_json::from_slice(&request.body) {
+                Ok(body) => body,
+                Err(_) => return ResponseTemplate::new(400),
+            };
+
+        let mut messages = self.messages.lock().expect("Should lock the messages");
+        for message in body.remove("messages").unwrap_or_default() {
+            messages.push((self.from, Utc::now(), message));
+        }
+
+        ResponseTemplate::new(200).set_body_json(SuccessResponse { success: true })
+    }
+}
+
+/// Serves the `validator-messages`, `last-approved` and `events-aggregates` of the channel
+struct ChannelResponder {
+    channel: Channel,
+    messages: Messages,
+    aggregates: Vec<EventAggregate>,
+}
+
+impl ChannelResponder {
+    fn latest(&self, from: &str, types: &[&str]) -> Vec<ValidatorMessage> {
+        let messages = self.messages.lock().expect("Should lock the messages");
+
+        messages
+            .iter()
+            .rev()
+            .find(|(validator, _, msg)| {
+                validator.to_checksum() == from && types.contains(&message_type(msg))
+            })
+            .map(|(from, received, msg)| ValidatorMessage {
+                from: *from,
+                received: *received,
+                msg: msg.clone(),
+            })
+            .into_iter()
+            .collect()
+    }
+
+    fn last_approved(&self) -> Option<LastApproved> {
+        let messages = self.messages.lock().expect("Should lock the messages");
+
+        let (approve_from, approve_received, approve_state) =
+            messages
+                .iter()
+                .rev()
+                .find_map(|(from, received, msg)| match msg {
+                    MessageTypes::ApproveState(approve_state) => {
+                        Some((*from, *received, approve_state.clone()))
+                    }
+                    _ => None,
+                })?;
+
+        let new_state = messages.iter().find_map(|(from, received, msg)| match msg {
+            MessageTypes::NewState(new_state)
+                if new_state.state_root == approve_state.state_root =>
+            {
+                Some(NewStateValidatorMessage {
+                    from: *from,
+                    received: *received,
+                    msg: msg.clone(),
+                })
+            }
+            _ => None,
+        });
+
+        Some(LastApproved {
+            new_state,
+            approve_state: Some(ApproveStateValidatorMessage {
+                from: approve_from,
+                received: approve_received,
+                msg: MessageTypes::ApproveState(approve_state),
+            }),
+        })
+    }
+}
+
+impl Respond for ChannelResponder {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let channel_path = format!("/channel/{}/", self.channel.id);
+        let route = match request.url.path().strip_prefix(&channel_path) {
+            Some(route) => route,
+            None => return ResponseTemplate::new(404),
+        };
+        let segments = route.split('/').collect::<Vec<_>>();
+
+        match segments.as_slice() {
+            ["validator-messages", from, types] => {
+                let types = types.split('+').collect::<Vec<_>>();
+
+                ResponseTemplate::new(200).set_body_json(ValidatorMessageResponse {
+                    validator_messages: self.latest(from, &types),
+                })
+            }
+            ["last-approved"] => ResponseTemplate::new(200).set_body_json(LastApprovedResponse {
+                last_approved: self.last_approved(),
+                heartbeats: None,
+            }),
+            ["events-aggregates"] => {
+                let after = request
+                    .url
+                    .query_pairs()
+                    .find(|(key, _)| key == "after")
+                    .and_then(|(_, value)| value.parse::<i64>().ok())
+                    .map(|millis| Utc.timestamp_millis(millis))
+                    .unwrap_or_else(|| Utc.timestamp(0, 0));
+
+                ResponseTemplate::new(200).set_body_json(EventAggregateResponse {
+                    channel: self.channel.clone(),
+                    events: self
+                        .aggregates
+                        .iter()
+                        .filter(|aggregate| aggregate.created > after)
+                        .cloned()
+                        .collect(),
+                })
+            }
+            _ => ResponseTemplate::new(404),
+        }
+    }
+}
+
+/// An in-memory sentry of a single validator
+pub struct MockSentry {
+    pub server: MockServer,
+    messages: Messages,
+}
+
+impl MockSentry {
+    pub async fn start() -> Self {
+        Self {
+            server: MockServer::start().await,
+            messages: Default::default(),
+        }
+    }
+
+    /// Mounts the routes of the `channel` which the `SentryApi` uses.
+    /// The `aggregates` are served on `events-aggregates`.
+    pub async fn mount(&self, channel: &Channel, aggregates: Vec<EventAggregate>) {
+        for validator in channel.spec.validators.iter() {
+            let auth_token = IDS
+                .iter()
+                .find(|(_, id)| **id == validator.id)
+                .and_then(|(name, _)| AUTH.get(name.as_str()))
+                .expect("The validator should have an auth token");
+
+            Mock::given(method("POST"))
+                .and(header(
+                    "Authorization",
+                    format!("Bearer {}", auth_token).as_str(),
+                ))
+                .respond_with(PropagateResponder {
+                    from: validator.id,
+                    messages: Arc::clone(&self.messages),
+                })
+                .mount(&self.server)
+                .await;
+        }
+
+        Mock::given(method("GET"))
+            .respond_with(ChannelResponder {
+                channel: channel.clone(),
+                messages: Arc::clone(&self.messages),
+                aggregates,
+            })
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Adds a message to the sentry, as if it was propagated by `from`
+    pub fn insert(&self, from: ValidatorId, message: MessageTypes) {
+        self.messages
+            .lock()
+            .expect("Should lock the messages")
+            .push((from, Utc::now(), message));
+    }
+
+    /// All the received messages of the given type from the validator
+    pub fn received(&self, from: &ValidatorId, message_type_name: &str) -> Vec<MessageTypes> {
+        self.messages
+            .lock()
+            .expect("Should lock the messages")
+            .iter()
+            .filter(|(validator, _, msg)| {
+                validator == from && message_type(msg) == message_type_name
+            })
+            .map(|(_, _, msg)| msg.clone())
+            .collect()
+    }
+}
+
+pub struct Harness {
+    pub leader_sentry: MockSentry,
+    pub follower_sentry: MockSentry,
+    pub leader: SentryApi<DummyAdapter>,
+    pub follower: SentryApi<DummyAdapter>,
+}
+
+impl Harness {
+    /// Creates the `DUMMY_CHANNEL` with the validators pointing to their mock sentries,
+    /// which both serve the given `aggregates`.
+    pub async fn setup(aggregates: Vec<EventAggregate>) -> Self {
+        let leader_sentry = MockSentry::start().await;
+        let follower_sentry = MockSentry::start().await;
+
+        let mut leader_desc = DUMMY_VALIDATOR_LEADER.clone();
+        leader_desc.url = leader_sentry.server.uri();
+        let mut follower_desc = DUMMY_VALIDATOR_FOLLOWER.clone();
+        follower_desc.url = follower_sentry.server.uri();
+
+        let mut channel = DUMMY_CHANNEL.clone();
+        channel.spec.validators = SpecValidators::new(leader_desc, follower_desc);
+
+        let aggregates = aggregates
+            .into_iter()
+            .map(|aggregate| EventAggregate {
+                channel_id: channel.id,
+                ..aggregate
+            })
+            .collect::<Vec<_>>();
+
+        leader_sentry.mount(&channel, aggregates.clone()).await;
+        follower_sentry.mount(&channel, aggregates).await;
+
+        Self {
+            leader: setup_sentry_api(IDS["leader"], &channel),
+            follower: setup_sentry_api(IDS["follower"], &channel),
+            leader_sentry,
+            follower_sentry,
+        }
+    }
+}
+
+fn setup_sentry_api(identity: ValidatorId, channel: &Channel) -> SentryApi<DummyAdapter> {
+    let config = configuration("development", None).expect("Dev config should be available");
+    let adapter = DummyAdapter::init(
+        DummyAdapterOptions {
+            dummy_identity: identity,
+            dummy_auth: IDS.clone(),
+            dummy_auth_tokens: AUTH.clone(),
+        },
+        &config,
+    );
+
+    SentryApi::init(
+        Arc::new(adapter),
+        channel.clone(),
+        &config,
+        discard_logger(),
+    )
+    .expect("Should init the SentryApi")
+}
+
+/// An `IMPRESSION` aggregate with a payout of `100` to the `publisher`.
+/// The `created` is truncated to milliseconds, as `events-aggregates?after=` is in milliseconds
+pub fn impressions_aggregate(created: DateTime<Utc>) -> EventAggregate {
+    let mut event_payouts = HashMap::new();
+    event_payouts.insert(IDS["publisher"], 100.into());
+
+    let mut events = HashMap::new();
+    events.insert(
+        "IMPRESSION".to_string(),
+        AggregateEvents {
+            event_counts: None,
+            event_payouts,
+        },
+    );
+
+    EventAggregate {
+        channel_id: DUMMY_CHANNEL.id,
+        created: Utc.timestamp_millis(created.timestamp_millis()),
+        events,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::follower::{self, ApproveStateResult, InvalidNewState};
+    use crate::{leader, producer};
+    use primitives::validator::NewState;
+
+    #[tokio::test]
+    async fn new_state_is_produced_propagated_and_approved() {
+        let harness = Harness::setup(vec![impressions_aggregate(Utc::now())]).await;
+        let (leader_id, follower_id) = (IDS["leader"], IDS["follower"]);
+
+        let leader_tick = leader::tick(&harness.leader)
+            .await
+            .expect("Leader tick should succeed");
+        let new_state_propagation = leader_tick
+            .new_state
+            .expect("The leader should produce a NewState");
+        assert!(new_state_propagation.iter().all(Result::is_ok));
+
+        let new_states = harness.follower_sentry.received(&leader_id, "NewState");
+        let new_state = match new_states.as_slice() {
+            [MessageTypes::NewState(new_state)] => new_state.clone(),
+            other => panic!("Expected a single NewState, got: {:?}", other),
+        };
+
+        let follower_tick = follower::tick(&harness.follower)
+            .await
+            .expect("Follower tick should succeed");
+        match follower_tick.approve_state {
+            ApproveStateResult::Sent(Some(propagation)) => {
+                assert!(propagation.iter().all(Result::is_ok))
+            }
+            other => panic!("Expected the NewState to be approved, got: {:?}", other),
+        }
+
+        match harness
+            .leader_sentry
+            .received(&follower_id, "ApproveState")
+            .as_slice()
+        {
+            [MessageTypes::ApproveState(approve_state)] => {
+                assert_eq!(new_state.state_root, approve_state.state_root);
+                assert!(approve_state.is_healthy);
+            }
+            other => panic!("Expected a single ApproveState, got: {:?}", other),
+        }
+
+        // no new event aggregates and the NewState is already approved
+        let leader_tick = leader::tick(&harness.leader)
+            .await
+            .expect("Leader tick should succeed");
+        assert!(leader_tick.new_state.is_none());
+        assert!(matches!(
+            leader_tick.producer_tick,
+            producer::TickStatus::NoNewEventAggr(_)
+        ));
+
+        let follower_tick = follower::tick(&harness.follower)
+            .await
+            .expect("Follower tick should succeed");
+        assert!(matches!(
+            follower_tick.approve_state,
+            ApproveStateResult::Sent(None)
+        ));
+        assert_eq!(
+            1,
+            harness
+                .leader_sentry
+                .received(&follower_id, "ApproveState")
+                .len()
+        );
+    }
+
+    #[tokio::test]
+    async fn follower_rejects_a_bad_new_state() {
+        let harness = Harness::setup(vec![impressions_aggregate(Utc::now())]).await;
+        let (leader_id, follower_id) = (IDS["leader"], IDS["follower"]);
+
+        leader::tick(&harness.leader)
+            .await
+            .expect("Leader tick should succeed");
+
+        let new_state = match harness
+            .follower_sentry
+            .received(&leader_id, "NewState")
+            .pop()
+        {
+            Some(MessageTypes::NewState(new_state)) => new_state,
+            other => panic!("Expected a NewState, got: {:?}", other),
+        };
+
+        // tamper with the balances, without changing the signed state root
+        let mut balances = new_state.balances.clone();
+        balances.insert(IDS["publisher2"], 1.into());
+        harness.follower_sentry.insert(
+            leader_id,
+            MessageTypes::NewState(NewState {
+                balances,
+                ..new_state
+            }),
+        );
+
+        let follower_tick = follower::tick(&harness.follower)
+            .await
+            .expect("Follower tick should succeed");
+        match follower_tick.approve_state {
+            ApproveStateResult::RejectedState { reason, .. } => {
+                assert!(matches!(reason, InvalidNewState::RootHash))
+            }
+            other => panic!("Expected the NewState to be rejected, got: {:?}", other),
+        }
+
+        assert!(harness
+            .leader_sentry
+            .received(&follower_id, "ApproveState")
+            .is_empty());
+        assert_eq!(
+            1,
+            harness
+                .leader_sentry
+                .received(&follower_id, "RejectState")
+                .len()
+        );
+    }
+}